@@ -0,0 +1,97 @@
+//! Markers left in the minimized code where large parts of the original were removed.
+//!
+//! The minimized code is diffed against the original formatted the same way, and a comment
+//! giving the number of removed lines is inserted at every site where more than a few lines of
+//! the original are gone. Comments are dropped by the parser, so the markers cannot change what
+//! the code means, unless one ends up inside a multi-line literal. The code is left unannotated
+//! in that case.
+use quote::ToTokens;
+
+use crate::report::{diff_lines, formatted};
+
+/// Minimum number of removed non-blank lines a site needs to be annotated.
+const MIN_ANNOTATED_LINES: usize = 3;
+
+/// Returns the minimized code with a marker at every site of a large deletion from the original.
+pub fn annotate(original_code: &str, minimized_code: &str) -> String {
+    let original = formatted(original_code);
+    let original_lines = original.lines().collect::<Vec<_>>();
+    let minimized_lines = minimized_code.lines().collect::<Vec<_>>();
+    let (original_kept, minimized_kept) = diff_lines(&original_lines, &minimized_lines);
+
+    let mut annotated = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < original_lines.len() || j < minimized_lines.len() {
+        let removed_start = i;
+        while i < original_lines.len() && !original_kept[i] {
+            i += 1;
+        }
+        let removed = &original_lines[removed_start..i];
+        let removed_count = removed
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .count();
+        if removed_count >= MIN_ANNOTATED_LINES {
+            let first_line = removed.iter().find(|line| !line.trim().is_empty());
+            let indentation = first_line
+                .map(|line| &line[..line.len() - line.trim_start().len()])
+                .unwrap_or_default();
+            annotated.push_str(&format!(
+                "{indentation}// [ddebug] {removed_count} lines removed here\n"
+            ));
+        }
+        // Lines rewritten by the passes, then the next line kept from the original.
+        while j < minimized_lines.len() && !minimized_kept[j] {
+            annotated.push_str(minimized_lines[j]);
+            annotated.push('\n');
+            j += 1;
+        }
+        if i < original_lines.len() && j < minimized_lines.len() {
+            annotated.push_str(minimized_lines[j]);
+            annotated.push('\n');
+            i += 1;
+            j += 1;
+        }
+    }
+
+    if same_tokens(&annotated, minimized_code) {
+        annotated
+    } else {
+        minimized_code.to_owned()
+    }
+}
+
+fn same_tokens(a: &str, b: &str) -> bool {
+    match (syn::parse_file(a), syn::parse_file(b)) {
+        (Ok(a), Ok(b)) => a.to_token_stream().to_string() == b.to_token_stream().to_string(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::annotate;
+
+    #[test]
+    fn annotate_large_deletions() {
+        let original = r#"
+fn main() {
+    let a = 1;
+    let b = 2;
+    let c = 3;
+    let d = 4;
+    let e = 5;
+}
+"#;
+        let minimized = "fn main() {\n    let a = 1;\n    let e = 5;\n}\n";
+
+        assert_eq!(
+            annotate(original, minimized),
+            "fn main() {\n    let a = 1;\n    // [ddebug] 3 lines removed here\n    let e = 5;\n}\n"
+        );
+        // Small deletions are not annotated.
+        let minimized =
+            "fn main() {\n    let a = 1;\n    let b = 2;\n    let c = 3;\n    let e = 5;\n}\n";
+        assert_eq!(annotate(original, minimized), minimized);
+    }
+}
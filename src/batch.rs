@@ -0,0 +1,268 @@
+//! Batch minimization of every crate of a directory.
+//!
+//! Each crate directly inside the directory is minimized in place with the same configuration,
+//! one after the other. A global time budget is shared by all of them: a crate started before the
+//! budget runs out stops testing variants when it does, the remaining ones are skipped. A report
+//! is written for every crate, together with a combined summary.
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+use crate::{
+    config::SearchConfig,
+    paths::portable,
+    searcher::{ASTGuidedSearcher, Search, Target},
+    toolchain::Toolchain,
+};
+
+/// Directory of the reports inside the batch directory, unless given otherwise.
+pub const DEFAULT_REPORTS_DIR: &str = "ddebug-reports";
+
+/// Name of the combined summary written next to the per-crate reports.
+const SUMMARY_FILE: &str = "summary.txt";
+
+#[derive(Error, Debug)]
+pub enum BatchError {
+    #[error("IO error emitted from batch: {0}")]
+    IOError(std::io::Error),
+}
+
+impl From<std::io::Error> for BatchError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IOError(value)
+    }
+}
+
+/// What happened to a crate of the batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CrateOutcome {
+    Minimized {
+        error: Option<String>,
+        code: String,
+        variants: usize,
+        toolchain: Toolchain,
+        /// Pinned requirements of the dependencies the code was verified against.
+        pinned_dependencies: Vec<String>,
+    },
+    /// The crate had nothing to minimize, e.g. it builds without errors.
+    NothingToMinimize,
+    Failed(String),
+    /// The time budget ran out before the crate was started.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrateReport {
+    pub name: String,
+    pub path: PathBuf,
+    pub outcome: CrateOutcome,
+    pub duration: Duration,
+}
+
+impl CrateReport {
+    fn status(&self) -> &'static str {
+        match self.outcome {
+            CrateOutcome::Minimized { .. } => "minimized",
+            CrateOutcome::NothingToMinimize => "nothing to minimize",
+            CrateOutcome::Failed(_) => "failed",
+            CrateOutcome::Skipped => "skipped",
+        }
+    }
+}
+
+impl std::fmt::Display for CrateReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "crate: {}", self.name)?;
+        writeln!(f, "path: {}", portable(&self.path))?;
+        writeln!(f, "status: {}", self.status())?;
+        writeln!(f, "duration: {:.1}s", self.duration.as_secs_f64())?;
+        match &self.outcome {
+            CrateOutcome::Minimized {
+                error,
+                code,
+                variants,
+                toolchain,
+                pinned_dependencies,
+            } => {
+                if let Some(error) = error {
+                    writeln!(f, "error: {error}")?;
+                }
+                writeln!(f, "variants: {variants}")?;
+                writeln!(f, "rustc: {}", toolchain.rustc_version())?;
+                writeln!(f, "cargo: {}", toolchain.cargo.trim_end())?;
+                if !pinned_dependencies.is_empty() {
+                    writeln!(f, "dependencies:")?;
+                    for requirement in pinned_dependencies {
+                        writeln!(f, "  {requirement}")?;
+                    }
+                }
+                writeln!(f)?;
+                write!(f, "{code}")?;
+            }
+            CrateOutcome::Failed(reason) => writeln!(f, "reason: {reason}")?,
+            CrateOutcome::NothingToMinimize | CrateOutcome::Skipped => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchReport {
+    pub crates: Vec<CrateReport>,
+}
+
+impl std::fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for report in &self.crates {
+            let detail = match &report.outcome {
+                CrateOutcome::Minimized {
+                    error, variants, ..
+                } => format!(
+                    "{} after {variants} variants",
+                    error.as_deref().unwrap_or("behavior kept")
+                ),
+                CrateOutcome::Failed(reason) => reason.clone(),
+                CrateOutcome::NothingToMinimize | CrateOutcome::Skipped => String::new(),
+            };
+            writeln!(
+                f,
+                "{:<24} {:<20} {:>7.1}s  {detail}",
+                report.name,
+                report.status(),
+                report.duration.as_secs_f64()
+            )?;
+        }
+        let minimized = self
+            .crates
+            .iter()
+            .filter(|report| matches!(report.outcome, CrateOutcome::Minimized { .. }))
+            .count();
+        writeln!(f, "{minimized} of {} crates minimized", self.crates.len())
+    }
+}
+
+pub struct Batch<'a> {
+    dir: &'a Path,
+    config: SearchConfig,
+    time_budget: Option<Duration>,
+}
+
+impl<'a> Batch<'a> {
+    /// Creates a batch over the crates of `dir`, minimized with `config` within `time_budget`.
+    pub fn new(dir: &'a Path, config: SearchConfig, time_budget: Option<Duration>) -> Self {
+        Self {
+            dir,
+            config,
+            time_budget,
+        }
+    }
+
+    /// Minimizes every crate and writes the per-crate reports and the summary into
+    /// `reports_dir`.
+    pub fn run(&self, reports_dir: &Path) -> Result<BatchReport, BatchError> {
+        let deadline = self
+            .time_budget
+            .map(|time_budget| Instant::now() + time_budget);
+        std::fs::create_dir_all(reports_dir)?;
+
+        let mut crates = vec![];
+        for crate_dir in crate_dirs(self.dir)? {
+            let name = crate_dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let started = Instant::now();
+            let outcome = if deadline.is_some_and(|deadline| started >= deadline) {
+                CrateOutcome::Skipped
+            } else {
+                println!("Minimizing {name}");
+                self.minimize(&crate_dir, deadline)
+            };
+            let report = CrateReport {
+                name,
+                path: crate_dir,
+                outcome,
+                duration: started.elapsed(),
+            };
+            std::fs::write(
+                reports_dir.join(format!("{}.txt", report.name)),
+                report.to_string(),
+            )?;
+            crates.push(report);
+        }
+
+        let report = BatchReport { crates };
+        std::fs::write(reports_dir.join(SUMMARY_FILE), report.to_string())?;
+        Ok(report)
+    }
+
+    fn minimize(&self, crate_dir: &Path, deadline: Option<Instant>) -> CrateOutcome {
+        let config = SearchConfig {
+            deadline,
+            ..self.config.clone()
+        };
+        match ASTGuidedSearcher::new(Target::Path(crate_dir), config).search() {
+            Ok(Some(minimized)) => CrateOutcome::Minimized {
+                error: minimized.master_error.map(|error| error.error_src),
+                code: minimized.code,
+                variants: minimized.variants,
+                toolchain: minimized.toolchain,
+                pinned_dependencies: minimized
+                    .locked_dependencies
+                    .iter()
+                    .map(|package| package.pinned_requirement())
+                    .collect(),
+            },
+            Ok(None) => CrateOutcome::NothingToMinimize,
+            Err(error) => CrateOutcome::Failed(error.to_string()),
+        }
+    }
+}
+
+/// Returns the directories of `dir` holding a `Cargo.toml`, sorted by name.
+fn crate_dirs(dir: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut crate_dirs = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.join("Cargo.toml").is_file() {
+            crate_dirs.push(path);
+        }
+    }
+    crate_dirs.sort();
+    Ok(crate_dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Batch, CrateOutcome};
+    use crate::{config::SearchConfig, test_dir::TestDir};
+
+    #[test]
+    fn batch_skips_crates_after_time_budget() {
+        let test_dir = TestDir::new("batch");
+        let dir = test_dir.path();
+        for name in ["a", "b"] {
+            std::fs::create_dir_all(dir.join(name).join("src")).unwrap();
+            std::fs::write(dir.join(name).join("Cargo.toml"), "").unwrap();
+        }
+        std::fs::create_dir_all(dir.join("not-a-crate")).unwrap();
+        let reports_dir = dir.join("reports");
+
+        let report = Batch::new(dir, SearchConfig::default(), Some(Duration::ZERO))
+            .run(&reports_dir)
+            .unwrap();
+
+        assert_eq!(report.crates.len(), 2);
+        assert!(report
+            .crates
+            .iter()
+            .all(|report| report.outcome == CrateOutcome::Skipped));
+        assert!(reports_dir.join("a.txt").exists());
+        assert!(reports_dir.join("summary.txt").exists());
+    }
+}
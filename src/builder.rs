@@ -1,13 +1,28 @@
 use std::{
-    io::Write,
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Read},
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
 };
 use thiserror::Error;
 
+use crate::{
+    manifest::{workspace_root, LibTarget},
+    paths::canonicalize,
+};
+
 /// A code builder. To detect error code.
 pub enum CodeBuilder<'a> {
     Path(&'a Path),
+    /// Only the integration test with the name, i.e. `tests/<name>.rs`, is built.
+    IntegrationTest(&'a Path, String),
+    /// Only the library is built, with all its crate types, so that the binaries using it do not
+    /// report errors of their own.
+    Lib(&'a Path),
+    /// The targets are built as tests, so that the errors of the integration tests are reported
+    /// too.
+    Tests(&'a Path),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -15,12 +30,96 @@ pub enum CodeBuilder<'a> {
 pub struct BuildError {
     pub error_code: Option<String>,
     pub source_file: Option<PathBuf>,
+    /// Line of the error location. It moves as the code is reduced, so it is not part of the
+    /// identity of the error, see [`BuildError::is_same_error`].
+    pub line: Option<usize>,
     pub error_src: String,
 }
 
+impl BuildError {
+    /// Returns true if both errors are the same error, wherever they are located in the file.
+    pub fn is_same_error(&self, other: &BuildError) -> bool {
+        self.error_code == other.error_code
+            && self.source_file == other.source_file
+            && self.error_src == other.error_src
+    }
+
+    /// Returns the error code, or the message if the error has no code.
+    pub fn short(&self) -> String {
+        self.error_code
+            .clone()
+            .unwrap_or_else(|| self.error_src.clone())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Represents a warning returned from running cargo build.
+pub struct BuildWarning {
+    /// Name of the lint that emitted the warning, only known if the warning is followed by a
+    /// `#[warn(..)]` note.
+    pub lint: Option<String>,
+    pub source_file: Option<PathBuf>,
+    pub warning_src: String,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BuildErros {
     pub errors: Vec<BuildError>,
+    pub warnings: Vec<BuildWarning>,
+    /// Summary lines of the run, such as `error: could not compile ...`. These are not errors of
+    /// the code themselves.
+    pub summary: Vec<String>,
+}
+
+/// Prefixes of the error lines summarizing a failed build rather than reporting an error.
+pub const SUMMARY_ERROR_PREFIXES: &[&str] = &["error: could not compile", "error: aborting due to"];
+
+/// Prefix of the errors reported when linking the crate fails.
+const LINKER_ERROR_PREFIX: &str = "error: linking with";
+
+/// Problems reported by the linkers in the notes of a linker error, identifying it.
+const LINKER_PROBLEMS: &[&str] = &[
+    "undefined symbol",
+    "undefined reference to",
+    "multiple definition of",
+    "duplicate symbol",
+];
+
+/// Marker of the location `rust-lld` gives for the references to an undefined symbol, e.g.
+/// `>>> referenced by main.rs:8 (src/main.rs:8)`.
+const LINKER_REFERENCE_MARKER: &str = ">>> referenced by";
+
+/// Returns true if the line summarizes a build instead of reporting an error or a warning.
+pub fn is_summary_line(line: &str) -> bool {
+    let line = line.trim_start();
+    SUMMARY_ERROR_PREFIXES
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+        || (line.starts_with("warning: `") && line.contains(" generated "))
+}
+
+impl BuildErros {
+    /// Groups the errors pointing to a source file by that file, keeping their order.
+    pub fn by_source_file(&self) -> BTreeMap<&Path, Vec<&BuildError>> {
+        let mut groups: BTreeMap<&Path, Vec<&BuildError>> = BTreeMap::new();
+        for error in &self.errors {
+            if let Some(source_file) = &error.source_file {
+                groups.entry(source_file).or_default().push(error);
+            }
+        }
+        groups
+    }
+
+    /// Returns the number of errors reported by the summary lines, if there are any.
+    pub fn reported_error_count(&self) -> Option<usize> {
+        self.summary.iter().find_map(|line| {
+            let (_, count) = line.split_once("due to ")?;
+            match count.split_whitespace().next()? {
+                "previous" => Some(1),
+                count => count.parse().ok(),
+            }
+        })
+    }
 }
 
 #[derive(Error, Debug)]
@@ -35,6 +134,8 @@ pub enum CodeBuilderError {
     IOError(std::io::Error),
     #[error("Cargo output parse error: {0}")]
     CargoOutputParseError(ParseError),
+    #[error("Cargo did not report an executable for the target")]
+    ExecutableNotFound,
 }
 
 impl From<std::io::Error> for CodeBuilderError {
@@ -49,99 +150,443 @@ impl From<ParseError> for CodeBuilderError {
     }
 }
 
+/// Incremental parser of cargo's output, fed one line at a time as cargo prints it.
+#[derive(Debug, Default)]
+pub struct BuildErrosParser {
+    current_error: Option<BuildError>,
+    current_warning: Option<BuildWarning>,
+    errors: Vec<BuildError>,
+    warnings: Vec<BuildWarning>,
+    summary: Vec<String>,
+    /// True if the previous raw line was a diagnostic line, the line following it holds its
+    /// location.
+    keep_next_line: bool,
+}
+
+impl BuildErrosParser {
+    /// Feeds a raw line of cargo's stderr. Only the lines starting with `error` or `warning`
+    /// together with the line following them, the `aborting` lines and the lint notes of the
+    /// warnings are parsed, the rest are rendered snippets and notes.
+    pub fn feed_output_line(&mut self, line: &str) -> Result<(), ParseError> {
+        let lowercase = line.to_lowercase();
+        if std::mem::take(&mut self.keep_next_line) {
+            self.parse_line(line)
+        } else if lowercase.starts_with("error") || lowercase.starts_with("warning") {
+            self.keep_next_line = true;
+            self.parse_line(line)
+        } else if lowercase.contains("aborting")
+            || line.contains("#[warn(")
+            || self.in_linker_error()
+        {
+            self.parse_line(line)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Parses a line that is known to be relevant.
+    pub fn parse_line(&mut self, line: &str) -> Result<(), ParseError> {
+        let line = line.trim();
+        if is_summary_line(line) {
+            // Summary lines are metadata of the run, not errors.
+            self.summary.push(line.to_string());
+        } else if line.starts_with("error") || line.starts_with("warning") {
+            // We found a new diagnostic. The previous one did not have location information,
+            // keep it without a source file.
+            self.errors.extend(self.current_error.take());
+            self.warnings.extend(self.current_warning.take());
+
+            if line.starts_with("warning") {
+                self.current_warning = Some(BuildWarning {
+                    lint: None,
+                    source_file: None,
+                    warning_src: line.to_string(),
+                });
+                return Ok(());
+            }
+
+            // Check if we have an error code.
+            let error_code = line
+                .split('[')
+                .nth(1)
+                .and_then(|line| line.split(']').next())
+                .map(|code| code.to_string());
+            self.current_error = Some(BuildError {
+                error_code,
+                source_file: None,
+                line: None,
+                error_src: line.to_string(),
+            });
+        } else if line.starts_with("-->") {
+            // We found location information for the current diagnostic.
+            let loc_info = line.split('>').nth(1).map(|loc_info| loc_info.trim());
+            let mut loc_parts = loc_info
+                .into_iter()
+                .flat_map(|loc_info| loc_info.split(':'));
+            let path: Option<PathBuf> = loc_parts.next().map(|loc_info| loc_info.into());
+            let line = loc_parts.next().and_then(|line| line.parse().ok());
+
+            if let Some(mut warning) = self.current_warning.take() {
+                warning.source_file = path;
+                self.warnings.push(warning);
+                return Ok(());
+            }
+
+            // We should have a currently active error if not this is not a valid output for
+            // our tool.
+            let mut error = self
+                .current_error
+                .take()
+                .ok_or(ParseError::UnmatchedLocationInformation)?;
+            error.source_file = path;
+            error.line = line;
+            self.errors.push(error);
+        } else if self.in_linker_error() {
+            self.parse_linker_note(line);
+        } else if let Some(lint) = line
+            .split_once("#[warn(")
+            .and_then(|(_, rest)| rest.split_once(")]"))
+            .map(|(lint, _)| lint.to_string())
+        {
+            // The lint note follows the location of the warning it belongs to.
+            self.warnings.extend(self.current_warning.take());
+            if let Some(warning) = self.warnings.last_mut() {
+                warning.lint = Some(lint);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if the current diagnostic is a linker error, whose notes are parsed as well.
+    fn in_linker_error(&self) -> bool {
+        self.current_error
+            .as_ref()
+            .is_some_and(|error| error.error_src.starts_with(LINKER_ERROR_PREFIX))
+    }
+
+    /// Identifies the current linker error with the first problem its notes report, and locates
+    /// it at the first reference the linker gives.
+    fn parse_linker_note(&mut self, line: &str) {
+        let Some(error) = self.current_error.as_mut() else {
+            return;
+        };
+        let problem = LINKER_PROBLEMS
+            .iter()
+            .find_map(|problem| line.find(problem))
+            .map(|start| line[start..].trim_end_matches(['\'', '`']));
+        // Only the first problem is kept, the error then ends with it in parentheses.
+        if let Some(problem) = problem.filter(|_| !error.error_src.ends_with(')')) {
+            error.error_src = format!("{} ({problem})", error.error_src);
+        } else if let Some((_, reference)) = line.split_once(LINKER_REFERENCE_MARKER) {
+            let location = reference
+                .rsplit_once('(')
+                .and_then(|(_, location)| location.strip_suffix(')'))
+                .and_then(|location| location.rsplit_once(':'));
+            if let Some((path, line)) = location.filter(|_| error.source_file.is_none()) {
+                error.source_file = Some(path.into());
+                error.line = line.parse().ok();
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> BuildErros {
+        self.errors.extend(self.current_error);
+        self.warnings.extend(self.current_warning);
+        BuildErros {
+            errors: self.errors,
+            warnings: self.warnings,
+            summary: self.summary,
+        }
+    }
+}
+
 impl TryFrom<String> for BuildErros {
     type Error = ParseError;
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        let mut current_error = None;
-        let mut errors = vec![];
+        let mut parser = BuildErrosParser::default();
         for line in value.trim().lines() {
-            let line = line.trim();
-            if line.starts_with("error") {
-                // We found an error line.
-
-                // Check if we have an error code.
-                let error_code = line
-                    .split('[')
-                    .nth(1)
-                    .and_then(|line| line.split(']').next())
-                    .map(|code| code.to_string());
-                current_error = Some(BuildError {
-                    error_code,
-                    source_file: None,
-                    error_src: line.to_string(),
-                });
-            } else if line.trim().starts_with("-->") {
-                // We found location information for the current error.
-
-                // We should have a currently active error if not this is not a valid output for
-                // our tool.
-                let mut error = current_error
-                    .clone()
-                    .ok_or(ParseError::UnmatchedLocationInformation)?;
-
-                let loc_info = line.split('>').nth(1).map(|loc_info| loc_info.trim());
-                let path = loc_info
-                    .and_then(|loc_info| loc_info.split(':').next())
-                    .map(|loc_info| loc_info.into());
-                error.source_file = path;
-
-                errors.push(error);
-                current_error = None;
-            }
+            parser.parse_line(line)?;
         }
-        Ok(Self { errors })
+        Ok(parser.finish())
+    }
+}
+
+/// Output of a single `cargo build` invocation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildOutput {
+    pub success: bool,
+    pub stderr: String,
+    /// Diagnostics parsed from the stderr while cargo was running.
+    pub build_errors: BuildErros,
+}
+
+/// Output of executing the built program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// Exit code of the program, `None` if it was killed by a signal or timed out.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+}
+
+impl RunOutput {
+    /// Returns the stdout and stderr of the program together.
+    pub fn combined(&self) -> String {
+        format!("{}{}", self.stdout, self.stderr)
+    }
+}
+
+/// Returns the name of the integration test the file is the root of, i.e. `foo` for
+/// `tests/foo.rs` and `tests/foo/main.rs`.
+pub fn integration_test_name(file: &Path) -> Option<String> {
+    let components = file
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    match components.as_slice() {
+        ["tests", file_name] => file_name.strip_suffix(".rs").map(str::to_owned),
+        ["tests", name, "main.rs"] => Some((*name).to_owned()),
+        _ => None,
     }
 }
 
 impl<'a> CodeBuilder<'a> {
-    pub fn collect_errors(&'a self) -> Result<BuildErros, CodeBuilderError> {
+    /// Creates the builder of the crate target `root_file` belongs to, the integration test or
+    /// the library if it is the root of one.
+    pub fn for_root_file(path: &'a Path, root_file: &Path) -> Self {
+        if let Some(name) = integration_test_name(root_file) {
+            return CodeBuilder::IntegrationTest(path, name);
+        }
+        match LibTarget::load(path) {
+            Some(lib) if lib.path == root_file => CodeBuilder::Lib(path),
+            _ => CodeBuilder::Path(path),
+        }
+    }
+
+    fn path(&self) -> &Path {
         match self {
-            CodeBuilder::Path(src_code_path) => {
-                let build_output = execute_cargo_check_and_grep(src_code_path)?;
-                Ok(BuildErros::try_from(build_output)?)
-            }
+            CodeBuilder::Path(path)
+            | CodeBuilder::IntegrationTest(path, _)
+            | CodeBuilder::Lib(path)
+            | CodeBuilder::Tests(path) => path,
+        }
+    }
+
+    /// Arguments selecting the crate target to build.
+    fn target_args(&self) -> Vec<&str> {
+        match self {
+            CodeBuilder::Path(_) => vec![],
+            CodeBuilder::IntegrationTest(_, name) => vec!["--test", name],
+            CodeBuilder::Lib(_) => vec!["--lib"],
+            CodeBuilder::Tests(_) => vec!["--tests"],
         }
     }
+
+    /// Builds the target and returns the output of cargo.
+    pub fn build(&'a self) -> Result<BuildOutput, CodeBuilderError> {
+        execute_cargo_build(self.path(), &self.target_args())
+    }
+
+    /// Runs the executable of the built target, killing it if it does not exit within
+    /// `timeout`.
+    ///
+    /// The executable is run directly rather than through `cargo run` so that the replayed
+    /// compiler warnings do not end up in its stderr.
+    pub fn run(&'a self, timeout: Duration) -> Result<RunOutput, CodeBuilderError> {
+        let executable = find_executable(self.path(), &self.target_args())?
+            .ok_or(CodeBuilderError::ExecutableNotFound)?;
+        Ok(run_executable(&executable, self.path(), timeout)?)
+    }
+
+    pub fn collect_errors(&'a self) -> Result<BuildErros, CodeBuilderError> {
+        Ok(self.build()?.build_errors)
+    }
 }
 
-fn execute_cargo_check_and_grep(path: &Path) -> Result<String, std::io::Error> {
-    // Run `cargo build` and capture its output
-    let cargo_output = Command::new("cargo")
+fn execute_cargo_build(path: &Path, target_args: &[&str]) -> Result<BuildOutput, CodeBuilderError> {
+    // Run `cargo build` and parse its output as it is printed.
+    let mut child = Command::new("cargo")
         .current_dir(path)
         .arg("build")
+        .args(target_args)
+        .stdout(Stdio::null())
         .stderr(Stdio::piped())
-        .output()?;
+        .spawn()?;
 
-    // Prepare `ripgrep` command with the desired pattern
-    let grep_output = Command::new("rg")
+    let mut stderr = String::new();
+    let mut parser = BuildErrosParser::default();
+    if let Some(child_stderr) = child.stderr.take() {
+        for line in BufReader::new(child_stderr).lines() {
+            let line = line?;
+            parser.feed_output_line(&line)?;
+            stderr.push_str(&line);
+            stderr.push('\n');
+        }
+    }
+
+    let mut build_errors = parser.finish();
+    if let Some(package_dir) = package_dir_in_workspace(path) {
+        strip_package_dir(&mut build_errors, &package_dir);
+    }
+    Ok(BuildOutput {
+        success: child.wait()?.success(),
+        stderr,
+        build_errors,
+    })
+}
+
+/// Returns the directory of the package relative to the root of its workspace, `None` if it is
+/// the root itself or not part of a workspace.
+fn package_dir_in_workspace(path: &Path) -> Option<PathBuf> {
+    let package_dir = canonicalize(path).ok()?;
+    let relative = package_dir
+        .strip_prefix(workspace_root(&package_dir)?)
+        .ok()?;
+    (!relative.as_os_str().is_empty()).then(|| relative.to_path_buf())
+}
+
+/// Makes the paths of the diagnostics relative to the package, cargo reports them relative to the
+/// root of the workspace.
+fn strip_package_dir(build_errors: &mut BuildErros, package_dir: &Path) {
+    let strip = |source_file: &mut Option<PathBuf>| {
+        if let Some(relative) = source_file
+            .as_deref()
+            .and_then(|source_file| source_file.strip_prefix(package_dir).ok())
+        {
+            *source_file = Some(relative.to_path_buf());
+        }
+    };
+    for error in &mut build_errors.errors {
+        strip(&mut error.source_file);
+    }
+    for warning in &mut build_errors.warnings {
+        strip(&mut warning.source_file);
+    }
+}
+
+/// Asks cargo for the path of the executable produced for the target.
+fn find_executable(path: &Path, target_args: &[&str]) -> Result<Option<PathBuf>, std::io::Error> {
+    let cargo_output = Command::new("cargo")
         .current_dir(path)
-        .arg("-i")
-        .arg("--multiline")
-        .arg("(^error.*\\n.*)|(aborting)")
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()?;
+        .args(["build", "--quiet", "--message-format=json"])
+        .args(target_args)
+        .stderr(Stdio::null())
+        .output()?;
+    let stdout = String::from_utf8_lossy(&cargo_output.stdout);
+
+    const EXECUTABLE_KEY: &str = "\"executable\":\"";
+    let executable = stdout.lines().find_map(|line| {
+        let start = line.find(EXECUTABLE_KEY)? + EXECUTABLE_KEY.len();
+        let end = start + line[start..].find('"')?;
+        Some(PathBuf::from(line[start..end].replace("\\\\", "\\")))
+    });
+    Ok(executable)
+}
+
+/// Runs the executable inside `dir`, killing it after `timeout`.
+pub fn run_executable(
+    executable: &Path,
+    dir: &Path,
+    timeout: Duration,
+) -> Result<RunOutput, std::io::Error> {
+    let child = spawn_in_process_group(
+        Command::new(executable)
+            .current_dir(dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )?;
+    wait_with_timeout(child, timeout)
+}
+
+/// Spawns the command in a process group of its own, so that [`wait_with_timeout`] kills the
+/// processes it starts along with it, such as the test binaries run by `cargo test`.
+pub fn spawn_in_process_group(command: &mut Command) -> Result<Child, std::io::Error> {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    command.spawn()
+}
 
-    // Write cargo's output to `ripgrep`'s stdin
-    let mut grep_stdin = grep_output
-        .stdin
-        .as_ref()
-        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, "Failed to open rg stdin"))?;
-    grep_stdin.write_all(&cargo_output.stderr)?;
+/// Waits for the child to exit, killing it once the timeout passes. Its stdout and stderr must be
+/// piped. If it was spawned with [`spawn_in_process_group`], the processes it started are killed
+/// too, otherwise they would keep its pipes open and the wait would last as long as they do.
+pub fn wait_with_timeout(mut child: Child, timeout: Duration) -> Result<RunOutput, std::io::Error> {
+    // Pipes are drained on their own threads so that a chatty program cannot block on a full
+    // pipe buffer before the timeout.
+    let stdout = child.stdout.take().map(read_to_string_in_background);
+    let stderr = child.stderr.take().map(read_to_string_in_background);
 
-    // Collect the output from `ripgrep`
-    let grep_result = grep_output.wait_with_output()?;
+    let start = Instant::now();
+    let (exit_code, timed_out) = loop {
+        if let Some(status) = child.try_wait()? {
+            break (status.code(), false);
+        }
+        if start.elapsed() >= timeout {
+            kill_process_group(&mut child)?;
+            child.wait()?;
+            break (None, true);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+
+    let join = |handle: Option<std::thread::JoinHandle<String>>| {
+        handle
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default()
+    };
+    Ok(RunOutput {
+        stdout: join(stdout),
+        stderr: join(stderr),
+        exit_code,
+        timed_out,
+    })
+}
 
-    // Convert the output to a String and return it
-    String::from_utf8(grep_result.stdout)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+/// Kills the child and the processes of its process group, or only the child if it does not lead
+/// a process group.
+fn kill_process_group(child: &mut Child) -> Result<(), std::io::Error> {
+    let pid = child.id().to_string();
+    #[cfg(unix)]
+    let mut kill = {
+        let mut kill = Command::new("kill");
+        kill.args(["-KILL", "--", &format!("-{pid}")]);
+        kill
+    };
+    #[cfg(not(unix))]
+    let mut kill = {
+        let mut kill = Command::new("taskkill");
+        kill.args(["/T", "/F", "/PID", &pid]);
+        kill
+    };
+    let killed = kill
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !killed {
+        child.kill()?;
+    }
+    Ok(())
+}
+
+fn read_to_string_in_background<R: Read + Send + 'static>(
+    mut reader: R,
+) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut bytes = vec![];
+        let _ = reader.read_to_end(&mut bytes);
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
-    use super::{BuildError, BuildErros, CodeBuilder};
+    use super::{
+        integration_test_name, strip_package_dir, BuildError, BuildErros, BuildErrosParser,
+        CodeBuilder,
+    };
 
     #[test]
     fn test_parse_single_error_code() {
@@ -156,16 +601,48 @@ error: could not compile `test_project` (bin "test_project") due to previous err
         let expected_error = BuildError {
             error_code: Some("E0384".to_owned()),
             source_file: Some("test/test_project/src/main.rs".into()),
+            line: Some(4),
             error_src: "error[E0384]: cannot assign twice to immutable variable `a`".to_owned(),
         };
 
         let expected_build_errors = BuildErros {
             errors: vec![expected_error],
+            warnings: vec![],
+            summary: vec!["error: could not compile `test_project` (bin \"test_project\") due to previous error; 3 warnings emitted".to_owned()],
         };
 
         assert_eq!(expected_build_errors, build_errors);
     }
 
+    #[test]
+    fn test_parse_linker_error() {
+        let test_cargo_output = r#"
+error: linking with `cc` failed: exit status: 1
+  |
+  = note:  "cc" "-m64" "/tmp/a/target/debug/deps/rustcupqWjj/symbols.o" "-nodefaultlibs"
+  = note: some arguments are omitted. use `--verbose` to show all linker arguments
+  = note: rust-lld: error: undefined symbol: missing_symbol
+          >>> referenced by main.rs:8 (src/main.rs:8)
+          >>>               /tmp/a/target/debug/deps/a-eb93.rcgu.o:(entry)
+          collect2: error: ld returned 1 exit status
+error: could not compile `a` (bin "a") due to 1 previous error
+"#;
+        let mut parser = BuildErrosParser::default();
+        for line in test_cargo_output.lines() {
+            parser.feed_output_line(line).unwrap();
+        }
+
+        assert_eq!(
+            parser.finish().errors,
+            vec![BuildError {
+                error_code: None,
+                source_file: Some("src/main.rs".into()),
+                line: Some(8),
+                error_src: "error: linking with `cc` failed: exit status: 1 (undefined symbol: missing_symbol)".to_owned(),
+            }]
+        );
+    }
+
     #[test]
     fn test_collect_errors_test_project() {
         let project_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -179,13 +656,176 @@ error: could not compile `test_project` (bin "test_project") due to previous err
         let expected_error = BuildError {
             error_code: Some("E0384".to_owned()),
             source_file: Some("src/main.rs".into()),
-            error_src: "error[E0384]: cannot assign twice to immutable variable `a`".to_owned(),
+            line: Some(5),
+            error_src: "error[E0384]: cannot assign twice to immutable variable `b`".to_owned(),
         };
 
-        let expected_build_errors = BuildErros {
-            errors: vec![expected_error],
-        };
+        assert_eq!(errors.errors, vec![expected_error]);
+        assert_eq!(errors.reported_error_count(), Some(1));
+    }
+
+    #[test]
+    fn test_strip_package_dir() {
+        let mut build_errors = BuildErros::try_from(
+            "error[E0384]: cannot assign twice to immutable variable `b`\n --> member/src/main.rs:5:5\nerror[E0425]: cannot find value `c` in this scope\n --> other/src/lib.rs:2:5".to_owned(),
+        )
+        .unwrap();
+        strip_package_dir(&mut build_errors, Path::new("member"));
+
+        let source_files = build_errors
+            .errors
+            .iter()
+            .map(|error| error.source_file.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            source_files,
+            vec![
+                Some(Path::new("src/main.rs")),
+                Some(Path::new("other/src/lib.rs"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_integration_test_name() {
+        assert_eq!(
+            integration_test_name(Path::new("tests/foo.rs")).as_deref(),
+            Some("foo")
+        );
+        assert_eq!(
+            integration_test_name(Path::new("tests/foo/main.rs")).as_deref(),
+            Some("foo")
+        );
+        assert_eq!(integration_test_name(Path::new("tests/foo/util.rs")), None);
+        assert_eq!(integration_test_name(Path::new("src/main.rs")), None);
+    }
+
+    #[test]
+    fn test_parse_summary_and_unlocated_errors() {
+        let test_cargo_output = r#"
+error: linking with `cc` failed: exit status: 1
+error[E0425]: cannot find value `x` in this scope
+ --> src/main.rs:2:5
+error: aborting due to 2 previous errors
+error: could not compile `test_project` (bin "test_project") due to 2 previous errors
+"#;
+
+        let build_errors = BuildErros::try_from(test_cargo_output.to_string()).unwrap();
+
+        assert_eq!(build_errors.errors.len(), 2);
+        assert_eq!(build_errors.errors[0].source_file, None);
+        assert_eq!(
+            build_errors.errors[1].source_file,
+            Some("src/main.rs".into())
+        );
+        assert_eq!(build_errors.summary.len(), 2);
+        assert_eq!(build_errors.reported_error_count(), Some(2));
+    }
+
+    #[test]
+    fn test_parse_warnings() {
+        let test_cargo_output = r#"
+warning: variable does not need to be mutable
+ --> src/main.rs:2:9
+  = note: `#[warn(unused_mut)]` on by default
+warning: unused variable: `a`
+ --> src/main.rs:2:13
+error[E0384]: cannot assign twice to immutable variable `b`
+ --> src/main.rs:4:5
+warning: `test_project` (bin "test_project") generated 2 warnings
+"#;
+
+        let build_errors = BuildErros::try_from(test_cargo_output.to_string()).unwrap();
+
+        assert_eq!(build_errors.errors.len(), 1);
+        assert_eq!(build_errors.warnings.len(), 2);
+        assert_eq!(build_errors.warnings[0].lint.as_deref(), Some("unused_mut"));
+        assert_eq!(
+            build_errors.warnings[1].source_file,
+            Some("src/main.rs".into())
+        );
+        assert_eq!(build_errors.warnings[1].lint, None);
+        assert_eq!(build_errors.summary.len(), 1);
+    }
+
+    #[test]
+    fn test_feed_raw_output_lines() {
+        let raw_output = r#"   Compiling test_project v0.1.0
+error[E0382]: borrow of moved value: `v`
+ --> src/main.rs:4:20
+  |
+2 |     let v = vec![1];
+  |         - move occurs because `v` has type `Vec<i32>`
+note: consider changing this parameter type
+ --> src/main.rs:7:9
+error: could not compile `test_project` (bin "test_project") due to 1 previous error"#;
+
+        let mut parser = BuildErrosParser::default();
+        for line in raw_output.lines() {
+            parser.feed_output_line(line).unwrap();
+        }
+        let build_errors = parser.finish();
+
+        assert_eq!(build_errors.errors.len(), 1);
+        assert_eq!(build_errors.errors[0].error_code.as_deref(), Some("E0382"));
+        assert_eq!(build_errors.reported_error_count(), Some(1));
+    }
+
+    #[test]
+    fn test_group_errors_by_source_file() {
+        let test_cargo_output = r#"
+error[E0384]: cannot assign twice to immutable variable `a`
+ --> src/foo.rs:4:5
+error[E0425]: cannot find value `x` in this scope
+ --> src/main.rs:2:5
+error[E0308]: mismatched types
+ --> src/foo.rs:9:13
+"#;
+
+        let build_errors = BuildErros::try_from(test_cargo_output.to_string()).unwrap();
+        let groups = build_errors.by_source_file();
+
+        assert_eq!(groups.len(), 2);
+        let foo_errors = &groups[Path::new("src/foo.rs")];
+        assert_eq!(foo_errors.len(), 2);
+        assert_eq!(foo_errors[0].error_code.as_deref(), Some("E0384"));
+        assert_eq!(foo_errors[1].error_code.as_deref(), Some("E0308"));
+        assert_eq!(groups[Path::new("src/main.rs")].len(), 1);
+    }
+
+    #[test]
+    fn test_run_timeout_kills_program() {
+        let child = std::process::Command::new("sleep")
+            .arg("10")
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let run_output =
+            super::wait_with_timeout(child, std::time::Duration::from_millis(100)).unwrap();
+
+        assert!(run_output.timed_out);
+        assert_eq!(run_output.exit_code, None);
+    }
+
+    #[test]
+    fn test_run_timeout_kills_process_group() {
+        // The shell waits for `sleep`, which inherits its pipes and outlives it if only the shell
+        // is killed.
+        let child = super::spawn_in_process_group(
+            std::process::Command::new("sh")
+                .args(["-c", "sleep 10; true"])
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped()),
+        )
+        .unwrap();
+
+        let start = std::time::Instant::now();
+        let run_output =
+            super::wait_with_timeout(child, std::time::Duration::from_millis(100)).unwrap();
 
-        assert_eq!(errors, expected_build_errors)
+        assert!(run_output.timed_out);
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
     }
 }
@@ -0,0 +1,45 @@
+//! Cancellation of a running search, for the applications embedding the searcher such as editors
+//! and services.
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Token stopping a search at its next safe point once cancelled, i.e. before it tests another
+/// variant. The search then writes the minimized file back in its last accepted state and
+/// returns it as its result.
+///
+/// Clones share their state, so that the search can be cancelled from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CancelToken;
+
+    #[test]
+    fn clones_share_cancellation() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+
+        std::thread::spawn(move || token.cancel()).join().unwrap();
+        assert!(clone.is_cancelled());
+    }
+}
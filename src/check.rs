@@ -0,0 +1,353 @@
+//! Quick answer to whether a region of a file is relevant to the error, for editor integrations.
+//!
+//! The nodes covered by the region are removed together in a scratch copy of the target: if the
+//! first error of the file is still reported, the region is removable. Otherwise the nodes
+//! enclosing the region are tried from the innermost one outwards, within a small build budget, to
+//! find the smallest removable region around it. The target itself is never modified.
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use petgraph::{graph::NodeIndex, visit::Bfs, Direction};
+use proc_macro2::LineColumn;
+use syn::visit::Visit;
+use thiserror::Error;
+
+use crate::{
+    builder::{BuildError, CodeBuilder, CodeBuilderError},
+    generator::{CodeGenerator, CodeGeneratorError},
+    graph::GraphBuilder,
+    oracle::{Oracle, SameErrorOracle, VariantOutcome},
+    parser::{AstNode, ItemSummary},
+    printer::{EmitMode, Printer},
+    remover::NodeRemover,
+    workspace::ScratchWorkspace,
+};
+
+/// Default number of builds spent on a check, the initial one included.
+pub const DEFAULT_CHECK_BUDGET: usize = 8;
+
+#[derive(Error, Debug)]
+pub enum CheckError {
+    #[error("Error while trying to build code variant: {0}")]
+    BuildOperationError(CodeBuilderError),
+    #[error("Error while generating code variant: {0}")]
+    CodeGenerationError(CodeGeneratorError),
+    #[error("Cannot parse the checked file: {0}")]
+    ParseError(syn::Error),
+    #[error("No error is reported in the checked file: {0}")]
+    NoErrorInFile(PathBuf),
+    #[error("IO error emitted from check: {0}")]
+    IOError(std::io::Error),
+}
+
+impl From<CodeBuilderError> for CheckError {
+    fn from(value: CodeBuilderError) -> Self {
+        Self::BuildOperationError(value)
+    }
+}
+
+impl From<CodeGeneratorError> for CheckError {
+    fn from(value: CodeGeneratorError) -> Self {
+        Self::CodeGenerationError(value)
+    }
+}
+
+impl From<syn::Error> for CheckError {
+    fn from(value: syn::Error) -> Self {
+        Self::ParseError(value)
+    }
+}
+
+impl From<std::io::Error> for CheckError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IOError(value)
+    }
+}
+
+/// A region of a file, written `LINE[:COLUMN]-LINE[:COLUMN]` or `LINE` on the command line, with
+/// lines and columns starting at 1. A region without columns spans its lines entirely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    start: LineColumn,
+    end: LineColumn,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid region `{0}`, expected `LINE[:COLUMN]-LINE[:COLUMN]`")]
+pub struct InvalidRegion(String);
+
+impl FromStr for Region {
+    type Err = InvalidRegion;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidRegion(s.to_owned());
+        // Spans end after their last character, which is the inclusive end column counted from 1.
+        let position = |position: &str, default_column: usize, offset: usize| {
+            let (line, column) = match position.split_once(':') {
+                Some((line, column)) => (line, Some(column)),
+                None => (position, None),
+            };
+            let line = line.trim().parse::<usize>().ok().filter(|line| *line > 0)?;
+            let column = match column {
+                Some(column) => column.trim().parse::<usize>().ok()?.checked_sub(offset)?,
+                None => default_column,
+            };
+            Some(LineColumn { line, column })
+        };
+        let (start, end) = s.split_once('-').unwrap_or((s, s));
+        let region = Region {
+            start: position(start, 0, 1).ok_or_else(invalid)?,
+            end: position(end, usize::MAX, 0).ok_or_else(invalid)?,
+        };
+        (region.start <= region.end)
+            .then_some(region)
+            .ok_or_else(invalid)
+    }
+}
+
+impl Region {
+    fn contains(&self, start: LineColumn, end: LineColumn) -> bool {
+        self.start <= start && end <= self.end
+    }
+
+    fn is_inside(&self, start: LineColumn, end: LineColumn) -> bool {
+        start <= self.start && self.end <= end
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckReport {
+    pub error: BuildError,
+    /// Whether the nodes covered by the region can be removed together, `None` if the region
+    /// covers no node.
+    pub removable: Option<bool>,
+    /// Smallest removable region enclosing the checked one, the covered nodes if they are
+    /// removable.
+    pub smallest_removable: Option<ItemSummary>,
+    pub builds: usize,
+    pub budget_exhausted: bool,
+}
+
+impl std::fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "error: {}", self.error.error_src)?;
+        let removable = match self.removable {
+            Some(true) => "yes",
+            Some(false) => "no",
+            None => "no node in the region",
+        };
+        writeln!(f, "removable: {removable}")?;
+        match &self.smallest_removable {
+            Some(region) => writeln!(f, "smallest removable region: {region}")?,
+            None if self.budget_exhausted => {
+                writeln!(f, "smallest removable region: none within the budget")?
+            }
+            None => writeln!(f, "smallest removable region: none")?,
+        }
+        writeln!(f, "builds: {}", self.builds)
+    }
+}
+
+pub struct RegionCheck<'a> {
+    target: &'a Path,
+    file: &'a Path,
+    region: Region,
+    budget: usize,
+}
+
+impl<'a> RegionCheck<'a> {
+    /// Creates the check of `region` in `file`, relative to `target`, spending at most `budget`
+    /// builds.
+    pub fn new(target: &'a Path, file: &'a Path, region: Region, budget: usize) -> Self {
+        Self {
+            target,
+            file,
+            region,
+            budget: budget.max(1),
+        }
+    }
+
+    pub fn run(&self) -> Result<CheckReport, CheckError> {
+        let workspace = ScratchWorkspace::copy_of(self.target, "check")?;
+        let code_builder = CodeBuilder::for_root_file(workspace.path(), self.file);
+        let error = code_builder
+            .collect_errors()?
+            .errors
+            .into_iter()
+            .find(|error| error.source_file.as_deref() == Some(self.file))
+            .ok_or_else(|| CheckError::NoErrorInFile(self.file.to_path_buf()))?;
+        let oracle = SameErrorOracle::new(error.clone());
+        let file_path = workspace.path().join(self.file);
+        let source = std::fs::read_to_string(&file_path)?;
+        let file = syn::parse_file(&source)?;
+
+        let mut graph_builder = GraphBuilder::new();
+        graph_builder.visit_file(&file);
+        let Some(root) = graph_builder.root_node() else {
+            return Err(CodeGeneratorError::RootNodeMissingInSyntaxTree.into());
+        };
+        let graph = graph_builder.graph().clone();
+        let lines = |node: NodeIndex| {
+            let span = graph[node].span();
+            (span.start(), span.end())
+        };
+
+        // The outermost nodes inside the region, their descendants go with them.
+        let mut covered = vec![];
+        let mut bfs = Bfs::new(&graph, root);
+        while let Some(node) = bfs.next(&graph) {
+            let (start, end) = lines(node);
+            let parent_covered =
+                graph
+                    .neighbors_directed(node, Direction::Incoming)
+                    .any(|parent| {
+                        covered.contains(&parent) || {
+                            let (start, end) = lines(parent);
+                            parent != root && self.region.contains(start, end)
+                        }
+                    });
+            if node != root && self.region.contains(start, end) && !parent_covered {
+                covered.push(node);
+            }
+        }
+        // The nodes around the region, from the innermost one.
+        let mut enclosing = graph
+            .node_indices()
+            .filter(|node| *node != root)
+            .filter(|node| {
+                let (start, end) = lines(*node);
+                self.region.is_inside(start, end) && !covered.contains(node)
+            })
+            .collect::<Vec<_>>();
+        enclosing.sort_by_key(|node| std::cmp::Reverse(lines(*node).0));
+
+        let code_generator = CodeGenerator::new();
+        let printer = Printer::new(EmitMode::Verbatim, false);
+        let removable_without = |nodes: &[NodeIndex]| -> Result<bool, CheckError> {
+            let mut variant = graph.clone();
+            for node in nodes {
+                NodeRemover::remove_node(&mut variant, *node);
+            }
+            let code = printer.print(&source, &code_generator.generate(&variant, root)?);
+            std::fs::write(&file_path, code)?;
+            let outcome = VariantOutcome {
+                build_errors: code_builder.collect_errors()?,
+                run_output: None,
+            };
+            Ok(oracle.is_interesting(&outcome))
+        };
+
+        let summary = |nodes: &[NodeIndex]| {
+            let description = match nodes {
+                [node] => describe(&graph[*node]),
+                nodes => format!("{} nodes", nodes.len()),
+            };
+            ItemSummary {
+                description,
+                start_line: nodes
+                    .iter()
+                    .map(|node| lines(*node).0.line)
+                    .min()
+                    .unwrap_or(0),
+                end_line: nodes
+                    .iter()
+                    .map(|node| lines(*node).1.line)
+                    .max()
+                    .unwrap_or(0),
+            }
+        };
+        let removable = (!covered.is_empty())
+            .then(|| removable_without(&covered))
+            .transpose()?;
+        let mut smallest_removable = removable
+            .filter(|removable| *removable)
+            .map(|_| summary(&covered));
+        // The initial build and the one of the covered nodes.
+        let mut builds = 1 + usize::from(removable.is_some());
+        let mut tried = 0;
+        for node in &enclosing {
+            if smallest_removable.is_some() || builds >= self.budget {
+                break;
+            }
+            builds += 1;
+            tried += 1;
+            if removable_without(&[*node])? {
+                smallest_removable = Some(summary(&[*node]));
+            }
+        }
+
+        Ok(CheckReport {
+            error,
+            removable,
+            budget_exhausted: smallest_removable.is_none() && tried < enclosing.len(),
+            smallest_removable,
+            builds,
+        })
+    }
+}
+
+/// Returns a short description of the node.
+fn describe(node: &AstNode<'_>) -> String {
+    match node {
+        AstNode::SourceRoot(_) => "file".to_owned(),
+        AstNode::Item(item) => ItemSummary::from(*item).description,
+        AstNode::ItemFn(item_fn) => format!("fn {}", item_fn.sig.ident),
+        AstNode::Block(_) => "block".to_owned(),
+        AstNode::LocalStmt(_) => "let statement".to_owned(),
+        AstNode::Arm(_) => "match arm".to_owned(),
+        AstNode::Attribute(_) => "attribute".to_owned(),
+        AstNode::Operand(_) => "operand".to_owned(),
+        AstNode::FieldValue(_) => "field".to_owned(),
+        _ => "expression".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{Region, RegionCheck};
+
+    #[test]
+    fn parse_regions() {
+        let region = "3:5-4:10".parse::<Region>().unwrap();
+        assert_eq!((region.start.line, region.start.column), (3, 4));
+        assert_eq!((region.end.line, region.end.column), (4, 10));
+
+        let line = "3".parse::<Region>().unwrap();
+        assert_eq!((line.start.column, line.end.column), (0, usize::MAX));
+        assert!("4-3".parse::<Region>().is_err());
+        assert!("0".parse::<Region>().is_err());
+    }
+
+    #[test]
+    fn check_test_project_regions() {
+        let project_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test")
+            .join("data")
+            .join("test_project");
+        let check = |region: &str| {
+            RegionCheck::new(
+                &project_dir,
+                Path::new("src/main.rs"),
+                region.parse().unwrap(),
+                4,
+            )
+            .run()
+            .unwrap()
+        };
+
+        // `let a = 0;` is not needed, `let b = 0;` is.
+        let report = check("3");
+        assert_eq!(report.removable, Some(true));
+        assert_eq!(
+            report.smallest_removable.unwrap().to_string(),
+            "let statement (lines 3-3)"
+        );
+        let report = check("2");
+        assert_eq!(report.removable, Some(false));
+        assert!(report.smallest_removable.is_none());
+    }
+}
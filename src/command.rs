@@ -1,12 +1,253 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+use crate::{
+    check::{Region, DEFAULT_CHECK_BUDGET},
+    completions::Shell,
+    config::DEFAULT_RUN_TIMEOUT_SECS,
+    oracle::OracleExpression,
+    parser::NodeKind,
+    pattern::OutputPattern,
+    printer::EmitMode,
+    profile::Profile,
+    report::ReportFormat,
+    session::parse_session_name,
+    triage::DEFAULT_TRIAGE_BUDGET,
+};
 
 /// A delta debugger tool for finding minimally reproducable versions of programs.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
     /// Path of the target project.
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub path: Option<PathBuf>,
+    /// Manifest of the target project, instead of its path. Paths reported by cargo are resolved
+    /// relative to the directory of the manifest, even for a member of a workspace.
+    #[arg(
+        long,
+        global = true,
+        value_name = "Cargo.toml",
+        conflicts_with = "path"
+    )]
+    pub manifest_path: Option<PathBuf>,
+    /// Directory to write the minimized code and its expected stderr as a UI test fixture.
+    #[arg(long, value_name = "DIR")]
+    pub emit_ui_test: Option<PathBuf>,
+    /// Source file to minimize, relative to the target, when errors are reported in several
+    /// files. Defaults to the file of the first error.
+    #[arg(long, value_name = "FILE")]
+    pub target_file: Option<PathBuf>,
+    /// Only check whether the region `LINE[:COLUMN]-LINE[:COLUMN]` of the target file can be
+    /// removed while keeping the first error, and print the smallest removable region enclosing
+    /// it. The target is not modified.
+    #[arg(long, value_name = "REGION", requires = "target_file")]
+    pub check_only: Option<Region>,
+    /// Number of builds spent by `--check-only`.
+    #[arg(long, value_name = "BUILDS", default_value_t = DEFAULT_CHECK_BUDGET, requires = "check_only")]
+    pub check_budget: usize,
+    /// Write a report of the minimization into `.ddebug/reports/` inside the target.
+    #[arg(long, value_name = "FORMAT")]
+    pub report: Option<ReportFormat>,
+    /// Continue the last minimization of the target from its last accepted reduction. Aborts if
+    /// the toolchain changed since.
+    #[arg(long)]
+    pub resume: bool,
+    /// Journal the minimization, and roll back or refine it, in the session named `NAME` instead
+    /// of the unnamed one, so that the minimizations of several errors of the target keep their
+    /// own state. See `ddebug sessions`.
+    #[arg(long, global = true, value_name = "NAME", value_parser = parse_session_name)]
+    pub session: Option<String>,
+    /// Keep the signatures of `pub` items intact so that dependent crates still compile against
+    /// the reproduction.
+    #[arg(long)]
+    pub preserve_pub_api: bool,
+    /// Kinds of nodes the reducer must not touch, such as `use,attrs,impl`.
+    #[arg(long, value_delimiter = ',', value_name = "KINDS")]
+    pub skip_kinds: Vec<NodeKind>,
+    /// Kinds of nodes the reducer is restricted to, such as `stmt,expr`.
+    #[arg(long, value_delimiter = ',', value_name = "KINDS")]
+    pub only_kinds: Vec<NodeKind>,
+    /// Insert `// [ddebug] N lines removed here` comments at the sites of large deletions in the
+    /// minimized code.
+    #[arg(long)]
+    pub annotate: bool,
+    /// How the variants are printed: formatted with prettyplease, copied verbatim from the source
+    /// with the removed code collapsed, or verbatim for the untouched items only. Use `verbatim`
+    /// for diagnostics that depend on the exact lines and columns.
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = EmitMode::Pretty)]
+    pub emit_mode: EmitMode,
+    /// Replace the removed code with blank lines and spaces instead of deleting it, keeping the
+    /// surviving code at its original lines and columns for position-sensitive bugs. Implies the
+    /// verbatim emit mode.
+    #[arg(long, conflicts_with_all = ["emit_mode", "annotate"])]
+    pub preserve_positions: bool,
+    /// Preset of the search options: `fast` for a quick single round, `thorough` to repeat the
+    /// reduction until nothing more is removed, `paranoid` to also verify every accepted variant
+    /// with a second build.
+    #[arg(long, value_enum, value_name = "PROFILE")]
+    pub profile: Option<Profile>,
+    /// Try removing every node, including the ones the error provably requires, such as `main` or
+    /// the declarations of the identifiers used on the error line, and the ones previous
+    /// minimizations of the file found required.
+    #[arg(long)]
+    pub no_prefilter: bool,
+    /// Reorder the remaining removals during the run, trying first the kinds of nodes whose
+    /// removals have been accepted the most so far.
+    #[arg(long)]
+    pub adaptive_order: bool,
+    /// Execute the variants instead of only building them.
+    #[arg(long, requires = "run_oracle")]
+    pub run: bool,
+    /// Output the executed program has to keep printing, either a string or a regular expression
+    /// between slashes (`/.../`).
+    #[arg(
+        long,
+        requires = "run",
+        group = "run_oracle",
+        value_name = "STRING_OR_REGEX"
+    )]
+    pub expect_output: Option<OutputPattern>,
+    /// Reference binary, e.g. the program built by another compiler, run inside the target like
+    /// the variants. The variants are kept while their stdout or exit code differ from the
+    /// reference's, to minimize miscompilations.
+    #[arg(long, requires = "run", group = "run_oracle", value_name = "BIN")]
+    pub reference: Option<PathBuf>,
+    /// Boolean expression deciding which variants are interesting, combining `error:<CODE>` and
+    /// `warning:<LINT>` atoms with `AND`, `OR`, `NOT` and parentheses.
+    #[arg(long, conflicts_with = "run_oracle", value_name = "EXPR")]
+    pub oracle: Option<OracleExpression>,
+    /// Input file read by the executed program, relative to the target. It is minimized after the
+    /// source, line by line and then byte by byte.
+    #[arg(long, requires = "run", value_name = "FILE")]
+    pub input: Option<PathBuf>,
+    /// Print the full compiler output of every variant instead of a one-line verdict.
+    #[arg(short, long)]
+    pub verbose: bool,
+    /// Explain why each rejected variant was rejected, e.g. the error changed from E0384 to
+    /// E0425, and summarize the reasons at the end. Useful when the code barely shrinks.
+    #[arg(long)]
+    pub explain_rejections: bool,
+    /// Keep the variants still reporting the preserved error after new unrelated ones, instead of
+    /// requiring it to stay the first error. Helps when the removals keep introducing errors.
+    #[arg(long, conflicts_with_all = ["run_oracle", "oracle"])]
+    pub allow_extra_errors: bool,
+    /// Write progress snapshots, such as the builds per minute, the acceptance rate and the code
+    /// size, to `.ddebug/metrics.csv` inside the target.
+    #[arg(long)]
+    pub metrics: bool,
+    /// Build a second time every accepted variant removing more than this percentage of the
+    /// remaining code, and keep it only if it is interesting again. Guards against an oracle
+    /// accepting a near-empty program.
+    #[arg(long, value_name = "PCT", value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub max_step_removal: Option<u8>,
+    /// Stop trying items once this many removals in a row are rejected, then statements, finally
+    /// moving on to the rewriting passes, instead of trying every node.
+    #[arg(long, value_name = "BUILDS", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub escalate_after: Option<usize>,
+    /// Keep an accepted reduction only if the test suite of the target, or the tests matching the
+    /// comma-separated filters, keep passing and failing as they did originally, so that the
+    /// minimized crate keeps working. The tests are given `--run-timeout` once built.
+    #[arg(
+        long,
+        value_name = "FILTERS",
+        num_args = 0..=1,
+        require_equals = true,
+        value_delimiter = ','
+    )]
+    pub guard_tests: Option<Vec<String>>,
+    /// Seconds an executed variant is given before it is killed.
+    #[arg(long, value_name = "SECS", default_value_t = DEFAULT_RUN_TIMEOUT_SECS)]
+    pub run_timeout: u64,
+}
+
+/// Subcommands of ddebug-rs. Without a subcommand the target is minimized.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Restore the minimized file to a checkpoint of the last minimization, or list the
+    /// checkpoints if none is given.
+    Rollback {
+        /// Index of the checkpoint to restore, 0 being the original file.
+        #[arg(long, value_name = "N")]
+        to: Option<usize>,
+    },
+    /// Minimize every crate of a directory with the same options, writing a report for each of
+    /// them and a combined summary.
+    Batch {
+        /// Directory holding the crates to minimize.
+        dir: PathBuf,
+        /// Seconds spent on the whole batch, crates not started in time are skipped.
+        #[arg(long, value_name = "SECS")]
+        time_budget: Option<u64>,
+        /// Directory to write the reports into, defaults to `ddebug-reports` inside the
+        /// directory.
+        #[arg(long, value_name = "DIR")]
+        reports: Option<PathBuf>,
+    },
+    /// Remove the session directory of the target, `.ddebug/`, with the journal, backups, reports
+    /// and logs of the last minimization.
+    Clean,
+    /// Quickly find a suspect region for every error of the target, without modifying it.
+    Triage {
+        /// Number of builds spent on each error.
+        #[arg(long, value_name = "BUILDS", default_value_t = DEFAULT_TRIAGE_BUDGET)]
+        budget: usize,
+        /// Number of errors triaged at the same time, defaults to the available parallelism.
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+    /// Re-verify the minimized file after editing it, e.g. renaming things for clarity, and try
+    /// to reduce it further around the edited lines only.
+    Refine,
+    /// Apply small mutations to the target, deleting statements, negating conditions and swapping
+    /// operators, and report the mutants its tests do not kill. Only `--target-file` is mutated
+    /// if given, and only the tests matching `--guard-tests` are run. The target is not modified.
+    Mutate,
+    /// Ask the minimization running on the target to stop before its next variant, keeping the
+    /// reductions accepted so far.
+    Stop,
+    /// Remove the code of the target its build and its tests do not need, file by file, and
+    /// report the removed lines. The tests kept passing can be restricted with `--guard-tests`.
+    Prune,
+    /// List, inspect, resume and delete the named sessions of the target, kept under
+    /// `.ddebug/sessions/`.
+    Sessions {
+        #[command(subcommand)]
+        command: SessionsCommand,
+    },
+    /// Print the completion script of the shell, e.g. `ddebug-rs completions bash >
+    /// /etc/bash_completion.d/ddebug-rs`.
+    Completions {
+        /// Shell to complete the commands of.
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page in roff, e.g. `ddebug-rs manpage > ddebug-rs.1`.
+    Manpage,
+}
+
+/// Subcommands of `ddebug sessions`.
+#[derive(Subcommand, Debug)]
+pub enum SessionsCommand {
+    /// List the named sessions of the target with their minimized file.
+    List,
+    /// Print the minimized file, the toolchain and the checkpoints of a session.
+    Show {
+        #[arg(value_parser = parse_session_name)]
+        name: String,
+    },
+    /// Continue the minimization of a session from its last accepted reduction, of the same file
+    /// and with the oracle and the options the session was started with.
+    Resume {
+        #[arg(value_parser = parse_session_name)]
+        name: String,
+    },
+    /// Delete a session with its journal, backups, reports and logs.
+    Delete {
+        #[arg(value_parser = parse_session_name)]
+        name: String,
+    },
 }
@@ -0,0 +1,307 @@
+//! Shell completion scripts, generated from the definition of the CLI.
+//!
+//! The scripts complete the subcommands, their options, and the values of the options taking a
+//! fixed set of values, such as `--emit-mode`. The values of the path options are completed as
+//! file paths.
+use clap::{Arg, Command, ValueHint};
+
+/// Shells a completion script can be generated for.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Returns the completion script of the command for the shell.
+pub fn completions(shell: Shell, command: &mut Command) -> String {
+    // Building the command propagates the global options and adds the help ones.
+    command.build();
+    match shell {
+        Shell::Bash => bash(command),
+        Shell::Zsh => zsh(command),
+        Shell::Fish => fish(command),
+    }
+}
+
+fn bash(command: &Command) -> String {
+    let name = command.get_name();
+    let function = format!("_{}", name.replace('-', "_"));
+    let subcommands = visible_subcommands(command)
+        .map(|subcommand| subcommand.get_name())
+        .collect::<Vec<_>>();
+
+    let mut script = format!(
+        "{function}() {{
+    local cur prev cmd opts
+    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"
+    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"
+    cmd=\"\"
+    for word in \"${{COMP_WORDS[@]:1:COMP_CWORD-1}}\"; do
+        case \"$word\" in
+            {}) cmd=\"$word\"; break ;;
+        esac
+    done
+    case \"$prev\" in\n",
+        subcommands.join("|")
+    );
+    let mut completed_values = vec![];
+    for arg in std::iter::once(command)
+        .chain(visible_subcommands(command))
+        .flat_map(visible_options)
+        .filter(|arg| arg.get_action().takes_values())
+    {
+        let flags = flags(arg).join("|");
+        if completed_values.contains(&flags) {
+            continue;
+        }
+        let values = possible_values(arg);
+        let reply = if !values.is_empty() {
+            format!("compgen -W \"{}\" -- \"$cur\"", values.join(" "))
+        } else if is_path(arg) {
+            "compgen -f -- \"$cur\"".to_owned()
+        } else {
+            "true".to_owned()
+        };
+        script.push_str(&format!(
+            "        {flags}) COMPREPLY=($({reply})); return ;;\n"
+        ));
+        completed_values.push(flags);
+    }
+    script.push_str("    esac\n    case \"$cmd\" in\n");
+    let words = |command: &Command, subcommands: &[&str]| {
+        let mut words = subcommands
+            .iter()
+            .map(|subcommand| subcommand.to_string())
+            .collect::<Vec<_>>();
+        words.extend(visible_options(command).flat_map(flags));
+        words.join(" ")
+    };
+    script.push_str(&format!(
+        "        \"\") opts=\"{}\" ;;\n",
+        words(command, &subcommands)
+    ));
+    for subcommand in visible_subcommands(command) {
+        script.push_str(&format!(
+            "        {}) opts=\"{}\" ;;\n",
+            subcommand.get_name(),
+            words(subcommand, &[])
+        ));
+    }
+    script.push_str(&format!(
+        "    esac
+    if [[ \"$cur\" == -* ]]; then
+        COMPREPLY=($(compgen -W \"$opts\" -- \"$cur\"))
+    else
+        COMPREPLY=($(compgen -W \"$opts\" -f -- \"$cur\"))
+    fi
+}}
+complete -F {function} {name}\n"
+    ));
+    script
+}
+
+fn zsh(command: &Command) -> String {
+    let name = command.get_name();
+    let function = format!("_{}", name.replace('-', "_"));
+    let mut script = format!("#compdef {name}\n\n{function}() {{\n    local line state\n");
+    script.push_str("    _arguments -C \\\n");
+    for spec in visible_options(command).flat_map(zsh_specs) {
+        script.push_str(&format!("        {spec} \\\n"));
+    }
+    script.push_str("        '1: :->commands' \\\n        '*::arg:->args'\n");
+    script.push_str("    case $state in\n        commands)\n            _values 'command' \\\n");
+    for subcommand in visible_subcommands(command) {
+        script.push_str(&format!(
+            "                '{}[{}]' \\\n",
+            subcommand.get_name(),
+            zsh_escape(&summary(subcommand.get_about().map(ToString::to_string)))
+        ));
+    }
+    script.push_str("            ;;\n        args)\n            case $line[1] in\n");
+    for subcommand in visible_subcommands(command) {
+        script.push_str(&format!(
+            "                {})\n                    _arguments \\\n",
+            subcommand.get_name()
+        ));
+        for spec in visible_options(subcommand).flat_map(zsh_specs) {
+            script.push_str(&format!("                        {spec} \\\n"));
+        }
+        for positional in subcommand.get_positionals() {
+            script.push_str(&format!(
+                "                        ':{}:{}' \\\n",
+                positional.get_id(),
+                zsh_values(positional)
+            ));
+        }
+        script.push_str("                    ;;\n");
+    }
+    script.push_str(&format!(
+        "            esac\n            ;;\n    esac\n}}\n\n{function} \"$@\"\n"
+    ));
+    script
+}
+
+/// Returns the `_arguments` specs of an option, one per flag.
+fn zsh_specs(arg: &Arg) -> Vec<String> {
+    let description = zsh_escape(&summary(arg.get_help().map(ToString::to_string)));
+    let value = if arg.get_action().takes_values() {
+        format!(":{}:{}", value_name(arg), zsh_values(arg))
+    } else {
+        String::new()
+    };
+    flags(arg)
+        .into_iter()
+        .map(|flag| format!("'{flag}[{description}]{value}'"))
+        .collect()
+}
+
+fn zsh_values(arg: &Arg) -> String {
+    let values = possible_values(arg);
+    if !values.is_empty() {
+        format!("({})", values.join(" "))
+    } else if is_path(arg) {
+        "_files".to_owned()
+    } else {
+        " ".to_owned()
+    }
+}
+
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn fish(command: &Command) -> String {
+    let name = command.get_name();
+    let mut script = String::new();
+    let top_level = "__fish_use_subcommand";
+    for subcommand in visible_subcommands(command) {
+        script.push_str(&format!(
+            "complete -c {name} -n \"{top_level}\" -f -a \"{}\" -d '{}'\n",
+            subcommand.get_name(),
+            fish_escape(&summary(subcommand.get_about().map(ToString::to_string)))
+        ));
+    }
+    for arg in visible_options(command) {
+        script.push_str(&fish_option(name, top_level, arg));
+    }
+    for subcommand in visible_subcommands(command) {
+        let condition = format!("__fish_seen_subcommand_from {}", subcommand.get_name());
+        for arg in visible_options(subcommand) {
+            script.push_str(&fish_option(name, &condition, arg));
+        }
+    }
+    script
+}
+
+fn fish_option(name: &str, condition: &str, arg: &Arg) -> String {
+    let mut line = format!("complete -c {name} -n \"{condition}\"");
+    if let Some(short) = arg.get_short() {
+        line.push_str(&format!(" -s {short}"));
+    }
+    if let Some(long) = arg.get_long() {
+        line.push_str(&format!(" -l {long}"));
+    }
+    if arg.get_action().takes_values() {
+        line.push_str(" -r");
+        let values = possible_values(arg);
+        if !values.is_empty() {
+            line.push_str(&format!(" -f -a \"{}\"", values.join(" ")));
+        } else if !is_path(arg) {
+            line.push_str(" -f");
+        }
+    }
+    line.push_str(&format!(
+        " -d '{}'\n",
+        fish_escape(&summary(arg.get_help().map(ToString::to_string)))
+    ));
+    line
+}
+
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn visible_subcommands(command: &Command) -> impl Iterator<Item = &Command> {
+    command
+        .get_subcommands()
+        .filter(|subcommand| !subcommand.is_hide_set())
+}
+
+fn visible_options(command: &Command) -> impl Iterator<Item = &Arg> {
+    command
+        .get_arguments()
+        .filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+}
+
+/// Returns the flags of an option, e.g. `-p` and `--path`.
+pub fn flags(arg: &Arg) -> Vec<String> {
+    let short = arg.get_short().map(|short| format!("-{short}"));
+    let long = arg.get_long().map(|long| format!("--{long}"));
+    short.into_iter().chain(long).collect()
+}
+
+pub fn possible_values(arg: &Arg) -> Vec<String> {
+    arg.get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_owned())
+        .collect()
+}
+
+fn is_path(arg: &Arg) -> bool {
+    matches!(
+        arg.get_value_hint(),
+        ValueHint::AnyPath | ValueHint::FilePath | ValueHint::DirPath
+    )
+}
+
+pub fn value_name(arg: &Arg) -> String {
+    arg.get_value_names()
+        .and_then(|names| names.first())
+        .map(ToString::to_string)
+        .unwrap_or_else(|| arg.get_id().to_string().to_uppercase())
+}
+
+/// Returns the first sentence of a help text.
+fn summary(help: Option<String>) -> String {
+    let help = help.unwrap_or_default();
+    let first_line = help.lines().next().unwrap_or_default();
+    // A sentence ends at a period followed by a capitalized word, not at abbreviations like
+    // `e.g.`.
+    let end = first_line
+        .match_indices(". ")
+        .map(|(ix, _)| ix)
+        .find(|ix| first_line[ix + 2..].starts_with(char::is_uppercase))
+        .unwrap_or(first_line.len());
+    first_line[..end].trim_end_matches('.').to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::{completions, Shell};
+    use crate::command::Args;
+
+    #[test]
+    fn completions_cover_subcommands_and_values() {
+        let bash = completions(Shell::Bash, &mut Args::command());
+        assert!(bash.contains("rollback|batch|clean|triage"));
+        assert!(bash.contains("--emit-mode) COMPREPLY=($(compgen -W \"pretty verbatim mixed\""));
+        assert!(bash.ends_with("complete -F _ddebug_rs ddebug-rs\n"));
+
+        let zsh = completions(Shell::Zsh, &mut Args::command());
+        assert!(zsh.starts_with("#compdef ddebug-rs\n"));
+        assert!(zsh
+            .contains("'--to[Index of the checkpoint to restore, 0 being the original file]:N: '"));
+
+        let fish = completions(Shell::Fish, &mut Args::command());
+        assert!(fish.contains(
+            "complete -c ddebug-rs -n \"__fish_seen_subcommand_from triage\" -s j -l jobs -r"
+        ));
+    }
+}
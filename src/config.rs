@@ -0,0 +1,227 @@
+//! Configuration of a search.
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    command::Args, oracle::OracleExpression, parser::NodeKind, pattern::OutputPattern,
+    printer::EmitMode,
+};
+
+/// Options controlling which variants the searcher is allowed to try.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchConfig {
+    /// Forbid removing or changing the signatures of `pub` items. Function bodies can still be
+    /// reduced.
+    pub preserve_pub_api: bool,
+    /// Kinds of nodes that are never removed.
+    pub skip_kinds: Vec<NodeKind>,
+    /// Kinds of nodes the removals are restricted to, any kind can be removed if empty.
+    pub only_kinds: Vec<NodeKind>,
+    /// Output the executed variant has to print to stay interesting. The variants are run only
+    /// if this is set.
+    pub expected_output: Option<OutputPattern>,
+    /// Reference binary the executed variant has to keep behaving differently from, run with
+    /// the same input. The variants are run if this is set.
+    pub reference: Option<PathBuf>,
+    /// Time an executed variant is given before it is killed.
+    pub run_timeout: Duration,
+    /// Print the full compiler output of every variant.
+    pub verbose: bool,
+    /// Explain why every rejected variant was rejected, and summarize the reasons at the end.
+    pub explain_rejections: bool,
+    /// Write progress snapshots to `.ddebug/metrics.csv` inside the target.
+    pub metrics: bool,
+    /// Keep the variants still reporting the preserved error after new ones, instead of
+    /// requiring it to stay the first error.
+    pub allow_extra_errors: bool,
+    /// Filters of the tests whose outcomes the accepted variants have to keep, the whole test
+    /// suite if empty, no test is run if not set.
+    pub guard_tests: Option<Vec<String>>,
+    /// Named session the minimization is journaled in, the unnamed one if not set.
+    pub session: Option<String>,
+    /// Continue the journal of the last minimization instead of starting a new one.
+    pub resume: bool,
+    /// Continue the journal of the last minimization from the file as edited since, only trying
+    /// the removals around the edited lines. The file is not stubbed nor rewritten.
+    pub refine: bool,
+    /// Remove the code the build and the tests of the target do not need, instead of minimizing
+    /// an error or a behavior.
+    pub prune: bool,
+    /// Time after which no more variants are tested, the search ends with the reductions
+    /// accepted so far.
+    pub deadline: Option<Instant>,
+    /// Input file of the executed program, relative to the target, minimized after the source.
+    pub input_file: Option<PathBuf>,
+    /// Expression deciding which variants are interesting, instead of keeping the first error.
+    pub oracle: Option<OracleExpression>,
+    /// File to minimize, relative to the target. The file of the first error is minimized if
+    /// this is not set.
+    pub target_file: Option<PathBuf>,
+    /// Try the kinds of nodes whose removals have been accepted the most during the run first.
+    pub adaptive_order: bool,
+    /// Mark the sites of large deletions in the minimized code with comments.
+    pub annotate: bool,
+    /// How the variants are printed.
+    pub emit_mode: EmitMode,
+    /// Keep the surviving code at its original lines and columns, blanking the removed code.
+    /// Implies the verbatim emit mode.
+    pub preserve_positions: bool,
+    /// Try stubbing every function body not containing the error at once before removing nodes.
+    pub stub_bodies: bool,
+    /// Run the rewriting passes after the removals.
+    pub run_passes: bool,
+    /// Number of rounds of removals and passes, repeated until a round accepts nothing if not
+    /// set.
+    pub max_rounds: Option<usize>,
+    /// Skip the removals of the nodes the error provably requires, e.g. the declarations of the
+    /// identifiers used on the error line, and of the nodes previous minimizations found required.
+    pub prefilter: bool,
+    /// Build every accepted variant a second time, and keep it only if it is interesting again.
+    pub verify_accepted: bool,
+    /// Number of rejected removals in a row after which the items are no longer tried, then the
+    /// statements, and finally the removals end. Every node is tried if not set.
+    pub escalate_after: Option<usize>,
+    /// Percentage of the remaining code an accepted variant can remove before it is built a
+    /// second time, and kept only if it is interesting again.
+    pub max_step_removal: Option<u8>,
+    /// Arguments the minimization was started with, saved in its journal so that resuming its
+    /// session reuses its oracle and options, see `ddebug sessions resume`.
+    pub arguments: Vec<String>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            preserve_pub_api: false,
+            skip_kinds: vec![],
+            only_kinds: vec![],
+            expected_output: None,
+            reference: None,
+            run_timeout: Duration::from_secs(DEFAULT_RUN_TIMEOUT_SECS),
+            verbose: false,
+            explain_rejections: false,
+            metrics: false,
+            allow_extra_errors: false,
+            guard_tests: None,
+            session: None,
+            resume: false,
+            refine: false,
+            prune: false,
+            deadline: None,
+            input_file: None,
+            oracle: None,
+            target_file: None,
+            adaptive_order: false,
+            annotate: false,
+            emit_mode: EmitMode::Pretty,
+            preserve_positions: false,
+            stub_bodies: true,
+            run_passes: true,
+            max_rounds: Some(1),
+            prefilter: true,
+            verify_accepted: false,
+            max_step_removal: None,
+            escalate_after: None,
+            arguments: vec![],
+        }
+    }
+}
+
+impl SearchConfig {
+    /// Returns the configuration refining the journaled file of the last minimization after it
+    /// was edited, in a single round of removals.
+    pub fn refining(self, source_file: &Path) -> Self {
+        Self {
+            refine: true,
+            resume: false,
+            target_file: Some(source_file.to_path_buf()),
+            stub_bodies: false,
+            run_passes: false,
+            max_rounds: Some(1),
+            ..self
+        }
+    }
+
+    /// Returns the configuration pruning the dead code of the source file, keeping the target
+    /// building and the outcomes of its tests, only by removals.
+    pub fn pruning(self, source_file: &Path) -> Self {
+        Self {
+            prune: true,
+            resume: false,
+            refine: false,
+            target_file: Some(source_file.to_path_buf()),
+            guard_tests: self.guard_tests.or(Some(vec![])),
+            run_passes: false,
+            ..self
+        }
+    }
+
+    /// Returns true if nodes of these kinds may be removed.
+    pub fn allows_kinds(&self, kinds: &[NodeKind]) -> bool {
+        let skipped = kinds.iter().any(|kind| self.skip_kinds.contains(kind));
+        let selected =
+            self.only_kinds.is_empty() || kinds.iter().any(|kind| self.only_kinds.contains(kind));
+        !skipped && selected
+    }
+}
+
+/// Stall threshold of the fast profile, see [`SearchConfig::escalate_after`].
+pub const DEFAULT_ESCALATE_AFTER: usize = 20;
+
+/// Default value of `--run-timeout`, in seconds.
+pub const DEFAULT_RUN_TIMEOUT_SECS: u64 = 10;
+
+impl From<&Args> for SearchConfig {
+    fn from(args: &Args) -> Self {
+        let mut config = Self {
+            preserve_pub_api: args.preserve_pub_api,
+            skip_kinds: args.skip_kinds.clone(),
+            only_kinds: args.only_kinds.clone(),
+            expected_output: args.expect_output.clone(),
+            // The reference is run inside the target, its path must not depend on it.
+            reference: args
+                .reference
+                .as_ref()
+                .map(|reference| std::path::absolute(reference).unwrap_or(reference.clone())),
+            run_timeout: Duration::from_secs(args.run_timeout),
+            verbose: args.verbose,
+            explain_rejections: args.explain_rejections,
+            metrics: args.metrics,
+            allow_extra_errors: args.allow_extra_errors,
+            guard_tests: args.guard_tests.clone(),
+            session: args.session.clone(),
+            resume: args.resume,
+            deadline: None,
+            input_file: args.input.clone(),
+            oracle: args.oracle.clone(),
+            target_file: args.target_file.as_ref().map(|target_file| {
+                target_file
+                    .strip_prefix(".")
+                    .unwrap_or(target_file)
+                    .to_path_buf()
+            }),
+            adaptive_order: args.adaptive_order,
+            annotate: args.annotate,
+            emit_mode: if args.preserve_positions {
+                EmitMode::Verbatim
+            } else {
+                args.emit_mode
+            },
+            preserve_positions: args.preserve_positions,
+            prefilter: !args.no_prefilter,
+            max_step_removal: args.max_step_removal,
+            escalate_after: args.escalate_after,
+            ..Self::default()
+        };
+        if let Some(profile) = args.profile {
+            profile.apply(&mut config);
+            // The flags given along with the profile still apply.
+            config.adaptive_order |= args.adaptive_order;
+            config.prefilter &= !args.no_prefilter;
+            config.escalate_after = args.escalate_after.or(config.escalate_after);
+        }
+        config
+    }
+}
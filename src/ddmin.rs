@@ -0,0 +1,105 @@
+//! Delta debugging minimization (ddmin) over a sequence of elements.
+//!
+//! The input is split into `n` chunks and the complement of each chunk is tested. If a complement
+//! is still interesting it becomes the new input, otherwise the granularity is doubled until
+//! every chunk is a single element.
+
+/// Outcome of a minimization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DdminResult<T> {
+    /// Elements that could not be removed, in their original order.
+    pub kept: Vec<T>,
+    /// Number of times the test was run.
+    pub tests: usize,
+    /// True if the minimization stopped because it ran out of budget rather than converging.
+    pub budget_exhausted: bool,
+}
+
+/// Minimizes `elements` to a subset for which `test` still returns true, running `test` at most
+/// `budget` times. `test` is assumed to hold for the whole input.
+pub fn ddmin<T: Clone, E>(
+    elements: &[T],
+    budget: Option<usize>,
+    mut test: impl FnMut(&[T]) -> Result<bool, E>,
+) -> Result<DdminResult<T>, E> {
+    let mut current = elements.to_vec();
+    let mut granularity = 2;
+    let mut tests = 0;
+
+    while current.len() >= 2 {
+        let chunk_size = current.len().div_ceil(granularity);
+        let chunk_count = current.len().div_ceil(chunk_size);
+
+        let mut reduced = false;
+        for chunk in 0..chunk_count {
+            if budget.is_some_and(|budget| tests >= budget) {
+                return Ok(DdminResult {
+                    kept: current,
+                    tests,
+                    budget_exhausted: true,
+                });
+            }
+
+            let complement = current
+                .iter()
+                .enumerate()
+                .filter(|(ix, _)| ix / chunk_size != chunk)
+                .map(|(_, element)| element.clone())
+                .collect::<Vec<_>>();
+            tests += 1;
+            if test(&complement)? {
+                current = complement;
+                granularity = (granularity - 1).max(2);
+                reduced = true;
+                break;
+            }
+        }
+
+        if !reduced {
+            if granularity >= current.len() {
+                break;
+            }
+            granularity = (granularity * 2).min(current.len());
+        }
+    }
+
+    Ok(DdminResult {
+        kept: current,
+        tests,
+        budget_exhausted: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::ddmin;
+
+    #[test]
+    fn ddmin_keeps_required_elements() {
+        let elements = (0..10).collect::<Vec<_>>();
+
+        let result = ddmin(&elements, None, |subset| {
+            Ok::<_, Infallible>(subset.contains(&3) && subset.contains(&7))
+        })
+        .unwrap();
+
+        assert_eq!(result.kept, vec![3, 7]);
+        assert!(!result.budget_exhausted);
+    }
+
+    #[test]
+    fn ddmin_stops_at_budget() {
+        let elements = (0..10).collect::<Vec<_>>();
+
+        let result = ddmin(&elements, Some(2), |subset| {
+            Ok::<_, Infallible>(subset.contains(&3) && subset.contains(&7))
+        })
+        .unwrap();
+
+        assert_eq!(result.tests, 2);
+        assert!(result.budget_exhausted);
+        assert!(result.kept.contains(&3) && result.kept.contains(&7));
+    }
+}
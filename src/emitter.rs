@@ -0,0 +1,275 @@
+//! Emission of the minimized reproduction as a UI test fixture.
+//!
+//! Emitted fixtures follow the layout used by `trybuild` and `ui_test`: a `<name>.rs` file holding
+//! the reproduction and a `<name>.stderr` file holding the normalized compiler output expected from
+//! it.
+use std::path::{Path, PathBuf};
+
+use crate::{builder::is_summary_line, searcher::Minimized};
+
+/// Placeholder used for the directory of the fixture inside the expected stderr.
+const DIR_PLACEHOLDER: &str = "$DIR";
+
+/// Prefixes of the lines produced by cargo itself rather than the compiler.
+const CARGO_LINE_PREFIXES: &[&str] = &[
+    "Compiling ",
+    "Checking ",
+    "Finished ",
+    "Blocking ",
+    "Updating ",
+    "Locking ",
+    "Downloading ",
+    "Downloaded ",
+    "Running ",
+    "warning: unused manifest key",
+    "warning: no edition set",
+];
+
+/// Prefixes of the summary lines that are not part of any diagnostic, besides the build summary
+/// errors.
+const SUMMARY_LINE_PREFIXES: &[&str] = &[
+    "For more information about this error",
+    "For more information about an error",
+    "Some errors have detailed explanations",
+];
+
+/// A UI test fixture generated from a minimized reproduction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UiTest {
+    pub name: String,
+    pub source: String,
+    pub expected_stderr: String,
+    /// Dependency requirements pinning the versions the reproduction was verified against.
+    pub pinned_dependencies: Vec<String>,
+    /// Crate types the reproduction has to be built as, such as `cdylib`.
+    pub crate_types: Vec<String>,
+}
+
+impl UiTest {
+    /// Creates the fixture from the minimized code and the stderr of building it inside
+    /// `project_dir`, relative to the working directory or not.
+    pub fn new(minimized: &Minimized, build_stderr: &str, project_dir: &Path) -> Self {
+        // Cargo reports the paths outside the project as absolute ones.
+        let project_dir = project_dir
+            .canonicalize()
+            .unwrap_or_else(|_| project_dir.to_path_buf());
+        let name = minimized
+            .master_error
+            .as_ref()
+            .and_then(|master_error| master_error.error_code.as_ref())
+            .map(|code| code.to_lowercase())
+            .unwrap_or_else(|| "repro".to_owned());
+        let expected_stderr =
+            normalize_stderr(build_stderr, &minimized.source_file, &name, &project_dir);
+        Self {
+            name,
+            source: minimized.code.clone(),
+            expected_stderr,
+            pinned_dependencies: minimized
+                .locked_dependencies
+                .iter()
+                .map(|package| package.pinned_requirement())
+                .collect(),
+            crate_types: minimized.crate_types.clone(),
+        }
+    }
+
+    /// Writes `<name>.rs` and `<name>.stderr` into `dir`, returning the path of the source file.
+    /// The manifest tables of the crate, if there are any, are written into `<name>.deps`: the
+    /// crate types as a `[lib]` table and the pinned dependencies as a `[dependencies]` one.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf, std::io::Error> {
+        std::fs::create_dir_all(dir)?;
+        let source_path = dir.join(format!("{}.rs", self.name));
+        std::fs::write(&source_path, &self.source)?;
+        std::fs::write(
+            dir.join(format!("{}.stderr", self.name)),
+            &self.expected_stderr,
+        )?;
+        let manifest_tables = self.manifest_tables();
+        if !manifest_tables.is_empty() {
+            std::fs::write(dir.join(format!("{}.deps", self.name)), manifest_tables)?;
+        }
+        Ok(source_path)
+    }
+
+    /// Returns the tables to add to the manifest of the crate holding the reproduction.
+    pub fn manifest_tables(&self) -> String {
+        let mut tables = vec![];
+        if !self.crate_types.is_empty() {
+            let crate_types = self
+                .crate_types
+                .iter()
+                .map(|crate_type| format!("\"{crate_type}\""))
+                .collect::<Vec<_>>();
+            tables.push(format!(
+                "[lib]\ncrate-type = [{}]\n",
+                crate_types.join(", ")
+            ));
+        }
+        if !self.pinned_dependencies.is_empty() {
+            let mut deps = "[dependencies]\n".to_owned();
+            for requirement in &self.pinned_dependencies {
+                deps.push_str(requirement);
+                deps.push('\n');
+            }
+            tables.push(deps);
+        }
+        tables.join("\n")
+    }
+}
+
+/// Normalizes cargo's stderr the way UI test suites expect it.
+///
+/// Cargo status and summary lines are dropped, the path of the minimized file is replaced with
+/// `$DIR/<name>.rs` and any remaining path inside the project directory is made relative to
+/// `$DIR`.
+fn normalize_stderr(stderr: &str, source_file: &Path, name: &str, project_dir: &Path) -> String {
+    let source_file = source_file.display().to_string();
+    let fixture_file = format!("{DIR_PLACEHOLDER}/{name}.rs");
+    // Only the directory as a whole path prefix is replaced, not its text anywhere in a line.
+    let project_dir_prefixes = ['/', std::path::MAIN_SEPARATOR].map(|separator| {
+        (
+            format!("{}{separator}", project_dir.display()),
+            format!("{DIR_PLACEHOLDER}{separator}"),
+        )
+    });
+
+    let mut normalized: Vec<String> = vec![];
+    for line in stderr.lines() {
+        let trimmed = line.trim_start();
+        let is_cargo_line = CARGO_LINE_PREFIXES
+            .iter()
+            .any(|prefix| trimmed.starts_with(prefix));
+        let is_summary_line = is_summary_line(trimmed)
+            || SUMMARY_LINE_PREFIXES
+                .iter()
+                .any(|prefix| trimmed.starts_with(prefix));
+        if is_cargo_line || is_summary_line {
+            continue;
+        }
+
+        let line = project_dir_prefixes.iter().fold(
+            line.replace(&source_file, &fixture_file),
+            |line, (prefix, placeholder)| line.replace(prefix, placeholder),
+        );
+        let line = line.trim_end();
+
+        // Collapse consecutive blank lines left behind by the removed ones.
+        if line.is_empty() && normalized.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        normalized.push(line.to_owned());
+    }
+
+    while normalized.last().is_some_and(|last| last.is_empty()) {
+        normalized.pop();
+    }
+    let mut normalized = normalized.join("\n");
+    normalized.push('\n');
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{normalize_stderr, UiTest};
+    use crate::{builder::BuildError, searcher::Minimized, toolchain::Toolchain};
+
+    const TEST_STDERR: &str = r#"warning: unused manifest key: package.0edition
+   Compiling test_project v0.0.0 (/home/user/test_project)
+error[E0384]: cannot assign twice to immutable variable `b`
+ --> src/main.rs:3:5
+  |
+2 |     let b = 0;
+  |         - first assignment to `b`
+3 |     b = 10;
+  |     ^^^^^^ cannot assign twice to immutable variable
+
+For more information about this error, try `rustc --explain E0384`.
+warning: `test_project` (bin "test_project") generated 1 warning
+error: could not compile `test_project` (bin "test_project") due to previous error; 1 warning emitted
+"#;
+
+    fn minimized() -> Minimized {
+        Minimized {
+            source_file: "src/main.rs".into(),
+            code: "fn main() {}\n".to_owned(),
+            master_error: Some(BuildError {
+                error_code: Some("E0384".to_owned()),
+                source_file: Some("src/main.rs".into()),
+                line: Some(3),
+                error_src: "error[E0384]: cannot assign twice to immutable variable `b`".to_owned(),
+            }),
+            original_code: "fn main() { let a = 0; }\n".to_owned(),
+            variants: 1,
+            accepted: 0,
+            duration: std::time::Duration::ZERO,
+            locked_dependencies: vec![],
+            crate_types: vec!["cdylib".to_owned()],
+            cancelled: false,
+            unsupported: vec![],
+            toolchain: Toolchain {
+                rustc: "rustc 1.76.0 (07dca489a 2024-02-04)\n".to_owned(),
+                cargo: "cargo 1.76.0 (c84b36747 2024-01-18)\n".to_owned(),
+            },
+        }
+    }
+
+    #[test]
+    fn normalize_stderr_strips_cargo_noise() {
+        let normalized = normalize_stderr(
+            TEST_STDERR,
+            Path::new("src/main.rs"),
+            "e0384",
+            Path::new("/home/user/test_project"),
+        );
+
+        let expected = r#"error[E0384]: cannot assign twice to immutable variable `b`
+ --> $DIR/e0384.rs:3:5
+  |
+2 |     let b = 0;
+  |         - first assignment to `b`
+3 |     b = 10;
+  |     ^^^^^^ cannot assign twice to immutable variable
+"#;
+        assert_eq!(normalized, expected)
+    }
+
+    #[test]
+    fn ui_test_of_relative_project_dir() {
+        let project_dir = std::env::current_dir().unwrap().canonicalize().unwrap();
+        let stderr = TEST_STDERR.replace(
+            " --> src/main.rs:3:5",
+            &format!(
+                " --> src/main.rs:3:5\n  ::: {}/src/shapes.rs:1:1",
+                project_dir.display()
+            ),
+        );
+
+        let normalized = UiTest::new(&minimized(), &stderr, Path::new(".")).expected_stderr;
+
+        assert!(normalized.contains(" --> $DIR/e0384.rs:3:5"));
+        assert!(normalized.contains("  ::: $DIR/src/shapes.rs:1:1"));
+        assert!(normalized.contains("first assignment to `b`"));
+    }
+
+    #[test]
+    fn ui_test_named_after_error_code() {
+        let minimized = minimized();
+
+        let ui_test = UiTest::new(
+            &minimized,
+            TEST_STDERR,
+            Path::new("/home/user/test_project"),
+        );
+
+        assert_eq!(ui_test.name, "e0384");
+        assert!(ui_test.expected_stderr.contains(" --> $DIR/e0384.rs:3:5"));
+        assert_eq!(ui_test.source, minimized.code);
+        assert_eq!(
+            ui_test.manifest_tables(),
+            "[lib]\ncrate-type = [\"cdylib\"]\n"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Files of the target that are never minimized.
+//!
+//! Errors can point into code that is not the user's: build outputs `include!`d from `OUT_DIR`,
+//! vendored dependencies, or files ignored by git or excluded from the package. Such files are
+//! skipped when choosing the file to minimize, unless given explicitly, as they tend to be huge and
+//! machine-generated. The `.gitignore` at the root of the target and the `package.exclude` of its
+//! manifest are honored, with the usual glob syntax (`*`, `**`, `?`), negations aside.
+use std::path::{Component, Path};
+
+use crate::{manifest::array_value, session::SESSION_DIR};
+
+/// Directories excluded in every target.
+const EXCLUDED_DIRS: &[&str] = &["target", "vendor", SESSION_DIR];
+
+/// Gitignore-style patterns of the excluded files of a target.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Exclusions {
+    patterns: Vec<String>,
+}
+
+impl Exclusions {
+    /// Reads the exclusions of the target, from its `.gitignore` and `Cargo.toml`.
+    pub fn load(target: &Path) -> Self {
+        let mut patterns = EXCLUDED_DIRS
+            .iter()
+            .map(|dir| format!("/{dir}"))
+            .collect::<Vec<_>>();
+        if let Ok(gitignore) = std::fs::read_to_string(target.join(".gitignore")) {
+            patterns.extend(gitignore_patterns(&gitignore));
+        }
+        if let Ok(manifest) = std::fs::read_to_string(target.join("Cargo.toml")) {
+            patterns.extend(package_excludes(&manifest));
+        }
+        Self { patterns }
+    }
+
+    /// Returns true if the file, relative to the target, is excluded. Files outside of the
+    /// target, such as the sources of registry dependencies, are always excluded.
+    pub fn is_excluded(&self, file: &Path) -> bool {
+        let mut components = vec![];
+        for component in file.components() {
+            match component {
+                Component::Normal(component) => components.push(component.to_string_lossy()),
+                Component::CurDir => {}
+                _ => return true,
+            }
+        }
+        // A file is excluded if it or any of its parent directories matches.
+        (1..=components.len()).any(|len| {
+            let path = components[..len].join("/");
+            self.patterns
+                .iter()
+                .any(|pattern| pattern_matches(pattern, &path))
+        })
+    }
+}
+
+fn gitignore_patterns(gitignore: &str) -> impl Iterator<Item = String> + '_ {
+    gitignore
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))
+        .map(str::to_owned)
+}
+
+/// Returns the patterns of the `exclude` array of the `[package]` table.
+fn package_excludes(manifest: &str) -> Vec<String> {
+    array_value(manifest, "package", "exclude").unwrap_or_default()
+}
+
+/// Matches a gitignore-style pattern against a path relative to the target, with `/` separators.
+/// Patterns without a slash but a trailing one match the name of a file or directory at any depth.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.strip_suffix('/').unwrap_or(pattern);
+    if pattern.contains('/') {
+        glob_matches(pattern.trim_start_matches('/').as_bytes(), path.as_bytes())
+    } else {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        glob_matches(pattern.as_bytes(), name.as_bytes())
+    }
+}
+
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', b'/', rest @ ..] => {
+            // `**/` matches any number of directories, none included.
+            glob_matches(rest, text)
+                || text
+                    .iter()
+                    .enumerate()
+                    .any(|(ix, byte)| *byte == b'/' && glob_matches(rest, &text[ix + 1..]))
+        }
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|ix| glob_matches(rest, &text[ix..])),
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|ix| *ix == 0 || text[ix - 1] != b'/')
+            .any(|ix| glob_matches(rest, &text[ix..])),
+        [b'?', rest @ ..] => {
+            matches!(text.first(), Some(byte) if *byte != b'/') && glob_matches(rest, &text[1..])
+        }
+        [byte, rest @ ..] => text.first() == Some(byte) && glob_matches(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{package_excludes, Exclusions};
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn exclusions_of_target() {
+        let test_dir = TestDir::new("exclude");
+        let target = test_dir.path();
+        std::fs::create_dir_all(target).unwrap();
+        std::fs::write(
+            target.join(".gitignore"),
+            "# generated\n*.gen.rs\n/src/bindings/\n",
+        )
+        .unwrap();
+        std::fs::write(
+            target.join("Cargo.toml"),
+            "[package]\nname = \"a\"\nexclude = [\n  \"benches/**/data.rs\",\n]\n",
+        )
+        .unwrap();
+
+        let exclusions = Exclusions::load(target);
+
+        assert!(exclusions.is_excluded(Path::new("target/debug/build/a-1/out/gen.rs")));
+        assert!(exclusions.is_excluded(Path::new("vendor/b/src/lib.rs")));
+        assert!(exclusions.is_excluded(Path::new("src/parser.gen.rs")));
+        assert!(exclusions.is_excluded(Path::new("src/bindings/ffi.rs")));
+        assert!(exclusions.is_excluded(Path::new("benches/a/b/data.rs")));
+        assert!(exclusions.is_excluded(Path::new("/home/user/.cargo/registry/c/src/lib.rs")));
+        assert!(!exclusions.is_excluded(Path::new("src/main.rs")));
+        assert!(!exclusions.is_excluded(Path::new("src/target.rs")));
+    }
+
+    #[test]
+    fn package_excludes_only_from_package_table() {
+        let manifest = "[package]\nexclude = [\"a/*\", \"b\"]\n[workspace]\nexclude = [\"c\"]\n";
+
+        assert_eq!(package_excludes(manifest), vec!["a/*", "b"]);
+    }
+}
@@ -0,0 +1,212 @@
+//! Frontier of the nodes left to try removing.
+//!
+//! Nodes are tried in BFS order. With the adaptive order, the frontier learns from the run: the
+//! acceptance rate of every kind of node is tracked, and the next node tried is the one of the
+//! kind that has been accepted the most so far, in BFS order among equals. Kinds whose removals
+//! keep being rejected, attributes for instance, sink to the end of the frontier.
+//!
+//! With a stall threshold, the frontier also escalates its granularity when that many removals
+//! in a row are rejected: items are no longer tried, only statements, then only expressions. Once
+//! expressions stall too, the removals end and the rewriting passes take over.
+use std::collections::{HashMap, VecDeque};
+
+use petgraph::{graph::NodeIndex, stable_graph::StableDiGraph};
+
+use crate::parser::{AstNode, NodeKind};
+
+/// Acceptance rates of the removals, by the kinds of the removed node.
+#[derive(Debug, Default)]
+pub struct AcceptanceRates {
+    /// Accepted and tried removals of each kind.
+    counts: HashMap<Vec<NodeKind>, (usize, usize)>,
+}
+
+impl AcceptanceRates {
+    pub fn record(&mut self, kinds: Vec<NodeKind>, accepted: bool) {
+        let (accepted_count, tried_count) = self.counts.entry(kinds).or_default();
+        *accepted_count += usize::from(accepted);
+        *tried_count += 1;
+    }
+
+    /// Estimated probability that removing a node of the kinds is accepted. Kinds not tried yet
+    /// are given even odds.
+    pub fn rate(&self, kinds: &[NodeKind]) -> f64 {
+        let (accepted_count, tried_count) = self.counts.get(kinds).copied().unwrap_or_default();
+        (accepted_count as f64 + 1.0) / (tried_count as f64 + 2.0)
+    }
+}
+
+/// Size of the nodes whose removals are tried, from the coarsest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Granularity {
+    Items,
+    Statements,
+    Expressions,
+}
+
+impl Granularity {
+    pub fn of(node: &AstNode<'_>) -> Self {
+        match node {
+            AstNode::SourceRoot(_) | AstNode::Item(_) | AstNode::ItemFn(_) => Self::Items,
+            AstNode::Block(_)
+            | AstNode::LocalStmt(_)
+            | AstNode::ExprArray(_)
+            | AstNode::ExprAssign(_)
+            | AstNode::ExprIfLet(_)
+            | AstNode::ExprWhileLet(_)
+            | AstNode::ExprMatch(_) => Self::Statements,
+            AstNode::ExprLet(_)
+            | AstNode::Arm(_)
+            | AstNode::Attribute(_)
+            | AstNode::ExprBinary(_)
+            | AstNode::Operand(_)
+            | AstNode::ExprUnary(_)
+            | AstNode::ExprStruct(_)
+            | AstNode::FieldValue(_)
+            | AstNode::ExprTuple(_) => Self::Expressions,
+        }
+    }
+
+    fn finer(self) -> Option<Self> {
+        match self {
+            Self::Items => Some(Self::Statements),
+            Self::Statements => Some(Self::Expressions),
+            Self::Expressions => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Granularity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Items => f.write_str("items"),
+            Self::Statements => f.write_str("statements"),
+            Self::Expressions => f.write_str("expressions"),
+        }
+    }
+}
+
+/// Change of granularity after the removals stalled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escalation {
+    /// Only the nodes of this granularity or a finer one are tried from now on.
+    To(Granularity),
+    /// The finest granularity stalled as well, no more removals are tried.
+    Exhausted,
+}
+
+pub struct Frontier {
+    nodes: VecDeque<NodeIndex>,
+    /// Rates the nodes are ordered by, `None` for the plain BFS order.
+    rates: Option<AcceptanceRates>,
+    /// Coarsest granularity still tried, `None` once every granularity stalled.
+    granularity: Option<Granularity>,
+    /// Number of rejected removals in a row after which the granularity is escalated.
+    stall_threshold: Option<usize>,
+    /// Number of rejected removals since the last accepted one or escalation.
+    stalled: usize,
+}
+
+impl Frontier {
+    pub fn new(adaptive: bool, stall_threshold: Option<usize>) -> Self {
+        Self {
+            nodes: VecDeque::new(),
+            rates: adaptive.then(AcceptanceRates::default),
+            granularity: Some(Granularity::Items),
+            stall_threshold,
+            stalled: 0,
+        }
+    }
+
+    /// Returns true if the node is coarser than the current granularity, so only its children
+    /// are tried.
+    pub fn is_coarser(&self, node: &AstNode<'_>) -> bool {
+        self.granularity
+            .is_some_and(|granularity| Granularity::of(node) < granularity)
+    }
+
+    /// Adds the children of the node to the frontier.
+    pub fn extend_children(&mut self, graph: &StableDiGraph<AstNode<'_>, ()>, node: NodeIndex) {
+        self.nodes.extend(graph.neighbors(node));
+    }
+
+    /// Takes the next node to try out of the frontier.
+    pub fn pop(&mut self, graph: &StableDiGraph<AstNode<'_>, ()>) -> Option<NodeIndex> {
+        let Some(rates) = &self.rates else {
+            return self.nodes.pop_front();
+        };
+        let mut best: Option<(usize, f64)> = None;
+        for (ix, node) in self.nodes.iter().enumerate() {
+            // Nodes removed together with an ancestor are skipped anyway.
+            let rate = graph
+                .node_weight(*node)
+                .map(|node| rates.rate(&node.kinds()))
+                .unwrap_or(f64::INFINITY);
+            if best.is_none_or(|(_, best_rate)| rate > best_rate) {
+                best = Some((ix, rate));
+            }
+        }
+        self.nodes.remove(best?.0)
+    }
+
+    /// Records whether removing a node of the kinds was accepted, returning the escalation of the
+    /// granularity if the removals stalled.
+    pub fn record(&mut self, kinds: Vec<NodeKind>, accepted: bool) -> Option<Escalation> {
+        if let Some(rates) = &mut self.rates {
+            rates.record(kinds, accepted);
+        }
+        self.stalled = if accepted { 0 } else { self.stalled + 1 };
+        if self
+            .stall_threshold
+            .is_none_or(|stall_threshold| self.stalled < stall_threshold)
+        {
+            return None;
+        }
+        self.stalled = 0;
+        self.granularity = self.granularity.and_then(Granularity::finer);
+        Some(match self.granularity {
+            Some(granularity) => Escalation::To(granularity),
+            None => Escalation::Exhausted,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AcceptanceRates, Escalation, Frontier, Granularity};
+    use crate::parser::NodeKind;
+
+    #[test]
+    fn acceptance_rates_favor_accepted_kinds() {
+        let mut rates = AcceptanceRates::default();
+        for _ in 0..3 {
+            rates.record(vec![NodeKind::Attrs], false);
+        }
+        rates.record(vec![NodeKind::Stmt], true);
+
+        assert!(rates.rate(&[NodeKind::Stmt]) > rates.rate(&[NodeKind::Item]));
+        assert!(rates.rate(&[NodeKind::Item]) > rates.rate(&[NodeKind::Attrs]));
+    }
+
+    #[test]
+    fn frontier_escalates_when_stalled() {
+        let mut frontier = Frontier::new(false, Some(2));
+        frontier.record(vec![NodeKind::Item], false);
+        assert_eq!(frontier.record(vec![NodeKind::Item], true), None);
+        assert_eq!(frontier.record(vec![NodeKind::Item], false), None);
+        assert_eq!(
+            frontier.record(vec![NodeKind::Item], false),
+            Some(Escalation::To(Granularity::Statements))
+        );
+        frontier.record(vec![NodeKind::Stmt], false);
+        assert_eq!(
+            frontier.record(vec![NodeKind::Stmt], false),
+            Some(Escalation::To(Granularity::Expressions))
+        );
+        frontier.record(vec![NodeKind::Expr], false);
+        assert_eq!(
+            frontier.record(vec![NodeKind::Expr], false),
+            Some(Escalation::Exhausted)
+        );
+    }
+}
@@ -8,13 +8,23 @@ use petgraph::{
     visit::{EdgeRef, Walker},
     Direction,
 };
-use syn::{Block, Expr, ExprArray, ExprAssign, ExprLet, File, Item, ItemFn, Local, Stmt};
+use syn::{
+    punctuated::Punctuated, Arm, Attribute, Block, Expr, ExprArray, ExprAssign, ExprBinary,
+    ExprBlock, ExprIf, ExprLet, ExprMatch, ExprParen, ExprStruct, ExprTuple, ExprUnary, ExprWhile,
+    FieldValue, File, Item, ItemFn, Local, ReturnType, Stmt, Token,
+};
 use thiserror::Error;
 
-use crate::parser::AstNode;
+use crate::parser::{expr_attrs, is_let_condition, leading_attrs_mut, AstNode};
+
+/// Code generation from the graph of a syntax tree.
+///
+/// The generator holds no state between two generations, the nodes generated for a graph are
+/// kept by a [`Generation`] for the time of the call, so that it is `Send`.
+pub struct CodeGenerator;
 
-/// Code generation from the `SyntaxTree`.
-pub struct CodeGenerator {
+/// The nodes generated so far for a graph, by index.
+struct Generation {
     ix_to_ast_node: HashMap<NodeIndex, GeneratedASTNode>,
 }
 
@@ -40,6 +50,12 @@ pub enum GeneratedASTNode {
     ExprArray(ExprArray),
     ExprAssign(ExprAssign),
     ExprLet(ExprLet),
+    /// Expression generated for an `if let` or a `while let`, which is a plain block once its
+    /// condition is removed, for a `match`, or for an operation or an operand.
+    Expr(Expr),
+    Arm(Arm),
+    Attribute(Attribute),
+    FieldValue(FieldValue),
 }
 
 impl std::fmt::Debug for GeneratedASTNode {
@@ -53,6 +69,10 @@ impl std::fmt::Debug for GeneratedASTNode {
             Self::ExprArray(_) => f.write_str("expr_array"),
             Self::ExprAssign(_) => f.write_str("expr_assign"),
             Self::ExprLet(_) => f.write_str("expr_let"),
+            Self::Expr(_) => f.write_str("expr"),
+            Self::Arm(_) => f.write_str("arm"),
+            Self::Attribute(_) => f.write_str("attribute"),
+            Self::FieldValue(_) => f.write_str("field_value"),
         }
     }
 }
@@ -68,6 +88,29 @@ impl From<AstNode<'_>> for GeneratedASTNode {
             AstNode::ExprArray(expr_array) => GeneratedASTNode::ExprArray(expr_array.clone()),
             AstNode::ExprAssign(expr_assign) => GeneratedASTNode::ExprAssign(expr_assign.clone()),
             AstNode::ExprLet(expr_let) => GeneratedASTNode::ExprLet(expr_let.clone()),
+            AstNode::ExprIfLet(expr_if) => GeneratedASTNode::Expr(Expr::If(expr_if.clone())),
+            AstNode::ExprWhileLet(expr_while) => {
+                GeneratedASTNode::Expr(Expr::While(expr_while.clone()))
+            }
+            AstNode::ExprMatch(expr_match) => {
+                GeneratedASTNode::Expr(Expr::Match(expr_match.clone()))
+            }
+            AstNode::Arm(arm) => GeneratedASTNode::Arm(arm.clone()),
+            AstNode::Attribute(attr) => GeneratedASTNode::Attribute(attr.clone()),
+            AstNode::ExprBinary(expr_binary) => {
+                GeneratedASTNode::Expr(Expr::Binary(expr_binary.clone()))
+            }
+            AstNode::Operand(expr) => GeneratedASTNode::Expr(expr.clone()),
+            AstNode::ExprUnary(expr_unary) => {
+                GeneratedASTNode::Expr(Expr::Unary(expr_unary.clone()))
+            }
+            AstNode::ExprStruct(expr_struct) => {
+                GeneratedASTNode::Expr(Expr::Struct(expr_struct.clone()))
+            }
+            AstNode::FieldValue(field_value) => GeneratedASTNode::FieldValue(field_value.clone()),
+            AstNode::ExprTuple(expr_tuple) => {
+                GeneratedASTNode::Expr(Expr::Tuple(expr_tuple.clone()))
+            }
         }
     }
 }
@@ -78,6 +121,7 @@ impl TryFrom<GeneratedASTNode> for Stmt {
     fn try_from(value: GeneratedASTNode) -> Result<Self, Self::Error> {
         match value {
             GeneratedASTNode::LocalStmt(local_stmt) => Ok(Stmt::Local(local_stmt)),
+            GeneratedASTNode::Item(item) => Ok(Stmt::Item(item)),
             GeneratedASTNode::ExprArray(expr_arr) => {
                 let expr = Expr::Array(expr_arr);
                 // TODO: look into this `,` being none.
@@ -93,6 +137,7 @@ impl TryFrom<GeneratedASTNode> for Stmt {
                 // TODO: look into this `,` being none.
                 Ok(Stmt::Expr(expr, None))
             }
+            GeneratedASTNode::Expr(expr) => Ok(Stmt::Expr(expr, None)),
             other => Err(Self::Error::MismatchedASTConversion(
                 format!("{other:?}"),
                 "stmt".to_owned(),
@@ -145,16 +190,29 @@ impl TryFrom<GeneratedASTNode> for Item {
 
 impl CodeGenerator {
     pub fn new() -> Self {
-        Self {
+        Self
+    }
+
+    /// Generates the file of the syntax tree, the nodes taken from the original file keep their
+    /// spans.
+    pub fn generate(
+        &self,
+        graph: &StableDiGraph<AstNode<'_>, ()>,
+        root_node_ix: NodeIndex,
+    ) -> Result<File, CodeGeneratorError> {
+        Generation {
             ix_to_ast_node: HashMap::new(),
         }
+        .generate(graph, root_node_ix)
     }
+}
 
-    pub fn generate(
+impl Generation {
+    fn generate(
         &mut self,
         graph: &StableDiGraph<AstNode<'_>, ()>,
         root_node_ix: NodeIndex,
-    ) -> Result<String, CodeGeneratorError> {
+    ) -> Result<File, CodeGeneratorError> {
         // Get the source root.
         let bfs = petgraph::visit::Bfs::new(graph, root_node_ix);
 
@@ -183,7 +241,7 @@ impl CodeGenerator {
                     });
                     break;
                 }
-                AstNode::Item(_) => {
+                AstNode::Item(original_item) => {
                     let item_fn = graph
                         .edges_directed(node_ix, Direction::Outgoing)
                         .map(|edge| edge.target())
@@ -197,6 +255,11 @@ impl CodeGenerator {
                         let item = Item::Fn(item_fn);
                         self.ix_to_ast_node
                             .insert(node_ix, GeneratedASTNode::Item(item));
+                    } else if !matches!(original_item, Item::Fn(_)) {
+                        // Items other than functions are not reconstructed from their children,
+                        // emit them as they are.
+                        self.ix_to_ast_node
+                            .insert(node_ix, GeneratedASTNode::Item((*original_item).clone()));
                     }
                 }
                 AstNode::ItemFn(item_fn) => {
@@ -206,10 +269,7 @@ impl CodeGenerator {
                         .map(|target_ix| self.ix_to_ast_node[&target_ix].clone())
                         .map(Block::try_from)
                         .find_map(Result::ok)
-                        .unwrap_or_else(|| Block {
-                            brace_token: Default::default(),
-                            stmts: vec![],
-                        });
+                        .unwrap_or_else(|| stub_block(&item_fn.sig.output));
 
                     let item_fn = ItemFn {
                         attrs: item_fn.attrs.clone(),
@@ -222,14 +282,26 @@ impl CodeGenerator {
                         .insert(node_ix, GeneratedASTNode::ItemFn(item_fn));
                 }
                 AstNode::Block(block) => {
-                    let mut child_stmnts = graph
-                        .edges_directed(node_ix, Direction::Outgoing)
-                        .map(|edge| edge.target())
-                        .map(|target_ix| self.ix_to_ast_node[&target_ix].clone())
-                        .map(Stmt::try_from)
-                        .collect::<Result<Vec<Stmt>, _>>()?;
-
-                    child_stmnts.reverse();
+                    // Statements that are not modeled in the syntax tree are kept as they are,
+                    // modeled ones are generated from their child node if it is still present.
+                    let mut child_stmnts = vec![];
+                    for stmt in &block.stmts {
+                        let Some(stmt_node) = AstNode::from_stmt(stmt) else {
+                            child_stmnts.push(stmt.clone());
+                            continue;
+                        };
+                        let generated_child = self.generated_child(graph, node_ix, &stmt_node);
+                        if let Some(generated_child) = generated_child {
+                            let generated_stmt = match Stmt::try_from(generated_child)? {
+                                Stmt::Expr(expr, _) => match stmt {
+                                    Stmt::Expr(_, semi) => Stmt::Expr(expr, *semi),
+                                    _ => Stmt::Expr(expr, None),
+                                },
+                                generated_stmt => generated_stmt,
+                            };
+                            child_stmnts.push(generated_stmt);
+                        }
+                    }
 
                     let block = Block {
                         brace_token: block.brace_token,
@@ -239,6 +311,214 @@ impl CodeGenerator {
                     self.ix_to_ast_node
                         .insert(node_ix, GeneratedASTNode::Block(block));
                 }
+                AstNode::LocalStmt(original_local) => {
+                    let mut local = (*original_local).clone();
+                    // The `else` block of a `let ... else` is generated from its child node, a
+                    // diverging stub replaces it if it is removed.
+                    let original_diverge = original_local
+                        .init
+                        .as_ref()
+                        .and_then(|init| init.diverge.as_ref());
+                    let diverge = local.init.as_mut().and_then(|init| init.diverge.as_mut());
+                    if let (Some((_, original_expr)), Some((_, expr))) = (original_diverge, diverge)
+                    {
+                        if let (Expr::Block(original_block), Expr::Block(expr_block)) =
+                            (original_expr.as_ref(), expr.as_mut())
+                        {
+                            expr_block.block = self
+                                .generated_block(graph, node_ix, &original_block.block)?
+                                .unwrap_or_else(|| syn::parse_quote!({ todo!() }));
+                        }
+                    }
+                    if let (Some(original_init), Some(init)) =
+                        (&original_local.init, local.init.as_mut())
+                    {
+                        *init.expr = self.generated_expr(graph, node_ix, &original_init.expr);
+                    }
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::LocalStmt(local));
+                }
+                AstNode::ExprIfLet(expr_if) => {
+                    let expr = self.generate_if_let(graph, node_ix, expr_if)?;
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::Expr(expr));
+                }
+                AstNode::ExprWhileLet(expr_while) => {
+                    let attrs = self.generated_attrs(graph, node_ix, &expr_while.attrs);
+                    let body = self
+                        .generated_block(graph, node_ix, &expr_while.body)?
+                        .unwrap_or_else(empty_block);
+                    let expr = if self.has_condition(graph, node_ix, &expr_while.cond) {
+                        Expr::While(ExprWhile {
+                            attrs,
+                            body,
+                            ..(*expr_while).clone()
+                        })
+                    } else {
+                        Expr::Block(ExprBlock {
+                            attrs,
+                            label: None,
+                            block: body,
+                        })
+                    };
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::Expr(expr));
+                }
+                AstNode::ExprMatch(expr_match) => {
+                    let arms = expr_match
+                        .arms
+                        .iter()
+                        .filter_map(|arm| {
+                            match self.generated_child(graph, node_ix, &AstNode::Arm(arm)) {
+                                Some(GeneratedASTNode::Arm(arm)) => Some(arm),
+                                _ => None,
+                            }
+                        })
+                        .collect();
+                    let expr = Expr::Match(ExprMatch {
+                        attrs: self.generated_attrs(graph, node_ix, &expr_match.attrs),
+                        arms,
+                        ..(*expr_match).clone()
+                    });
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::Expr(expr));
+                }
+                AstNode::Arm(original_arm) => {
+                    let mut arm = (*original_arm).clone();
+                    arm.attrs = self.generated_attrs(graph, node_ix, &original_arm.attrs);
+                    if let (Expr::Block(original_block), Expr::Block(body)) =
+                        (original_arm.body.as_ref(), arm.body.as_mut())
+                    {
+                        body.block = self
+                            .generated_block(graph, node_ix, &original_block.block)?
+                            .unwrap_or_else(empty_block);
+                    } else {
+                        *arm.body = self.generated_expr(graph, node_ix, &original_arm.body);
+                    }
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::Arm(arm));
+                }
+                AstNode::ExprArray(expr_array) => {
+                    let expr_array = ExprArray {
+                        attrs: self.generated_attrs(graph, node_ix, &expr_array.attrs),
+                        ..(*expr_array).clone()
+                    };
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::ExprArray(expr_array));
+                }
+                AstNode::ExprAssign(original_assign) => {
+                    let attrs = self.generated_attrs(graph, node_ix, expr_attrs(node));
+                    let mut expr_assign = (*original_assign).clone();
+                    if original_assign.attrs.is_empty() {
+                        if let Some(leading_attrs) = leading_attrs_mut(&mut expr_assign.left) {
+                            *leading_attrs = attrs;
+                        }
+                    } else {
+                        expr_assign.attrs = attrs;
+                    }
+                    *expr_assign.right =
+                        self.generated_expr(graph, node_ix, &original_assign.right);
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::ExprAssign(expr_assign));
+                }
+                AstNode::ExprBinary(expr_binary) => {
+                    let operand = |operand: &Expr| match self.generated_child(
+                        graph,
+                        node_ix,
+                        &AstNode::Operand(operand),
+                    ) {
+                        Some(GeneratedASTNode::Expr(expr)) => Some(expr),
+                        _ => None,
+                    };
+                    let expr = match (operand(&expr_binary.left), operand(&expr_binary.right)) {
+                        (Some(left), Some(right)) => Expr::Binary(ExprBinary {
+                            left: Box::new(left),
+                            right: Box::new(right),
+                            ..(*expr_binary).clone()
+                        }),
+                        (Some(operand), None) | (None, Some(operand)) => operand,
+                        (None, None) => syn::parse_quote!(todo!()),
+                    };
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::Expr(expr));
+                }
+                AstNode::Operand(expr) => {
+                    let expr = self.generated_expr(graph, node_ix, expr);
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::Expr(expr));
+                }
+                AstNode::ExprUnary(expr_unary) => {
+                    let expr = Expr::Unary(ExprUnary {
+                        expr: Box::new(self.generated_expr(graph, node_ix, &expr_unary.expr)),
+                        ..(*expr_unary).clone()
+                    });
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::Expr(expr));
+                }
+                AstNode::ExprStruct(original_struct) => {
+                    let fields = original_struct
+                        .fields
+                        .iter()
+                        .filter_map(|field| {
+                            match self.generated_child(graph, node_ix, &AstNode::FieldValue(field))
+                            {
+                                Some(GeneratedASTNode::FieldValue(field)) => Some(field),
+                                _ => None,
+                            }
+                        })
+                        .collect::<Punctuated<_, Token![,]>>();
+                    let mut expr_struct = ExprStruct {
+                        fields,
+                        ..(*original_struct).clone()
+                    };
+                    // The removed fields are given their default values.
+                    if expr_struct.fields.len() < original_struct.fields.len()
+                        && expr_struct.rest.is_none()
+                    {
+                        expr_struct.dot2_token = Some(Default::default());
+                        expr_struct.rest = Some(syn::parse_quote!(Default::default()));
+                    }
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::Expr(Expr::Struct(expr_struct)));
+                }
+                AstNode::FieldValue(original_field) => {
+                    let field_value = FieldValue {
+                        expr: self.generated_expr(graph, node_ix, &original_field.expr),
+                        ..(*original_field).clone()
+                    };
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::FieldValue(field_value));
+                }
+                AstNode::ExprTuple(expr_tuple) => {
+                    let mut elems = expr_tuple
+                        .elems
+                        .iter()
+                        .filter_map(|elem| {
+                            match self.generated_child(graph, node_ix, &AstNode::Operand(elem)) {
+                                Some(GeneratedASTNode::Expr(expr)) => Some(expr),
+                                _ => None,
+                            }
+                        })
+                        .collect::<Punctuated<_, Token![,]>>();
+                    // A single element needs its comma to stay a tuple.
+                    if elems.len() == 1 {
+                        elems.push_punct(Default::default());
+                    }
+                    let expr = Expr::Tuple(ExprTuple {
+                        elems,
+                        ..(*expr_tuple).clone()
+                    });
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::Expr(expr));
+                }
+                AstNode::ExprLet(expr_let) => {
+                    let expr_let = ExprLet {
+                        attrs: self.generated_attrs(graph, node_ix, &expr_let.attrs),
+                        ..(*expr_let).clone()
+                    };
+                    self.ix_to_ast_node
+                        .insert(node_ix, GeneratedASTNode::ExprLet(expr_let));
+                }
                 _ => {
                     // this is a leaf node.
                     self.ix_to_ast_node
@@ -247,25 +527,216 @@ impl CodeGenerator {
             }
         }
 
-        if let Some(file) = file {
-            Ok(prettyplease::unparse(&file))
-        } else {
-            Err(CodeGeneratorError::FileNotGeneratedFromTree)
+        file.ok_or(CodeGeneratorError::FileNotGeneratedFromTree)
+    }
+}
+
+impl Generation {
+    /// Returns the code generated for the child of the node standing for `child`, `None` if it
+    /// has been removed.
+    fn generated_child(
+        &self,
+        graph: &StableDiGraph<AstNode<'_>, ()>,
+        node_ix: NodeIndex,
+        child: &AstNode<'_>,
+    ) -> Option<GeneratedASTNode> {
+        graph
+            .edges_directed(node_ix, Direction::Outgoing)
+            .map(|edge| edge.target())
+            .find(|child_ix| graph[*child_ix].is_same_node(child))
+            .and_then(|child_ix| self.ix_to_ast_node.get(&child_ix).cloned())
+    }
+
+    fn generated_block(
+        &self,
+        graph: &StableDiGraph<AstNode<'_>, ()>,
+        node_ix: NodeIndex,
+        block: &Block,
+    ) -> Result<Option<Block>, CodeGeneratorError> {
+        self.generated_child(graph, node_ix, &AstNode::Block(block))
+            .map(Block::try_from)
+            .transpose()
+    }
+
+    /// Returns the attributes of the node whose own nodes have not been removed.
+    fn generated_attrs(
+        &self,
+        graph: &StableDiGraph<AstNode<'_>, ()>,
+        node_ix: NodeIndex,
+        attrs: &[Attribute],
+    ) -> Vec<Attribute> {
+        attrs
+            .iter()
+            .filter(|attr| {
+                self.generated_child(graph, node_ix, &AstNode::Attribute(attr))
+                    .is_some()
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the expression the node holds, generated from its child if it is modeled. A
+    /// removed unary operation is replaced with its operand, other removed expressions become a
+    /// `todo!()`.
+    fn generated_expr(
+        &self,
+        graph: &StableDiGraph<AstNode<'_>, ()>,
+        node_ix: NodeIndex,
+        expr: &Expr,
+    ) -> Expr {
+        match expr {
+            Expr::Paren(expr_paren) => Expr::Paren(ExprParen {
+                expr: Box::new(self.generated_expr(graph, node_ix, &expr_paren.expr)),
+                ..expr_paren.clone()
+            }),
+            Expr::Binary(expr_binary) => {
+                match self.generated_child(graph, node_ix, &AstNode::ExprBinary(expr_binary)) {
+                    Some(GeneratedASTNode::Expr(expr)) => expr,
+                    _ => syn::parse_quote!(todo!()),
+                }
+            }
+            Expr::Unary(expr_unary) => {
+                match self.generated_child(graph, node_ix, &AstNode::ExprUnary(expr_unary)) {
+                    Some(GeneratedASTNode::Expr(expr)) => expr,
+                    _ => (*expr_unary.expr).clone(),
+                }
+            }
+            Expr::Struct(expr_struct) => {
+                match self.generated_child(graph, node_ix, &AstNode::ExprStruct(expr_struct)) {
+                    Some(GeneratedASTNode::Expr(expr)) => expr,
+                    _ => syn::parse_quote!(todo!()),
+                }
+            }
+            Expr::Tuple(expr_tuple) if !expr_tuple.elems.is_empty() => {
+                match self.generated_child(graph, node_ix, &AstNode::ExprTuple(expr_tuple)) {
+                    Some(GeneratedASTNode::Expr(expr)) => expr,
+                    _ => syn::parse_quote!(todo!()),
+                }
+            }
+            expr => expr.clone(),
+        }
+    }
+
+    /// Returns true if the `let` condition of the node has not been removed.
+    fn has_condition(
+        &self,
+        graph: &StableDiGraph<AstNode<'_>, ()>,
+        node_ix: NodeIndex,
+        cond: &Expr,
+    ) -> bool {
+        match cond {
+            Expr::Let(expr_let) => self
+                .generated_child(graph, node_ix, &AstNode::ExprLet(expr_let))
+                .is_some(),
+            _ => true,
+        }
+    }
+
+    /// Generates an `if let` from its children. Without its condition, it is replaced with its
+    /// then branch, and a removed `else` branch is dropped.
+    fn generate_if_let(
+        &self,
+        graph: &StableDiGraph<AstNode<'_>, ()>,
+        node_ix: NodeIndex,
+        expr_if: &ExprIf,
+    ) -> Result<Expr, CodeGeneratorError> {
+        let attrs = self.generated_attrs(graph, node_ix, &expr_if.attrs);
+        let then_branch = self
+            .generated_block(graph, node_ix, &expr_if.then_branch)?
+            .unwrap_or_else(empty_block);
+        if !self.has_condition(graph, node_ix, &expr_if.cond) {
+            return Ok(Expr::Block(ExprBlock {
+                attrs,
+                label: None,
+                block: then_branch,
+            }));
         }
+
+        let else_branch = match &expr_if.else_branch {
+            Some((else_token, else_expr)) => {
+                let else_expr = match else_expr.as_ref() {
+                    Expr::Block(expr_block) => self
+                        .generated_block(graph, node_ix, &expr_block.block)?
+                        .map(|block| {
+                            Expr::Block(ExprBlock {
+                                block,
+                                ..expr_block.clone()
+                            })
+                        }),
+                    Expr::If(else_if) if is_let_condition(&else_if.cond) => {
+                        match self.generated_child(graph, node_ix, &AstNode::ExprIfLet(else_if)) {
+                            Some(GeneratedASTNode::Expr(expr)) => Some(expr),
+                            _ => None,
+                        }
+                    }
+                    // Other `else if`s are not modeled, they are kept as they are.
+                    else_expr => Some(else_expr.clone()),
+                };
+                else_expr.map(|else_expr| (*else_token, Box::new(else_expr)))
+            }
+            None => None,
+        };
+        Ok(Expr::If(ExprIf {
+            attrs,
+            then_branch,
+            else_branch,
+            ..expr_if.clone()
+        }))
+    }
+}
+
+fn empty_block() -> Block {
+    Block {
+        brace_token: Default::default(),
+        stmts: vec![],
+    }
+}
+
+/// Creates the body of a function whose block is removed. Functions returning a value get a
+/// `todo!()` body so that their signature stays valid.
+fn stub_block(output: &ReturnType) -> Block {
+    let stmts = match output {
+        ReturnType::Default => vec![],
+        ReturnType::Type(..) => vec![Stmt::Expr(syn::parse_quote!(todo!()), None)],
+    };
+    Block {
+        brace_token: Default::default(),
+        stmts,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use syn::visit::Visit;
+    use syn::{visit::Visit, Expr, Stmt};
 
     use crate::{
-        graph::{GraphBuilder, SyntaxTree},
-        parser::AbstractSyntaxTree,
+        graph::GraphBuilder,
+        parser::{AbstractSyntaxTree, AstNode},
+        remover::NodeRemover,
     };
 
     use super::CodeGenerator;
 
+    /// Generates the code after removing the first node matching `is_removed` from its graph.
+    fn generate_without(test_code: &str, is_removed: fn(&AstNode<'_>) -> bool) -> String {
+        let file = AbstractSyntaxTree::parse(test_code).syn_file();
+        let mut graph_builder = GraphBuilder::new();
+        graph_builder.visit_file(&file);
+
+        let root_node = graph_builder.root_node().unwrap();
+        let mut graph = graph_builder.graph().clone();
+        let removed_node = graph
+            .node_indices()
+            .find(|node_ix| is_removed(&graph[*node_ix]))
+            .unwrap();
+        NodeRemover::remove_node(&mut graph, removed_node);
+        prettyplease::unparse(&CodeGenerator::new().generate(&graph, root_node).unwrap())
+    }
+
+    fn unparse(code: &str) -> String {
+        prettyplease::unparse(&syn::parse_file(code).unwrap())
+    }
+
     #[test]
     fn parse_unparse_parse() {
         let test_code = r#"
@@ -274,18 +745,284 @@ fn main() {}"#;
         let parsed_ast = AbstractSyntaxTree::parse(test_code);
         let file = parsed_ast.clone().syn_file();
 
-        let mut syntax_tree = SyntaxTree::new();
-        let mut graph_builder = GraphBuilder::new(&mut syntax_tree, None, None);
+        let mut graph_builder = GraphBuilder::new();
+        graph_builder.visit_file(&file);
+
+        let root_node = graph_builder.root_node().unwrap();
+        let code_generator = CodeGenerator::new();
+        let generated_code = code_generator
+            .generate(graph_builder.graph(), root_node)
+            .unwrap();
+
+        let reparsed_ast = AbstractSyntaxTree::parse(prettyplease::unparse(&generated_code));
+
+        assert_eq!(parsed_ast, reparsed_ast)
+    }
+
+    #[test]
+    fn generate_keeps_non_fn_items() {
+        let test_code = r#"
+pub struct PublicStruct;
+fn main() {}"#;
+        let parsed_ast = AbstractSyntaxTree::parse(test_code);
+        let file = parsed_ast.clone().syn_file();
+
+        let mut graph_builder = GraphBuilder::new();
         graph_builder.visit_file(&file);
 
         let root_node = graph_builder.root_node().unwrap();
-        let mut code_generator = CodeGenerator::new();
+        let code_generator = CodeGenerator::new();
         let generated_code = code_generator
-            .generate(graph_builder.syntax_tree().as_ref(), root_node)
+            .generate(graph_builder.graph(), root_node)
             .unwrap();
 
-        let reparsed_ast = AbstractSyntaxTree::parse(generated_code);
+        let reparsed_ast = AbstractSyntaxTree::parse(prettyplease::unparse(&generated_code));
 
         assert_eq!(parsed_ast, reparsed_ast)
     }
+
+    #[test]
+    fn generate_keeps_unmodeled_stmts() {
+        let test_code = r#"
+fn double(x: u32) -> u32 {
+    let y = x;
+    println!("{y}");
+    y * 2
+}"#;
+        let parsed_ast = AbstractSyntaxTree::parse(test_code);
+        let file = parsed_ast.clone().syn_file();
+
+        let mut graph_builder = GraphBuilder::new();
+        graph_builder.visit_file(&file);
+
+        let root_node = graph_builder.root_node().unwrap();
+        let code_generator = CodeGenerator::new();
+        let generated_code = code_generator
+            .generate(graph_builder.graph(), root_node)
+            .unwrap();
+
+        let reparsed_ast = AbstractSyntaxTree::parse(prettyplease::unparse(&generated_code));
+
+        assert_eq!(parsed_ast, reparsed_ast)
+    }
+
+    #[test]
+    fn generate_let_else_and_if_let() {
+        let test_code = r#"
+fn test_fn(a: Option<u32>) {
+    let Some(b) = a else {
+        let c = 0;
+        return;
+    };
+    if let Some(d) = a {
+        let e = d;
+    } else if let None = a {
+        let f = 0;
+    } else {
+        let g = 0;
+    }
+    while let Some(h) = a {
+        let i = h;
+    }
+}"#;
+        let parsed_ast = AbstractSyntaxTree::parse(test_code);
+        let file = parsed_ast.clone().syn_file();
+
+        let mut graph_builder = GraphBuilder::new();
+        graph_builder.visit_file(&file);
+
+        let root_node = graph_builder.root_node().unwrap();
+        let code_generator = CodeGenerator::new();
+        let generated_code = code_generator
+            .generate(graph_builder.graph(), root_node)
+            .unwrap();
+
+        assert_eq!(
+            parsed_ast,
+            AbstractSyntaxTree::parse(prettyplease::unparse(&generated_code))
+        );
+    }
+
+    #[test]
+    fn generate_if_let_without_condition() {
+        let test_code = r#"
+fn test_fn(a: Option<u32>) {
+    if let Some(d) = a {
+        let e = 0;
+    } else {
+        let g = 0;
+    }
+}"#;
+
+        assert_eq!(
+            generate_without(test_code, |node| matches!(node, AstNode::ExprLet(_))),
+            unparse(
+                r#"
+fn test_fn(a: Option<u32>) {
+    {
+        let e = 0;
+    }
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn generate_let_else_without_else_block() {
+        let test_code = r#"
+fn test_fn(a: Option<u32>) {
+    let Some(b) = a else {
+        return;
+    };
+}"#;
+
+        assert_eq!(
+            generate_without(test_code, |node| matches!(
+                node,
+                AstNode::Block(block) if matches!(block.stmts[..], [Stmt::Expr(Expr::Return(_), _)])
+            )),
+            unparse(
+                r#"
+fn test_fn(a: Option<u32>) {
+    let Some(b) = a else { todo!() };
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn generate_expression_attributes() {
+        let test_code = r#"
+fn test_fn(a: Option<u32>, mut b: u32) {
+    #[allow(unused_assignments)]
+    b = 10;
+    match a {
+        #[cfg(test)]
+        Some(c) => {
+            let d = c;
+        }
+        _ => {}
+    }
+}"#;
+
+        let file = AbstractSyntaxTree::parse(test_code).syn_file();
+        let mut graph_builder = GraphBuilder::new();
+        graph_builder.visit_file(&file);
+        let generated = CodeGenerator::new()
+            .generate(graph_builder.graph(), graph_builder.root_node().unwrap())
+            .unwrap();
+        assert_eq!(prettyplease::unparse(&generated), unparse(test_code));
+
+        assert_eq!(
+            generate_without(test_code, |node| matches!(node, AstNode::Attribute(_))),
+            unparse(
+                r#"
+fn test_fn(a: Option<u32>, mut b: u32) {
+    b = 10;
+    match a {
+        #[cfg(test)]
+        Some(c) => {
+            let d = c;
+        }
+        _ => {}
+    }
+}"#
+            )
+        );
+        assert_eq!(
+            generate_without(
+                test_code,
+                |node| matches!(node, AstNode::Arm(arm) if !arm.attrs.is_empty())
+            ),
+            unparse(
+                r#"
+fn test_fn(a: Option<u32>, mut b: u32) {
+    #[allow(unused_assignments)]
+    b = 10;
+    match a {
+        _ => {}
+    }
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn generate_without_operands() {
+        let test_code = r#"
+fn test_fn(a: u32) {
+    let b: u32 = a + f(a, 2);
+    let c = !(a > 2);
+}"#;
+
+        assert_eq!(
+            generate_without(test_code, |node| matches!(
+                node,
+                AstNode::Operand(Expr::Call(_))
+            )),
+            unparse(
+                r#"
+fn test_fn(a: u32) {
+    let b: u32 = a;
+    let c = !(a > 2);
+}"#
+            )
+        );
+        assert_eq!(
+            generate_without(test_code, |node| matches!(node, AstNode::ExprBinary(_))),
+            unparse(
+                r#"
+fn test_fn(a: u32) {
+    let b: u32 = todo!();
+    let c = !(a > 2);
+}"#
+            )
+        );
+        assert_eq!(
+            generate_without(test_code, |node| matches!(node, AstNode::ExprUnary(_))),
+            unparse(
+                r#"
+fn test_fn(a: u32) {
+    let b: u32 = a + f(a, 2);
+    let c = (a > 2);
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn generate_without_fields_and_elements() {
+        let test_code = r#"
+fn test_fn(a: u32) -> Config {
+    let pair = (a, Config { b: 1 });
+    Config { a, b: a + 1 }
+}"#;
+
+        assert_eq!(
+            generate_without(test_code, |node| matches!(
+                node,
+                AstNode::FieldValue(field) if matches!(field.expr, Expr::Binary(_))
+            )),
+            unparse(
+                r#"
+fn test_fn(a: u32) -> Config {
+    let pair = (a, Config { b: 1 });
+    Config { a, ..Default::default() }
+}"#
+            )
+        );
+        assert_eq!(
+            generate_without(test_code, |node| matches!(
+                node,
+                AstNode::Operand(Expr::Struct(_))
+            )),
+            unparse(
+                r#"
+fn test_fn(a: u32) -> Config {
+    let pair = (a,);
+    Config { a, b: a + 1 }
+}"#
+            )
+        );
+    }
 }
@@ -1,7 +1,11 @@
 use petgraph::{graph::NodeIndex, stable_graph::StableDiGraph};
-use syn::visit::{self, Visit};
+use syn::{
+    spanned::Spanned,
+    visit::{self, Visit},
+    Expr, ForeignItem, ImplItem, Item, Pat, Stmt, TraitItem, Type,
+};
 
-use crate::parser::AstNode;
+use crate::parser::{expr_attrs, is_let_condition, without_parens, AstNode};
 
 impl std::fmt::Debug for AstNode<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -14,90 +18,126 @@ impl std::fmt::Debug for AstNode<'_> {
             Self::ExprArray(_) => f.write_str("expr_array"),
             Self::ExprAssign(_) => f.write_str("expr_assign"),
             Self::ExprLet(_) => f.write_str("expr_let"),
+            Self::ExprIfLet(_) => f.write_str("expr_if_let"),
+            Self::ExprWhileLet(_) => f.write_str("expr_while_let"),
+            Self::ExprMatch(_) => f.write_str("expr_match"),
+            Self::Arm(_) => f.write_str("arm"),
+            Self::Attribute(_) => f.write_str("attribute"),
+            Self::ExprBinary(_) => f.write_str("expr_binary"),
+            Self::Operand(_) => f.write_str("operand"),
+            Self::ExprUnary(_) => f.write_str("expr_unary"),
+            Self::ExprStruct(_) => f.write_str("expr_struct"),
+            Self::FieldValue(_) => f.write_str("field_value"),
+            Self::ExprTuple(_) => f.write_str("expr_tuple"),
         }
     }
 }
 
-// Define a struct to represent the syntax tree
-#[derive(Debug)]
-pub struct SyntaxTree<'a> {
-    graph: StableDiGraph<AstNode<'a>, ()>,
-}
-
-impl<'a> AsRef<StableDiGraph<AstNode<'a>, ()>> for SyntaxTree<'a> {
-    fn as_ref(&self) -> &StableDiGraph<AstNode<'a>, ()> {
-        &self.graph
-    }
+/// A construct of the file the graph cannot model, such as tokens `syn` does not interpret or
+/// inline assembly. It is kept as is by the reductions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unsupported {
+    /// Line of the start of the construct, 1-based.
+    pub line: usize,
+    /// Column of the start of the construct, 1-based.
+    pub column: usize,
+    pub construct: &'static str,
 }
 
-impl<'a> AsMut<StableDiGraph<AstNode<'a>, ()>> for SyntaxTree<'a> {
-    fn as_mut(&mut self) -> &mut StableDiGraph<AstNode<'a>, ()> {
-        &mut self.graph
-    }
-}
-
-impl<'a> SyntaxTree<'a> {
-    // Constructor function to create a new SyntaxTree
-    pub fn new() -> Self {
-        SyntaxTree {
-            graph: StableDiGraph::new(),
+impl Unsupported {
+    fn new(span: proc_macro2::Span, construct: &'static str) -> Self {
+        let start = span.start();
+        Self {
+            line: start.line,
+            column: start.column + 1,
+            construct,
         }
     }
+}
 
-    // Function to add a node to the graph
-    fn add_node(&mut self, node: AstNode<'a>) -> NodeIndex {
-        self.graph.add_node(node)
-    }
-
-    // Function to add an edge between two nodes in the graph
-    fn add_edge(&mut self, source: NodeIndex, target: NodeIndex) {
-        self.graph.add_edge(source, target, ());
-    }
-
-    pub fn graph(&self) -> StableDiGraph<AstNode<'_>, ()> {
-        self.graph.clone()
+impl std::fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.construct)
     }
 }
 
 // Custom visitor to traverse the syntax tree and build the graph
 pub struct GraphBuilder<'a> {
-    syntax_tree: &'a mut SyntaxTree<'a>,
+    graph: StableDiGraph<AstNode<'a>, ()>,
     current_node: Option<NodeIndex>,
     root_node: Option<NodeIndex>,
+    /// Constructs met that the graph does not model, in the order of the file.
+    unsupported: Vec<Unsupported>,
 }
 
 impl<'a> GraphBuilder<'a> {
-    pub fn new(
-        syntax_tree: &'a mut SyntaxTree<'a>,
-        current_node: Option<NodeIndex>,
-        root_node: Option<NodeIndex>,
-    ) -> Self {
+    pub fn new() -> Self {
         Self {
-            syntax_tree,
-            current_node,
-            root_node,
+            graph: StableDiGraph::new(),
+            current_node: None,
+            root_node: None,
+            unsupported: vec![],
         }
     }
 
-    pub fn syntax_tree(&self) -> &SyntaxTree<'_> {
-        self.syntax_tree
+    pub fn graph(&self) -> &StableDiGraph<AstNode<'a>, ()> {
+        &self.graph
     }
 
     pub fn root_node(&self) -> Option<NodeIndex<u32>> {
         self.root_node
     }
+
+    pub fn unsupported(&self) -> &[Unsupported] {
+        &self.unsupported
+    }
+
+    fn record_unsupported(&mut self, span: proc_macro2::Span, construct: &'static str) {
+        self.unsupported.push(Unsupported::new(span, construct));
+    }
+
+    /// Returns true if the current node holds an expression matching `is_expr` in a position it
+    /// regenerates from its children. Operations found elsewhere are not modeled, their removal
+    /// would not change the generated code.
+    fn holds_expr(&self, is_expr: impl Fn(&Expr) -> bool) -> bool {
+        let is_expr = |expr: &Expr| is_expr(without_parens(expr));
+        let Some(current_node) = self.current_node else {
+            return false;
+        };
+        match &self.graph[current_node] {
+            AstNode::Block(block) => block
+                .stmts
+                .iter()
+                .any(|stmt| matches!(stmt, Stmt::Expr(expr, _) if is_expr(expr))),
+            AstNode::LocalStmt(local) => {
+                local.init.as_ref().is_some_and(|init| is_expr(&init.expr))
+            }
+            AstNode::ExprAssign(expr_assign) => is_expr(&expr_assign.right),
+            AstNode::Arm(arm) => is_expr(&arm.body),
+            AstNode::Operand(expr) => is_expr(expr),
+            AstNode::ExprUnary(expr_unary) => is_expr(&expr_unary.expr),
+            AstNode::FieldValue(field_value) => is_expr(&field_value.expr),
+            _ => false,
+        }
+    }
 }
 
-/// A macro to insert current node to the graph and visit its child.
+/// A macro to insert current node to the graph and visit its child. The attributes of an
+/// expression node are inserted as its first children.
 macro_rules! insert_and_visit {
     ($self:ident, $ast_node_variant:ident, $ast_node_var:ident, $visit_fn:ident) => {
         let ast_node = AstNode::$ast_node_variant($ast_node_var);
-        let node_index = $self.syntax_tree.add_node(ast_node);
+        let attrs = expr_attrs(&ast_node);
+        let node_index = $self.graph.add_node(ast_node);
+        for attr in attrs {
+            let attr_index = $self.graph.add_node(AstNode::Attribute(attr));
+            $self.graph.add_edge(node_index, attr_index, ());
+        }
 
         let parent_node = $self.current_node;
 
         if let Some(parent_node) = $self.current_node {
-            $self.syntax_tree.add_edge(parent_node, node_index);
+            $self.graph.add_edge(parent_node, node_index, ());
         }
 
         $self.current_node = Some(node_index);
@@ -106,17 +146,99 @@ macro_rules! insert_and_visit {
     };
 }
 
+impl<'a> GraphBuilder<'a> {
+    /// Inserts the node under the current one with operand children for `operands`, which are
+    /// visited in turn.
+    fn insert_with_operands(
+        &mut self,
+        ast_node: AstNode<'a>,
+        operands: impl Iterator<Item = &'a Expr>,
+    ) {
+        let node_index = self.graph.add_node(ast_node);
+        let parent_node = self.current_node;
+        if let Some(parent_node) = parent_node {
+            self.graph.add_edge(parent_node, node_index, ());
+        }
+        self.current_node = Some(node_index);
+        for operand in operands {
+            insert_and_visit!(self, Operand, operand, visit_expr);
+        }
+        self.current_node = parent_node;
+    }
+}
+
 impl<'a> Visit<'a> for GraphBuilder<'a> {
     fn visit_file(&mut self, file: &'a syn::File) {
         insert_and_visit!(self, SourceRoot, file, visit_file);
         // We inserted source root, the only node in the graph is the source root.
-        let root_node = self.syntax_tree.graph.node_indices().next();
+        let root_node = self.graph.node_indices().next();
         self.root_node = root_node;
     }
     fn visit_item(&mut self, item: &'a syn::Item) {
+        if let Item::Verbatim(tokens) = item {
+            self.record_unsupported(tokens.span(), "item syn does not interpret");
+        }
         insert_and_visit!(self, Item, item, visit_item);
     }
 
+    fn visit_impl_item(&mut self, impl_item: &'a ImplItem) {
+        if let ImplItem::Verbatim(tokens) = impl_item {
+            self.record_unsupported(tokens.span(), "impl item syn does not interpret");
+        }
+        visit::visit_impl_item(self, impl_item);
+    }
+
+    fn visit_trait_item(&mut self, trait_item: &'a TraitItem) {
+        if let TraitItem::Verbatim(tokens) = trait_item {
+            self.record_unsupported(tokens.span(), "trait item syn does not interpret");
+        }
+        visit::visit_trait_item(self, trait_item);
+    }
+
+    fn visit_foreign_item(&mut self, foreign_item: &'a ForeignItem) {
+        if let ForeignItem::Verbatim(tokens) = foreign_item {
+            self.record_unsupported(tokens.span(), "foreign item syn does not interpret");
+        }
+        visit::visit_foreign_item(self, foreign_item);
+    }
+
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        if let Expr::Verbatim(tokens) = expr {
+            self.record_unsupported(tokens.span(), "expression syn does not interpret");
+        }
+        visit::visit_expr(self, expr);
+    }
+
+    fn visit_type(&mut self, ty: &'a Type) {
+        if let Type::Verbatim(tokens) = ty {
+            self.record_unsupported(tokens.span(), "type syn does not interpret");
+        }
+        visit::visit_type(self, ty);
+    }
+
+    fn visit_pat(&mut self, pat: &'a Pat) {
+        if let Pat::Verbatim(tokens) = pat {
+            self.record_unsupported(tokens.span(), "pattern syn does not interpret");
+        }
+        visit::visit_pat(self, pat);
+    }
+
+    /// The tokens of a macro are not parsed, only the arguments of the invocations are reduced,
+    /// by a pass. The bodies of macro definitions and the operands of inline assembly are left
+    /// as is.
+    fn visit_macro(&mut self, mac: &'a syn::Macro) {
+        if mac.path.is_ident("macro_rules") {
+            self.record_unsupported(mac.span(), "body of a macro definition");
+        } else if mac.path.segments.last().is_some_and(|segment| {
+            ["asm", "global_asm", "naked_asm"]
+                .iter()
+                .any(|name| segment.ident == name)
+        }) {
+            self.record_unsupported(mac.span(), "inline assembly");
+        }
+        visit::visit_macro(self, mac);
+    }
+
     fn visit_item_fn(&mut self, item_fn: &'a syn::ItemFn) {
         insert_and_visit!(self, ItemFn, item_fn, visit_item_fn);
     }
@@ -140,6 +262,90 @@ impl<'a> Visit<'a> for GraphBuilder<'a> {
     fn visit_expr_let(&mut self, let_expr: &'a syn::ExprLet) {
         insert_and_visit!(self, ExprLet, let_expr, visit_expr_let);
     }
+
+    fn visit_expr_if(&mut self, expr_if: &'a syn::ExprIf) {
+        if is_let_condition(&expr_if.cond) {
+            insert_and_visit!(self, ExprIfLet, expr_if, visit_expr_if);
+        } else {
+            visit::visit_expr_if(self, expr_if);
+        }
+    }
+
+    fn visit_expr_match(&mut self, expr_match: &'a syn::ExprMatch) {
+        insert_and_visit!(self, ExprMatch, expr_match, visit_expr_match);
+    }
+
+    fn visit_arm(&mut self, arm: &'a syn::Arm) {
+        insert_and_visit!(self, Arm, arm, visit_arm);
+    }
+
+    fn visit_expr_binary(&mut self, expr_binary: &'a syn::ExprBinary) {
+        if !self.holds_expr(
+            |expr| matches!(expr, Expr::Binary(held) if std::ptr::eq(held, expr_binary)),
+        ) {
+            visit::visit_expr_binary(self, expr_binary);
+            return;
+        }
+        self.insert_with_operands(
+            AstNode::ExprBinary(expr_binary),
+            [&*expr_binary.left, &*expr_binary.right].into_iter(),
+        );
+    }
+
+    fn visit_expr_unary(&mut self, expr_unary: &'a syn::ExprUnary) {
+        if self
+            .holds_expr(|expr| matches!(expr, Expr::Unary(held) if std::ptr::eq(held, expr_unary)))
+        {
+            insert_and_visit!(self, ExprUnary, expr_unary, visit_expr_unary);
+        } else {
+            visit::visit_expr_unary(self, expr_unary);
+        }
+    }
+
+    fn visit_expr_struct(&mut self, expr_struct: &'a syn::ExprStruct) {
+        if self.holds_expr(
+            |expr| matches!(expr, Expr::Struct(held) if std::ptr::eq(held, expr_struct)),
+        ) {
+            insert_and_visit!(self, ExprStruct, expr_struct, visit_expr_struct);
+        } else {
+            visit::visit_expr_struct(self, expr_struct);
+        }
+    }
+
+    fn visit_field_value(&mut self, field_value: &'a syn::FieldValue) {
+        let in_struct = self.current_node.is_some_and(|current_node| {
+            matches!(
+                self.graph[current_node],
+                AstNode::ExprStruct(expr_struct)
+                    if expr_struct.fields.iter().any(|field| std::ptr::eq(field, field_value))
+            )
+        });
+        if in_struct {
+            insert_and_visit!(self, FieldValue, field_value, visit_field_value);
+        } else {
+            visit::visit_field_value(self, field_value);
+        }
+    }
+
+    fn visit_expr_tuple(&mut self, expr_tuple: &'a syn::ExprTuple) {
+        if !expr_tuple.elems.is_empty()
+            && self.holds_expr(
+                |expr| matches!(expr, Expr::Tuple(held) if std::ptr::eq(held, expr_tuple)),
+            )
+        {
+            self.insert_with_operands(AstNode::ExprTuple(expr_tuple), expr_tuple.elems.iter());
+        } else {
+            visit::visit_expr_tuple(self, expr_tuple);
+        }
+    }
+
+    fn visit_expr_while(&mut self, expr_while: &'a syn::ExprWhile) {
+        if is_let_condition(&expr_while.cond) {
+            insert_and_visit!(self, ExprWhileLet, expr_while, visit_expr_while);
+        } else {
+            visit::visit_expr_while(self, expr_while);
+        }
+    }
 }
 
 // TODO: Testing infra is very inefficient. Both from dev ex and performance perspectives (lots of
@@ -148,7 +354,7 @@ impl<'a> Visit<'a> for GraphBuilder<'a> {
 mod tests {
     use crate::parser::{AbstractSyntaxTree, AstNode};
 
-    use super::{GraphBuilder, SyntaxTree};
+    use super::GraphBuilder;
     use syn::visit::Visit;
 
     #[derive(Debug, PartialEq, Eq)]
@@ -161,6 +367,17 @@ mod tests {
         ExprArray,
         ExprAssign,
         ExprLet,
+        ExprIfLet,
+        ExprWhileLet,
+        ExprMatch,
+        Arm,
+        Attribute,
+        ExprBinary,
+        Operand,
+        ExprUnary,
+        ExprStruct,
+        FieldValue,
+        ExprTuple,
     }
 
     impl From<AstNode<'_>> for ASTNodeType {
@@ -174,6 +391,17 @@ mod tests {
                 AstNode::ExprArray(_) => ASTNodeType::ExprArray,
                 AstNode::ExprAssign(_) => ASTNodeType::ExprAssign,
                 AstNode::ExprLet(_) => ASTNodeType::ExprLet,
+                AstNode::ExprIfLet(_) => ASTNodeType::ExprIfLet,
+                AstNode::ExprWhileLet(_) => ASTNodeType::ExprWhileLet,
+                AstNode::ExprMatch(_) => ASTNodeType::ExprMatch,
+                AstNode::Arm(_) => ASTNodeType::Arm,
+                AstNode::Attribute(_) => ASTNodeType::Attribute,
+                AstNode::ExprBinary(_) => ASTNodeType::ExprBinary,
+                AstNode::Operand(_) => ASTNodeType::Operand,
+                AstNode::ExprUnary(_) => ASTNodeType::ExprUnary,
+                AstNode::ExprStruct(_) => ASTNodeType::ExprStruct,
+                AstNode::FieldValue(_) => ASTNodeType::FieldValue,
+                AstNode::ExprTuple(_) => ASTNodeType::ExprTuple,
             }
         }
     }
@@ -182,13 +410,12 @@ mod tests {
     fn leaf_nodes(ast: &AbstractSyntaxTree) -> Vec<ASTNodeType> {
         use petgraph::visit::Dfs;
 
-        let mut syntax_tree = SyntaxTree::new();
-        let mut graph_builder = GraphBuilder::new(&mut syntax_tree, None, None);
+        let mut graph_builder = GraphBuilder::new();
         let file = ast.clone().syn_file();
 
         // Construct the graph by visiting the entire file.
         graph_builder.visit_file(&file);
-        let graph = &graph_builder.syntax_tree.graph;
+        let graph = &graph_builder.graph;
 
         // Find SourceRoot.
         let source_root = graph
@@ -215,13 +442,12 @@ mod tests {
         let parsed_ast = AbstractSyntaxTree::parse(test_code);
         let file = parsed_ast.clone().syn_file();
 
-        let mut syntax_tree = SyntaxTree::new();
-        let mut graph_builder = GraphBuilder::new(&mut syntax_tree, None, None);
+        let mut graph_builder = GraphBuilder::new();
         graph_builder.visit_file(&file);
 
-        let graph = graph_builder.syntax_tree;
+        let graph = graph_builder.graph;
         // root -> item -> item_fn -> block
-        assert_eq!(graph.graph.node_count(), 4);
+        assert_eq!(graph.node_count(), 4);
 
         let leaf_node_types = leaf_nodes(&parsed_ast);
         let expected_leaf_node_types = vec![
@@ -241,14 +467,13 @@ fn main() {}"#;
         let parsed_ast = AbstractSyntaxTree::parse(test_code);
         let file = parsed_ast.clone().syn_file();
 
-        let mut syntax_tree = SyntaxTree::new();
-        let mut graph_builder = GraphBuilder::new(&mut syntax_tree, None, None);
+        let mut graph_builder = GraphBuilder::new();
         graph_builder.visit_file(&file);
 
-        let graph = graph_builder.syntax_tree;
+        let graph = graph_builder.graph;
         // root -> item -> item_fn -> block
         //     |-> item_fn -> block
-        assert_eq!(graph.graph.node_count(), 7);
+        assert_eq!(graph.node_count(), 7);
 
         let leaf_node_types = leaf_nodes(&parsed_ast);
         let expected_leaf_node_types = vec![
@@ -273,14 +498,13 @@ fn test_fn() {
         let parsed_ast = AbstractSyntaxTree::parse(test_code);
         let file = parsed_ast.clone().syn_file();
 
-        let mut syntax_tree = SyntaxTree::new();
-        let mut graph_builder = GraphBuilder::new(&mut syntax_tree, None, None);
+        let mut graph_builder = GraphBuilder::new();
         graph_builder.visit_file(&file);
 
-        let graph = graph_builder.syntax_tree;
+        let graph = graph_builder.graph;
         // root->item->item_fn->block->local_stmt->expr_assign
         //                          |->expr_array
-        assert_eq!(graph.graph.node_count(), 7);
+        assert_eq!(graph.node_count(), 7);
 
         let leaf_node_types = leaf_nodes(&parsed_ast);
         let expected_leaf_node_types = vec![
@@ -294,4 +518,87 @@ fn test_fn() {
         ];
         assert_eq!(leaf_node_types, expected_leaf_node_types)
     }
+
+    #[test]
+    fn graph_if_let_while_let() {
+        let test_code = r#"
+fn test_fn(a: Option<u32>) {
+    if let Some(b) = a {
+        let c = b;
+    }
+    while let Some(b) = a {}
+}"#;
+        let parsed_ast = AbstractSyntaxTree::parse(test_code);
+
+        let leaf_node_types = leaf_nodes(&parsed_ast);
+        let expected_leaf_node_types = vec![
+            ASTNodeType::SourceRoot,
+            ASTNodeType::Item,
+            ASTNodeType::ItemFn,
+            ASTNodeType::Block,
+            ASTNodeType::ExprIfLet,
+            ASTNodeType::ExprLet,
+            ASTNodeType::Block,
+            ASTNodeType::LocalStmt,
+            ASTNodeType::ExprWhileLet,
+            ASTNodeType::ExprLet,
+            ASTNodeType::Block,
+        ];
+        assert_eq!(leaf_node_types, expected_leaf_node_types)
+    }
+
+    #[test]
+    fn graph_binary_unary_operands() {
+        let test_code = r#"
+fn test_fn(a: u32) {
+    let b = a + -f(a);
+    g(a * 2);
+}"#;
+        let parsed_ast = AbstractSyntaxTree::parse(test_code);
+
+        // The operation in the call argument is not modeled.
+        let leaf_node_types = leaf_nodes(&parsed_ast);
+        let expected_leaf_node_types = vec![
+            ASTNodeType::SourceRoot,
+            ASTNodeType::Item,
+            ASTNodeType::ItemFn,
+            ASTNodeType::Block,
+            ASTNodeType::LocalStmt,
+            ASTNodeType::ExprBinary,
+            ASTNodeType::Operand,
+            ASTNodeType::Operand,
+            ASTNodeType::ExprUnary,
+        ];
+        assert_eq!(leaf_node_types, expected_leaf_node_types)
+    }
+
+    #[test]
+    fn graph_records_unsupported_constructs() {
+        let test_code = r#"
+macro_rules! id {
+    ($e:expr) => { $e };
+}
+fn declared();
+fn main() {
+    unsafe { core::arch::asm!("nop") };
+}"#;
+        let file = AbstractSyntaxTree::parse(test_code).syn_file();
+
+        let mut graph_builder = GraphBuilder::new();
+        graph_builder.visit_file(&file);
+
+        let unsupported = graph_builder
+            .unsupported()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unsupported,
+            vec![
+                "2:1: body of a macro definition",
+                "5:1: item syn does not interpret",
+                "7:14: inline assembly",
+            ]
+        );
+    }
 }
@@ -0,0 +1,247 @@
+//! Guard keeping the outcomes of the test suite of the target across the accepted reductions, so
+//! that the minimized crate keeps working as far as its tests tell.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use thiserror::Error;
+
+use crate::builder::{spawn_in_process_group, wait_with_timeout};
+
+/// Outcome of a single test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+impl std::fmt::Display for TestOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TestOutcome::Passed => write!(f, "passing"),
+            TestOutcome::Failed => write!(f, "failing"),
+            TestOutcome::Ignored => write!(f, "ignored"),
+        }
+    }
+}
+
+/// How the outcomes of the tests differ from the guarded ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestChange {
+    NotBuilt,
+    TimedOut,
+    /// The outcome of the test changed, `None` if the test is gone.
+    Outcome(String, TestOutcome, Option<TestOutcome>),
+}
+
+impl std::fmt::Display for TestChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TestChange::NotBuilt => write!(f, "tests no longer build"),
+            TestChange::TimedOut => write!(f, "tests timed out"),
+            TestChange::Outcome(name, expected, Some(outcome)) => {
+                write!(f, "test {name} went from {expected} to {outcome}")
+            }
+            TestChange::Outcome(name, _, None) => write!(f, "test {name} is gone"),
+        }
+    }
+}
+
+/// A run of the test suite.
+enum TestRun {
+    /// The tests ran, with the outcome of every test by name.
+    Ran(BTreeMap<String, TestOutcome>),
+    /// The tests did not build.
+    NotBuilt,
+    /// The tests were killed after the timeout.
+    TimedOut,
+}
+
+#[derive(Error, Debug)]
+pub enum GuardError {
+    #[error("IO error emitted from test guard: {0}")]
+    IOError(std::io::Error),
+    #[error("The test suite of the original target does not build, it cannot be guarded")]
+    NotBuilt,
+    #[error("The test suite of the original target timed out, raise --run-timeout to guard it")]
+    TimedOut,
+    #[error("The test suite of the original target has no test matching the filters")]
+    NoTests,
+}
+
+impl From<std::io::Error> for GuardError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IOError(value)
+    }
+}
+
+/// Runs the test suite of the target, or the tests matching the filters, and compares their
+/// outcomes to the ones of the original target.
+pub struct TestGuard {
+    path: PathBuf,
+    /// Filters passed to the test harness, every test runs if empty.
+    filters: Vec<String>,
+    /// Time the tests are given to run once built.
+    timeout: Duration,
+    expected: BTreeMap<String, TestOutcome>,
+}
+
+impl TestGuard {
+    /// Runs the tests of the original target, recording the outcomes to keep.
+    pub fn new(path: &Path, filters: Vec<String>, timeout: Duration) -> Result<Self, GuardError> {
+        let mut guard = Self {
+            path: path.to_path_buf(),
+            filters,
+            timeout,
+            expected: BTreeMap::new(),
+        };
+        guard.expected = match guard.run()? {
+            TestRun::Ran(outcomes) if outcomes.is_empty() => return Err(GuardError::NoTests),
+            TestRun::Ran(outcomes) => outcomes,
+            TestRun::NotBuilt => return Err(GuardError::NotBuilt),
+            TestRun::TimedOut => return Err(GuardError::TimedOut),
+        };
+        Ok(guard)
+    }
+
+    /// Number of tests guarded.
+    pub fn test_count(&self) -> usize {
+        self.expected.len()
+    }
+
+    /// Runs the tests of the current state of the target, returning how their outcomes differ from
+    /// the original ones, `None` if they do not.
+    pub fn check(&self) -> Result<Option<TestChange>, std::io::Error> {
+        let outcomes = match self.run()? {
+            TestRun::Ran(outcomes) => outcomes,
+            TestRun::NotBuilt => return Ok(Some(TestChange::NotBuilt)),
+            TestRun::TimedOut => return Ok(Some(TestChange::TimedOut)),
+        };
+        Ok(changed_outcome(&self.expected, &outcomes))
+    }
+
+    fn run(&self) -> Result<TestRun, std::io::Error> {
+        // The tests are built first so that the timeout only applies to running them.
+        let built = Command::new("cargo")
+            .current_dir(&self.path)
+            .args(["test", "--no-run", "--quiet"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?
+            .success();
+        if !built {
+            return Ok(TestRun::NotBuilt);
+        }
+
+        // Cargo runs the test binaries in its process group, they are killed with it on timeout.
+        let child = spawn_in_process_group(
+            Command::new("cargo")
+                .current_dir(&self.path)
+                .args(["test", "--no-fail-fast", "--"])
+                .args(&self.filters)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped()),
+        )?;
+        let output = wait_with_timeout(child, self.timeout)?;
+        if output.timed_out {
+            return Ok(TestRun::TimedOut);
+        }
+        Ok(TestRun::Ran(parse_test_outcomes(&output.stdout)))
+    }
+}
+
+/// Parses the `test <name> ... <outcome>` lines printed by the test harness.
+fn parse_test_outcomes(stdout: &str) -> BTreeMap<String, TestOutcome> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let (name, outcome) = line.strip_prefix("test ")?.rsplit_once(" ... ")?;
+            let outcome = match outcome {
+                "ok" => TestOutcome::Passed,
+                "FAILED" => TestOutcome::Failed,
+                // Ignored tests may be followed by the reason they are ignored.
+                ignored if ignored.starts_with("ignored") => TestOutcome::Ignored,
+                _ => return None,
+            };
+            Some((name.to_owned(), outcome))
+        })
+        .collect()
+}
+
+/// Returns the change of the first expected test whose outcome changed or which is gone.
+fn changed_outcome(
+    expected: &BTreeMap<String, TestOutcome>,
+    outcomes: &BTreeMap<String, TestOutcome>,
+) -> Option<TestChange> {
+    expected.iter().find_map(|(name, expected)| {
+        let outcome = outcomes.get(name).copied();
+        (outcome != Some(*expected)).then(|| TestChange::Outcome(name.clone(), *expected, outcome))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{changed_outcome, parse_test_outcomes, GuardError, TestGuard, TestOutcome};
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn parse_and_compare_test_outcomes() {
+        let stdout = "\
+running 4 tests
+test tests::adds ... ok
+test tests::slow ... ignored, takes a minute
+test tests::overflows ... FAILED
+test src/lib.rs - add (line 3) ... ok
+
+failures:
+test result: FAILED. 2 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out
+";
+        let expected = parse_test_outcomes(stdout);
+        assert_eq!(expected.len(), 4);
+        assert_eq!(expected["tests::slow"], TestOutcome::Ignored);
+        assert_eq!(expected["tests::overflows"], TestOutcome::Failed);
+        assert_eq!(expected["src/lib.rs - add (line 3)"], TestOutcome::Passed);
+        assert_eq!(changed_outcome(&expected, &expected), None);
+
+        let mut outcomes = expected.clone();
+        outcomes.insert("tests::adds".to_owned(), TestOutcome::Failed);
+        assert_eq!(
+            changed_outcome(&expected, &outcomes).map(|change| change.to_string()),
+            Some("test tests::adds went from passing to failing".to_owned())
+        );
+        outcomes.remove("src/lib.rs - add (line 3)");
+        assert_eq!(
+            changed_outcome(&expected, &outcomes).map(|change| change.to_string()),
+            Some("test src/lib.rs - add (line 3) is gone".to_owned())
+        );
+    }
+
+    #[test]
+    fn guard_times_out_on_sleeping_test() {
+        let test_dir = TestDir::new("guard");
+        let target = test_dir.path();
+        std::fs::create_dir_all(target.join("src")).unwrap();
+        std::fs::write(
+            target.join("Cargo.toml"),
+            "[package]\nname = \"sleeper\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            target.join("src/lib.rs"),
+            "#[test]\nfn sleeps() {\n    std::thread::sleep(std::time::Duration::from_secs(60));\n}\n",
+        )
+        .unwrap();
+
+        let start = Instant::now();
+        let guard = TestGuard::new(target, vec![], Duration::from_secs(1));
+        assert!(matches!(guard, Err(GuardError::TimedOut)));
+        // The test binary is killed along with cargo instead of running to its end.
+        assert!(start.elapsed() < Duration::from_secs(50));
+    }
+}
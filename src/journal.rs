@@ -0,0 +1,232 @@
+//! Journal of the reductions accepted during a minimization.
+//!
+//! Every accepted reduction is recorded as a checkpoint holding a snapshot of the minimized file,
+//! checkpoint 0 being the original file. The journal lives in the state of the session,
+//! `.ddebug/state/journal` inside the target or `.ddebug/sessions/<name>/state/journal` for a
+//! named session, and is reset at the start of each minimization, unless it is resumed, so any
+//! intermediate state of the last run can be restored afterwards.
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::{
+    paths::{from_portable, portable},
+    session::Session,
+};
+
+/// Directory of the journal, relative to the state of the session.
+const JOURNAL_DIR: &str = "journal";
+/// Name of the index file listing the checkpoints.
+const INDEX_FILE: &str = "index";
+/// Prefix of the index line holding the journaled file.
+const FILE_PREFIX: &str = "file\t";
+
+#[derive(Error, Debug)]
+pub enum JournalError {
+    #[error("IO error emitted from journal: {0}")]
+    IOError(std::io::Error),
+    #[error("No journal found at {0}, run a minimization first")]
+    JournalNotFound(PathBuf),
+    #[error("Journal index is corrupted at line: {0}")]
+    CorruptedIndex(String),
+    #[error("Checkpoint {0} does not exist, the journal has {1} checkpoints")]
+    UnknownCheckpoint(usize, usize),
+}
+
+impl From<std::io::Error> for JournalError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IOError(value)
+    }
+}
+
+/// A recorded state of the minimized file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub index: usize,
+    pub description: String,
+}
+
+pub struct Journal {
+    target: PathBuf,
+    /// Directory of the checkpoints, in the state of the session.
+    dir: PathBuf,
+    /// Journaled file, relative to the target.
+    source_file: PathBuf,
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl Journal {
+    /// Starts a new journal for `source_file`, discarding the previous one. The original code is
+    /// recorded as checkpoint 0.
+    pub fn create(
+        session: &Session,
+        source_file: &Path,
+        original: &str,
+    ) -> Result<Self, JournalError> {
+        let dir = session.state_dir().join(JOURNAL_DIR);
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        session.create_dir(&dir)?;
+
+        let mut journal = Self {
+            target: session.target().to_path_buf(),
+            dir,
+            source_file: source_file.to_path_buf(),
+            checkpoints: vec![],
+        };
+        journal.record(original, "original")?;
+        Ok(journal)
+    }
+
+    /// Opens the journal of the last minimization of the session.
+    pub fn open(session: &Session) -> Result<Self, JournalError> {
+        let dir = session.state_dir().join(JOURNAL_DIR);
+        let index = std::fs::read_to_string(dir.join(INDEX_FILE))
+            .map_err(|_| JournalError::JournalNotFound(dir.clone()))?;
+
+        let mut lines = index.lines();
+        let source_file = lines
+            .next()
+            .and_then(|line| line.strip_prefix(FILE_PREFIX))
+            .ok_or_else(|| JournalError::CorruptedIndex(String::new()))?;
+        let checkpoints = lines
+            .map(|line| {
+                line.split_once('\t')
+                    .and_then(|(index, description)| {
+                        Some(Checkpoint {
+                            index: index.parse().ok()?,
+                            description: description.to_owned(),
+                        })
+                    })
+                    .ok_or_else(|| JournalError::CorruptedIndex(line.to_owned()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            target: session.target().to_path_buf(),
+            dir,
+            source_file: from_portable(source_file),
+            checkpoints,
+        })
+    }
+
+    /// Records a new state of the file, returning the index of its checkpoint.
+    pub fn record(&mut self, code: &str, description: &str) -> Result<usize, JournalError> {
+        let index = self.checkpoints.len();
+        std::fs::write(self.snapshot_path(index), code)?;
+        self.checkpoints.push(Checkpoint {
+            index,
+            description: description.to_owned(),
+        });
+        self.write_index()?;
+        Ok(index)
+    }
+
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+
+    /// Journaled file, relative to the target.
+    pub fn source_file(&self) -> &Path {
+        &self.source_file
+    }
+
+    /// Restores the journaled file to the state recorded at checkpoint `index`.
+    pub fn rollback(&self, index: usize) -> Result<(), JournalError> {
+        std::fs::write(self.target.join(&self.source_file), self.snapshot(index)?)?;
+        Ok(())
+    }
+
+    /// Returns the code recorded at checkpoint `index`.
+    pub fn snapshot(&self, index: usize) -> Result<String, JournalError> {
+        if index >= self.checkpoints.len() {
+            return Err(JournalError::UnknownCheckpoint(
+                index,
+                self.checkpoints.len(),
+            ));
+        }
+        Ok(std::fs::read_to_string(self.snapshot_path(index))?)
+    }
+
+    /// Reads a copy saved with [`Journal::save_copy`], if there is one.
+    pub fn read_copy(&self, name: &str) -> Result<Option<String>, JournalError> {
+        match std::fs::read_to_string(self.dir.join(name)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Keeps a copy of some state of the minimization other than the journaled file, such as the
+    /// toolchain it runs with, next to the checkpoints.
+    pub fn save_copy(&self, name: &str, contents: &[u8]) -> Result<PathBuf, JournalError> {
+        let path = self.dir.join(name);
+        std::fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    fn snapshot_path(&self, index: usize) -> PathBuf {
+        self.dir.join(format!("{index}.rs"))
+    }
+
+    fn write_index(&self) -> Result<(), JournalError> {
+        let mut index = format!("{FILE_PREFIX}{}\n", portable(&self.source_file));
+        for checkpoint in &self.checkpoints {
+            index.push_str(&format!(
+                "{}\t{}\n",
+                checkpoint.index, checkpoint.description
+            ));
+        }
+        std::fs::write(self.dir.join(INDEX_FILE), index)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{Journal, JournalError};
+    use crate::{session::Session, test_dir::TestDir};
+
+    #[test]
+    fn journal_rollback_restores_checkpoint() {
+        let test_dir = TestDir::new("journal");
+        let target = test_dir.path();
+        std::fs::create_dir_all(target.join("src")).unwrap();
+        let source_file = Path::new("src/main.rs");
+
+        let session = Session::new(target);
+
+        let original = "fn main() { let a = 0; let b = 0; }";
+        let mut journal = Journal::create(&session, source_file, original).unwrap();
+        journal
+            .record("fn main() { let b = 0; }", "removed loc_stmt")
+            .unwrap();
+        journal.record("fn main() {}", "removed loc_stmt").unwrap();
+        std::fs::write(target.join(source_file), "fn main() {}").unwrap();
+
+        // A named session keeps its own journal.
+        let named = Session::new(target).named(Some("other"));
+        assert!(matches!(
+            Journal::open(&named),
+            Err(JournalError::JournalNotFound(_))
+        ));
+        Journal::create(&named, source_file, original).unwrap();
+        assert_eq!(Journal::open(&named).unwrap().checkpoints().len(), 1);
+
+        let journal = Journal::open(&session).unwrap();
+        assert_eq!(journal.checkpoints().len(), 3);
+        assert_eq!(journal.source_file(), source_file);
+
+        journal.rollback(1).unwrap();
+        let restored = std::fs::read_to_string(target.join(source_file)).unwrap();
+        assert_eq!(restored, "fn main() { let b = 0; }");
+
+        assert!(matches!(
+            journal.rollback(3),
+            Err(JournalError::UnknownCheckpoint(3, 3))
+        ));
+    }
+}
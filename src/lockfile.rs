@@ -0,0 +1,243 @@
+//! Dependency versions resolved in the `Cargo.lock` of the target.
+//!
+//! Only the `[[package]]` tables are read, which is all the reproduction needs to pin the
+//! versions it was verified against. The packages are also snapshotted when a minimization
+//! starts, so that a change of the resolution during the run, e.g. by a `cargo update`, is noticed
+//! before it silently changes the baseline of the oracle.
+use std::path::{Path, PathBuf};
+
+use crate::manifest::workspace_root;
+
+/// A package resolved by cargo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// Registry or repository of the package, `None` for the packages of the workspace.
+    pub source: Option<String>,
+}
+
+impl LockedPackage {
+    /// Returns the dependency line pinning this exact version, in `Cargo.toml` syntax.
+    pub fn pinned_requirement(&self) -> String {
+        format!("{} = \"={}\"", self.name, self.version)
+    }
+}
+
+/// Returns the path of the lockfile of the target, the one at the root of its workspace if it is
+/// a member of one.
+pub fn lockfile_path(target: &Path) -> PathBuf {
+    let target = std::path::absolute(target).unwrap_or_else(|_| target.to_path_buf());
+    workspace_root(&target)
+        .unwrap_or(&target)
+        .join("Cargo.lock")
+}
+
+/// Returns the packages resolved in the lockfile of the target, coming from outside of the
+/// workspace, `None` if the target has no lockfile.
+pub fn locked_dependencies(target: &Path) -> Result<Option<Vec<LockedPackage>>, std::io::Error> {
+    match std::fs::read_to_string(lockfile_path(target)) {
+        Ok(lockfile) => Ok(Some(
+            parse_lockfile(&lockfile)
+                .into_iter()
+                .filter(|package| package.source.is_some())
+                .collect(),
+        )),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// The packages resolved in the lockfile of the target at some point, the ones of the workspace
+/// included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockfileSnapshot {
+    packages: Vec<LockedPackage>,
+}
+
+impl LockfileSnapshot {
+    /// Snapshots the lockfile of the target, or of its workspace, empty if there is none.
+    pub fn capture(target: &Path) -> Result<Self, std::io::Error> {
+        match std::fs::read_to_string(lockfile_path(target)) {
+            Ok(lockfile) => Ok(Self::parse(&lockfile)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn parse(lockfile: &str) -> Self {
+        let mut packages = parse_lockfile(lockfile);
+        packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        Self { packages }
+    }
+
+    /// Describes the packages resolved differently in `current`, such as `syn 2.0.48 -> 2.0.50`,
+    /// empty if the resolution did not change.
+    pub fn changes(&self, current: &Self) -> Vec<String> {
+        let versions = |snapshot: &Self, name: &str| {
+            snapshot
+                .packages
+                .iter()
+                .filter(|package| package.name == name)
+                .map(|package| package.version.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut names = self
+            .packages
+            .iter()
+            .chain(&current.packages)
+            .map(|package| package.name.as_str())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names.dedup();
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let (before, after) = (versions(self, name), versions(current, name));
+                match (before.is_empty(), after.is_empty()) {
+                    _ if before == after => None,
+                    (true, _) => Some(format!("{name} {after} added")),
+                    (_, true) => Some(format!("{name} {before} removed")),
+                    _ => Some(format!("{name} {before} -> {after}")),
+                }
+            })
+            .collect()
+    }
+}
+
+fn parse_lockfile(lockfile: &str) -> Vec<LockedPackage> {
+    let mut packages = vec![];
+    let mut current: Option<LockedPackage> = None;
+    for line in lockfile.lines().map(str::trim) {
+        if line.starts_with('[') {
+            packages.extend(current.take());
+            if line == "[[package]]" {
+                current = Some(LockedPackage {
+                    name: String::new(),
+                    version: String::new(),
+                    source: None,
+                });
+            }
+            continue;
+        }
+        let (Some(package), Some((key, value))) = (current.as_mut(), line.split_once('=')) else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').to_owned();
+        match key.trim() {
+            "name" => package.name = value,
+            "version" => package.version = value,
+            "source" => package.source = Some(value),
+            _ => {}
+        }
+    }
+    packages.extend(current);
+    packages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{locked_dependencies, parse_lockfile, LockedPackage, LockfileSnapshot};
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn parse_lockfile_packages() {
+        let lockfile = r#"# This file is automatically @generated by Cargo.
+version = 3
+
+[[package]]
+name = "repro"
+version = "0.1.0"
+dependencies = [
+ "syn",
+]
+
+[[package]]
+name = "syn"
+version = "2.0.48"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "0f3531638e407dfc0814761abb7c00a5b54992b849452a0646b7f65c9f770f3f"
+"#;
+
+        let packages = parse_lockfile(lockfile);
+
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].source, None);
+        assert_eq!(
+            packages[1],
+            LockedPackage {
+                name: "syn".to_owned(),
+                version: "2.0.48".to_owned(),
+                source: Some("registry+https://github.com/rust-lang/crates.io-index".to_owned()),
+            }
+        );
+        assert_eq!(packages[1].pinned_requirement(), "syn = \"=2.0.48\"");
+    }
+
+    #[test]
+    fn lockfile_snapshot_changes() {
+        let package = |name: &str, version: &str| {
+            format!("[[package]]\nname = \"{name}\"\nversion = \"{version}\"\n")
+        };
+        let before = LockfileSnapshot::parse(
+            &[
+                package("repro", "0.1.0"),
+                package("syn", "2.0.48"),
+                package("log", "0.4.20"),
+            ]
+            .concat(),
+        );
+        let after = LockfileSnapshot::parse(
+            &[
+                package("syn", "2.0.50"),
+                package("repro", "0.1.0"),
+                package("quote", "1.0.35"),
+            ]
+            .concat(),
+        );
+
+        assert!(before.changes(&before).is_empty());
+        assert_eq!(
+            before.changes(&after),
+            [
+                "log 0.4.20 removed",
+                "quote 1.0.35 added",
+                "syn 2.0.48 -> 2.0.50"
+            ]
+        );
+    }
+
+    #[test]
+    fn lockfile_of_workspace_member() {
+        let test_dir = TestDir::new("lockfile");
+        let workspace = test_dir.path();
+        let member = workspace.join("member");
+        std::fs::create_dir_all(&member).unwrap();
+        std::fs::write(
+            workspace.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"member\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            member.join("Cargo.toml"),
+            "[package]\nname = \"member\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        assert_eq!(locked_dependencies(&member).unwrap(), None);
+
+        std::fs::write(
+            workspace.join("Cargo.lock"),
+            "[[package]]\nname = \"member\"\nversion = \"0.1.0\"\n\n\
+             [[package]]\nname = \"log\"\nversion = \"0.4.20\"\nsource = \"registry\"\n",
+        )
+        .unwrap();
+        let dependencies = locked_dependencies(&member).unwrap().unwrap();
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(dependencies[0].pinned_requirement(), "log = \"=0.4.20\"");
+        assert_eq!(
+            LockfileSnapshot::capture(&member).unwrap().packages.len(),
+            2
+        );
+    }
+}
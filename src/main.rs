@@ -1,32 +1,337 @@
+/// Markers of the removed parts of the original in the minimized code.
+mod annotate;
+/// Batch minimization of every crate of a directory.
+mod batch;
 /// Code builder, builds the code using rust compiler.
 mod builder;
+/// Cancellation of a running search.
+mod cancel;
+/// Region queries, whether a region of a file can be removed while keeping the error.
+mod check;
 /// Command definining the CLI for ddebug-rs.
 mod command;
+/// Shell completion scripts generated from the CLI.
+mod completions;
+/// Search configuration, collected from the CLI arguments.
+mod config;
+/// Delta debugging minimization over sequences of elements.
+mod ddmin;
+/// UI test emitter, writes minimized code as a UI test fixture.
+mod emitter;
+/// Generated, vendored and ignored files of the target, which are never minimized.
+mod exclude;
+/// Frontier of the nodes left to try removing, optionally reordered by acceptance rates.
+mod frontier;
 /// Code generator, generates the code from syntax tree.
 mod generator;
 /// Graph generator, generates a (pet)graph (`SyntaxTree`) from the parsed AST.
 mod graph;
+/// Guard keeping the outcomes of the test suite of the target across the reductions.
+mod guard;
+/// Journal of the accepted reductions, used to roll back to intermediate states.
+mod journal;
+/// Dependency versions resolved in the lockfile of the target.
+mod lockfile;
+/// Targets and values read from the manifest of the target.
+mod manifest;
+/// Man page generated from the CLI.
+mod manpage;
+/// Progress snapshots of a minimization, for plotting reduction curves.
+mod metrics;
+/// Mutation testing, finding the mutants the test suite of the target does not kill.
+mod mutate;
+/// Oracles deciding whether a variant still reproduces the minimized behavior.
+mod oracle;
 /// Rust parser interface, using `syn` crate parse rust code into AST nodes.
 mod parser;
+/// Rewriting passes simplifying constructs that cannot just be removed.
+mod passes;
+/// Platform-independent form of the persisted and reported paths.
+mod paths;
+/// Output patterns matched against executed variants.
+mod pattern;
+/// Syntactic checks skipping the removals that cannot keep the error.
+mod prefilter;
+/// Printing of the code variants, formatted or verbatim from the source.
+mod printer;
+/// Named presets of the search options.
+mod profile;
+/// Dead-code finder, removing the code neither the build nor the tests need.
+mod prune;
 /// A node remover for the syntax tree.
 mod remover;
+/// HTML report of a minimization.
+mod report;
+/// Nodes found required by previous minimizations, skipped by the next ones.
+mod required;
 /// Actual searcher which searches input program space for unnecessary statements.
 mod searcher;
+/// Session directory of the target, where everything persisted about it is kept.
+mod session;
+/// Temporary directories of the tests.
+#[cfg(test)]
+mod test_dir;
+/// Snapshot of the rustc and cargo versions building the target.
+mod toolchain;
+/// Quick per-error triage, finding rough suspect regions of every error.
+mod triage;
+/// Scratch copies of the target project.
+mod workspace;
 
-use std::env::current_dir;
+use std::{env::current_dir, path::Path, thread::available_parallelism, time::Duration};
 
-use clap::Parser;
-use command::Args;
-use searcher::{ASTGuidedSearcher, Search};
+use cancel::CancelToken;
+
+use batch::{Batch, DEFAULT_REPORTS_DIR};
+use builder::CodeBuilder;
+use check::RegionCheck;
+use clap::{CommandFactory, Parser};
+use command::{Args, Command, SessionsCommand};
+use completions::completions;
+use config::SearchConfig;
+use emitter::UiTest;
+use journal::Journal;
+use manifest::manifest_dir;
+use manpage::manpage;
+use mutate::Mutate;
+use prune::Prune;
+use report::{HtmlReport, ReportFormat};
+use searcher::{ASTGuidedSearcher, Search, ARGUMENTS_COPY, ARGUMENTS_SEPARATOR, TOOLCHAIN_COPY};
+use session::Session;
+use toolchain::Toolchain;
+use triage::Triage;
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    let current_dir = current_dir()?;
-    let target_path = args.path.unwrap_or(current_dir);
+    let target_path = match &args.manifest_path {
+        Some(manifest_path) => manifest_dir(manifest_path).ok_or_else(|| {
+            anyhow::anyhow!("{} is not a Cargo.toml file", manifest_path.display())
+        })?,
+        None => args.path.clone().unwrap_or(current_dir()?),
+    };
+
+    let session = Session::new(&target_path).named(args.session.as_deref());
+
+    match args.command {
+        Some(Command::Rollback { to }) => rollback(&session, to),
+        Some(Command::Refine) => {
+            let journal = Journal::open(&session)?;
+            let config = SearchConfig::from(&args).refining(journal.source_file());
+            ASTGuidedSearcher::new(searcher::Target::Path(&target_path), config)
+                .search_with_cancel(watch_stop_requests(&target_path))?;
+            Ok(())
+        }
+        Some(Command::Batch {
+            ref dir,
+            time_budget,
+            ref reports,
+        }) => {
+            let reports_dir = reports
+                .clone()
+                .unwrap_or_else(|| dir.join(DEFAULT_REPORTS_DIR));
+            let time_budget = time_budget.map(Duration::from_secs);
+            let report =
+                Batch::new(dir, SearchConfig::from(&args), time_budget).run(&reports_dir)?;
+            print!("{report}");
+            println!("Reports written to {}", reports_dir.display());
+            Ok(())
+        }
+        Some(Command::Clean) => {
+            if session.clean()? {
+                println!("Removed {}", session.dir().display());
+            } else {
+                println!("Nothing to clean at {}", session.dir().display());
+            }
+            Ok(())
+        }
+        Some(Command::Mutate) => {
+            let report = Mutate::new(&target_path, SearchConfig::from(&args)).run()?;
+            print!("{report}");
+            Ok(())
+        }
+        Some(Command::Prune) => {
+            let report = Prune::new(&target_path, SearchConfig::from(&args)).run()?;
+            print!("{report}");
+            Ok(())
+        }
+        Some(Command::Stop) => {
+            if session.request_stop()? {
+                println!(
+                    "Asked the minimization of {} to stop",
+                    target_path.display()
+                );
+            } else {
+                println!("No minimization is running on {}", target_path.display());
+            }
+            Ok(())
+        }
+        Some(Command::Sessions { ref command }) => sessions(&args, &target_path, command),
+        Some(Command::Completions { shell }) => {
+            print!("{}", completions(shell, &mut Args::command()));
+            Ok(())
+        }
+        Some(Command::Manpage) => {
+            print!("{}", manpage(&mut Args::command()));
+            Ok(())
+        }
+        Some(Command::Triage { budget, jobs }) => {
+            let jobs = match jobs {
+                Some(jobs) => jobs,
+                None => available_parallelism()?.get(),
+            };
+            let report = Triage::new(&target_path, budget, jobs).run()?;
+            print!("{report}");
+            Ok(())
+        }
+        None => match (args.check_only, &args.target_file) {
+            (Some(region), Some(file)) => {
+                let report =
+                    RegionCheck::new(&target_path, file, region, args.check_budget).run()?;
+                print!("{report}");
+                Ok(())
+            }
+            _ => {
+                let config = SearchConfig {
+                    arguments: std::env::args().skip(1).collect(),
+                    ..SearchConfig::from(&args)
+                };
+                minimize(&args, config, &target_path)
+            }
+        },
+    }
+}
+
+fn minimize(args: &Args, config: SearchConfig, target_path: &Path) -> anyhow::Result<()> {
+    let session = Session::new(target_path).named(config.session.as_deref());
+    let searcher = ASTGuidedSearcher::new(searcher::Target::Path(target_path), config);
+    let minimized = searcher.search_with_cancel(watch_stop_requests(target_path))?;
+
+    let Some(minimized) = minimized else {
+        return Ok(());
+    };
+    if args.emit_ui_test.is_none() && args.report.is_none() {
+        return Ok(());
+    }
+
+    // Rebuild the minimized code to capture the exact stderr it produces.
+    let build_output = CodeBuilder::for_root_file(target_path, &minimized.source_file).build()?;
+    if let Some(ui_test_dir) = &args.emit_ui_test {
+        let ui_test = UiTest::new(&minimized, &build_output.stderr, target_path);
+        let source_path = ui_test.write(ui_test_dir)?;
+        println!("UI test written to {}", source_path.display());
+    }
+    if let Some(ReportFormat::Html) = args.report {
+        let report_path = HtmlReport::new(&minimized, &build_output.stderr).write(&session)?;
+        println!("Report written to {}", report_path.display());
+    }
 
-    let searcher = ASTGuidedSearcher::new(searcher::Target::Path(&target_path));
-    searcher.search()?;
+    Ok(())
+}
+
+/// Returns the token cancelled once `ddebug stop` is run on the target.
+fn watch_stop_requests(target_path: &Path) -> CancelToken {
+    let session = Session::new(target_path);
+    // The request of a minimization that ended before seeing it must not stop this one.
+    session.take_stop_request();
+    let cancel = CancelToken::new();
+    let watched = cancel.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_millis(500));
+        if session.take_stop_request() {
+            watched.cancel();
+            break;
+        }
+    });
+    cancel
+}
+
+fn rollback(session: &Session, to: Option<usize>) -> anyhow::Result<()> {
+    let journal = Journal::open(session)?;
+    match to {
+        Some(index) => {
+            journal.rollback(index)?;
+            println!(
+                "Restored {} to checkpoint {index}",
+                journal.source_file().display()
+            );
+        }
+        None => {
+            println!("Checkpoints of {}:", journal.source_file().display());
+            for checkpoint in journal.checkpoints() {
+                println!("{:>4}  {}", checkpoint.index, checkpoint.description);
+            }
+        }
+    }
+    Ok(())
+}
 
+fn sessions(args: &Args, target_path: &Path, command: &SessionsCommand) -> anyhow::Result<()> {
+    let sessions = Session::new(target_path);
+    match command {
+        SessionsCommand::List => {
+            let names = sessions.names()?;
+            if names.is_empty() {
+                println!("No named session in {}", sessions.dir().display());
+            }
+            for name in names {
+                let session = Session::new(target_path).named(Some(&name));
+                match Journal::open(&session) {
+                    Ok(journal) => println!(
+                        "{name:<16}  {}, {} checkpoints",
+                        journal.source_file().display(),
+                        journal.checkpoints().len()
+                    ),
+                    Err(_) => println!("{name:<16}  no journal"),
+                }
+            }
+        }
+        SessionsCommand::Show { name } => {
+            let session = Session::new(target_path).named(Some(name));
+            let journal = Journal::open(&session)?;
+            println!("Session {name} of {}", journal.source_file().display());
+            if let Some(toolchain) = journal.read_copy(TOOLCHAIN_COPY)? {
+                let Ok(toolchain) = toolchain.parse::<Toolchain>();
+                println!("Toolchain: {}", toolchain.rustc_version());
+            }
+            if let Some(arguments) = journal.read_copy(ARGUMENTS_COPY)? {
+                println!("Arguments: {}", arguments.replace(ARGUMENTS_SEPARATOR, " "));
+            }
+            println!("Checkpoints:");
+            for checkpoint in journal.checkpoints() {
+                println!("{:>4}  {}", checkpoint.index, checkpoint.description);
+            }
+        }
+        SessionsCommand::Delete { name } => {
+            sessions.delete(name)?;
+            println!("Deleted the session {name}");
+        }
+        SessionsCommand::Resume { name } => {
+            let session = Session::new(target_path).named(Some(name));
+            let journal = Journal::open(&session)?;
+            // The session goes on with the oracle and the options it was started with, the
+            // current ones only if it did not save them.
+            let saved_args = journal
+                .read_copy(ARGUMENTS_COPY)?
+                .map(|arguments| {
+                    Args::try_parse_from(
+                        std::iter::once(env!("CARGO_PKG_NAME")).chain(
+                            arguments
+                                .split(ARGUMENTS_SEPARATOR)
+                                .filter(|argument| !argument.is_empty()),
+                        ),
+                    )
+                })
+                .transpose()?;
+            let args = saved_args.as_ref().unwrap_or(args);
+            let config = SearchConfig {
+                session: Some(name.clone()),
+                resume: true,
+                target_file: Some(journal.source_file().to_path_buf()),
+                ..SearchConfig::from(args)
+            };
+            return minimize(args, config, target_path);
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,144 @@
+//! Values read from the `Cargo.toml` of the target.
+//!
+//! Only the few keys the minimization needs are read, with a line based reader handling the
+//! string and array values of the usual manifest layout, one key per line.
+use std::path::{Path, PathBuf};
+
+/// Root of the library target unless the manifest gives another path.
+const DEFAULT_LIB_PATH: &str = "src/lib.rs";
+
+/// The library target of the package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibTarget {
+    /// Root file of the library, relative to the target.
+    pub path: PathBuf,
+    /// Crate types of the `[lib]` table, such as `cdylib` or `staticlib`, empty if not given.
+    pub crate_types: Vec<String>,
+}
+
+impl LibTarget {
+    /// Reads the library target of the package, `None` if it has none.
+    pub fn load(target: &Path) -> Option<Self> {
+        let manifest = std::fs::read_to_string(target.join("Cargo.toml")).unwrap_or_default();
+        let path = string_value(&manifest, "lib", "path")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_LIB_PATH));
+        let path = path.strip_prefix(".").unwrap_or(&path).to_path_buf();
+        target.join(&path).is_file().then(|| Self {
+            path,
+            crate_types: array_value(&manifest, "lib", "crate-type").unwrap_or_default(),
+        })
+    }
+}
+
+/// Returns the directory of the manifest, `None` if the path is not the one of a `Cargo.toml`.
+pub fn manifest_dir(manifest_path: &Path) -> Option<PathBuf> {
+    if manifest_path.file_name()? != "Cargo.toml" || !manifest_path.is_file() {
+        return None;
+    }
+    match manifest_path.parent()? {
+        dir if dir.as_os_str().is_empty() => Some(PathBuf::from(".")),
+        dir => Some(dir.to_path_buf()),
+    }
+}
+
+/// Returns the root of the workspace the package in `dir` belongs to, the closest directory
+/// holding it whose manifest has a `[workspace]` table. `dir` has to be absolute.
+pub fn workspace_root(dir: &Path) -> Option<&Path> {
+    dir.ancestors().find(|dir| {
+        std::fs::read_to_string(dir.join("Cargo.toml"))
+            .is_ok_and(|manifest| manifest.lines().any(|line| line.trim() == "[workspace]"))
+    })
+}
+
+/// Returns the string value of the key in the table.
+pub fn string_value(manifest: &str, table: &str, key: &str) -> Option<String> {
+    let value = raw_value(manifest, table, key)?;
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .map(str::to_owned)
+}
+
+/// Returns the strings of the array value of the key in the table, which can span several lines.
+pub fn array_value(manifest: &str, table: &str, key: &str) -> Option<Vec<String>> {
+    let array = raw_value(manifest, table, key)?;
+    Some(
+        array
+            .split('"')
+            .skip(1)
+            .step_by(2)
+            .map(str::to_owned)
+            .collect(),
+    )
+}
+
+/// Returns the value of the key in the table as written, up to the closing bracket for arrays.
+fn raw_value(manifest: &str, table: &str, key: &str) -> Option<String> {
+    let header = format!("[{table}]");
+    let mut in_table = false;
+    let mut value = None::<String>;
+    for line in manifest.lines().map(str::trim) {
+        if let Some(value) = &mut value {
+            value.push_str(line);
+        } else if line.starts_with('[') {
+            in_table = line == header;
+        } else if let Some((line_key, line_value)) = line
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()))
+        {
+            if in_table && line_key == key {
+                value = Some(line_value.to_owned());
+            }
+        }
+        if value
+            .as_ref()
+            .is_some_and(|value| !value.starts_with('[') || value.contains(']'))
+        {
+            break;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{array_value, manifest_dir, string_value};
+
+    #[test]
+    fn manifest_values_from_their_table() {
+        let manifest = "[package]\nname = \"a\"\n\n[lib]\npath = \"src/ffi.rs\"\ncrate-type = [\n  \"cdylib\",\n  \"rlib\",\n]\n";
+
+        assert_eq!(
+            string_value(manifest, "lib", "path").as_deref(),
+            Some("src/ffi.rs")
+        );
+        assert_eq!(
+            array_value(manifest, "lib", "crate-type"),
+            Some(vec!["cdylib".to_owned(), "rlib".to_owned()])
+        );
+        assert_eq!(string_value(manifest, "package", "path"), None);
+    }
+
+    #[test]
+    fn manifest_dir_of_cargo_toml_only() {
+        let project_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test")
+            .join("data")
+            .join("test_project");
+
+        assert_eq!(
+            manifest_dir(&project_dir.join("Cargo.toml")),
+            Some(project_dir.clone())
+        );
+        assert_eq!(
+            manifest_dir(Path::new("Cargo.toml")),
+            Some(PathBuf::from("."))
+        );
+        assert_eq!(manifest_dir(&project_dir.join("src/main.rs")), None);
+        assert_eq!(manifest_dir(&project_dir.join("missing/Cargo.toml")), None);
+    }
+}
@@ -0,0 +1,121 @@
+//! Man page of the CLI in roff, generated from its definition.
+//!
+//! The page documents the options of the minimization first, then every subcommand with its own
+//! arguments, so that it stays in sync with `--help`.
+use clap::{Arg, Command};
+
+use crate::completions::{flags, possible_values, value_name};
+
+/// Returns the man page of the command.
+pub fn manpage(command: &mut Command) -> String {
+    command.build();
+    let name = command.get_name().to_owned();
+    let version = command.get_version().unwrap_or_default();
+
+    let mut page = format!(
+        ".TH {} 1 \"{name} {version}\"\n",
+        escape(&name.to_uppercase())
+    );
+    page.push_str(&format!(
+        ".SH NAME\n{} \\- {}\n",
+        escape(&name),
+        escape(&about(command))
+    ));
+    page.push_str(&format!(
+        ".SH SYNOPSIS\n\\fB{}\\fR [\\fIOPTIONS\\fR] [\\fICOMMAND\\fR]\n",
+        escape(&name)
+    ));
+    page.push_str(".SH OPTIONS\n");
+    for arg in command.get_arguments().filter(|arg| !arg.is_hide_set()) {
+        page.push_str(&argument(arg));
+    }
+    page.push_str(".SH COMMANDS\n");
+    for subcommand in command
+        .get_subcommands()
+        .filter(|subcommand| !subcommand.is_hide_set())
+    {
+        page.push_str(&format!(
+            ".SS {} {}\n{}\n",
+            escape(&name),
+            escape(subcommand.get_name()),
+            escape(&about(subcommand))
+        ));
+        for arg in subcommand
+            .get_arguments()
+            // The global options are documented once, with the options of the minimization.
+            .filter(|arg| !arg.is_hide_set() && !arg.is_global_set())
+        {
+            page.push_str(&argument(arg));
+        }
+    }
+    page
+}
+
+fn about(command: &Command) -> String {
+    command
+        .get_long_about()
+        .or(command.get_about())
+        .map(ToString::to_string)
+        .unwrap_or_default()
+}
+
+/// Returns the tagged paragraph documenting an argument.
+fn argument(arg: &Arg) -> String {
+    let mut tag = flags(arg)
+        .iter()
+        .map(|flag| format!("\\fB{}\\fR", escape(flag)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if arg.get_action().takes_values() {
+        if !tag.is_empty() {
+            tag.push(' ');
+        }
+        tag.push_str(&format!("\\fI{}\\fR", escape(&value_name(arg))));
+    }
+    let mut help = arg
+        .get_long_help()
+        .or(arg.get_help())
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    let values = possible_values(arg);
+    if !values.is_empty() {
+        help.push_str(&format!(" [possible values: {}]", values.join(", ")));
+    }
+    format!(".TP\n{tag}\n{}\n", escape(&help))
+}
+
+/// Escapes the text for roff, where backslashes and dashes are special and lines starting with a
+/// dot or a quote are requests.
+fn escape(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let line = line.replace('\\', "\\\\").replace('-', "\\-");
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{line}")
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::manpage;
+    use crate::command::Args;
+
+    #[test]
+    fn manpage_documents_options_and_subcommands() {
+        let page = manpage(&mut Args::command());
+
+        assert!(page.starts_with(".TH DDEBUG\\-RS 1 \"ddebug-rs 0.0.0\"\n"));
+        assert!(page.contains(".TP\n\\fB\\-p\\fR, \\fB\\-\\-path\\fR \\fIPATH\\fR\n"));
+        assert!(page.contains("[possible values: pretty, verbatim, mixed]"));
+        assert!(page.contains(".SS ddebug\\-rs triage\n"));
+        // The global path option is not repeated for every subcommand.
+        assert_eq!(page.matches("\\-\\-path").count(), 1);
+    }
+}
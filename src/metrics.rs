@@ -0,0 +1,123 @@
+//! Progress snapshots of a minimization, written to `.ddebug/metrics.csv` when asked for with
+//! `--metrics`, so that reduction curves can be plotted. Nothing leaves the machine.
+//!
+//! A row is written when the first variant is tested, then at most every
+//! [`METRICS_INTERVAL`], and once more when the minimization ends.
+use std::{
+    fs::File,
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use crate::session::Session;
+
+/// Columns of the metrics file.
+pub const METRICS_HEADER: &str =
+    "elapsed_secs,variants,accepted,builds_per_minute,acceptance_rate,code_size";
+
+/// Minimum time between two snapshots.
+pub const METRICS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// State of the minimization at some point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Number of variants tested, the original target included.
+    pub variants: usize,
+    /// Number of reductions accepted.
+    pub accepted: usize,
+    /// Size of the minimized code, ignoring whitespace.
+    pub code_size: usize,
+}
+
+pub struct Metrics {
+    file: File,
+    started: Instant,
+    last_snapshot: Option<Instant>,
+}
+
+impl Metrics {
+    /// Opens the metrics file of the session, continuing the previous one if `append` is set.
+    pub fn open(session: &Session, append: bool) -> Result<Self, std::io::Error> {
+        let mut file = session.open_metrics(append)?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{METRICS_HEADER}")?;
+        }
+        Ok(Self {
+            file,
+            started: Instant::now(),
+            last_snapshot: None,
+        })
+    }
+
+    /// Writes the snapshot if none was written during the last interval.
+    pub fn tick(&mut self, snapshot: &Snapshot) {
+        let due = self
+            .last_snapshot
+            .is_none_or(|last_snapshot| last_snapshot.elapsed() >= METRICS_INTERVAL);
+        if due {
+            self.record(snapshot);
+        }
+    }
+
+    /// Writes the snapshot.
+    pub fn record(&mut self, snapshot: &Snapshot) {
+        // The metrics are only informative, failing to write them does not stop the search.
+        let _ = writeln!(self.file, "{}", row(self.started.elapsed(), snapshot));
+        self.last_snapshot = Some(Instant::now());
+    }
+}
+
+/// Returns the row of the snapshot taken after `elapsed`.
+fn row(elapsed: Duration, snapshot: &Snapshot) -> String {
+    let minutes = elapsed.as_secs_f64() / 60.0;
+    let builds_per_minute = if minutes > 0.0 {
+        snapshot.variants as f64 / minutes
+    } else {
+        0.0
+    };
+    // The original target is not a candidate reduction.
+    let candidates = snapshot.variants.saturating_sub(1);
+    let acceptance_rate = if candidates > 0 {
+        snapshot.accepted as f64 / candidates as f64
+    } else {
+        0.0
+    };
+    format!(
+        "{:.1},{},{},{builds_per_minute:.1},{acceptance_rate:.3},{}",
+        elapsed.as_secs_f64(),
+        snapshot.variants,
+        snapshot.accepted,
+        snapshot.code_size
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{row, Snapshot};
+
+    #[test]
+    fn metrics_rows() {
+        let snapshot = Snapshot {
+            variants: 21,
+            accepted: 5,
+            code_size: 120,
+        };
+        assert_eq!(
+            row(Duration::from_secs(30), &snapshot),
+            "30.0,21,5,42.0,0.250,120"
+        );
+        assert_eq!(
+            row(
+                Duration::ZERO,
+                &Snapshot {
+                    variants: 1,
+                    accepted: 0,
+                    code_size: 120
+                }
+            ),
+            "0.0,1,0,0.0,0.000,120"
+        );
+    }
+}
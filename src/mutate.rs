@@ -0,0 +1,381 @@
+//! Mutation testing, finding the behavior of the target its test suite does not check.
+//!
+//! Small mutations are applied to the source files one at a time, inside a scratch copy of the
+//! target: statements are deleted through the syntax graph and the code generator like the
+//! searcher removes them, the conditions of `if` and `while` are negated, and binary operators are
+//! swapped. A mutant is killed if the outcome of a test changes, and unviable if the tests no
+//! longer build. The surviving mutants point at code no test depends on. The mutants are printed
+//! verbatim with the positions preserved, so that the rest of the file, and the line numbers in
+//! the names of its doctests, are untouched.
+use std::path::{Path, PathBuf};
+
+use quote::ToTokens;
+use syn::{spanned::Spanned, visit::Visit, visit_mut::VisitMut, BinOp, Expr, File};
+use thiserror::Error;
+
+use crate::{
+    config::SearchConfig,
+    generator::{CodeGenerator, CodeGeneratorError},
+    graph::GraphBuilder,
+    guard::{GuardError, TestChange, TestGuard},
+    parser::{AbstractSyntaxTree, AstNode, NodeKind},
+    paths::portable,
+    printer::{EmitMode, Printer},
+    prune::source_files,
+    remover::NodeRemover,
+    workspace::ScratchWorkspace,
+};
+
+#[derive(Error, Debug)]
+pub enum MutateError {
+    #[error("IO error emitted from mutate: {0}")]
+    IOError(std::io::Error),
+    #[error("Error while running the tests: {0}")]
+    GuardOperationError(GuardError),
+    #[error("Error while generating a mutant: {0}")]
+    MutantGeneration(CodeGeneratorError),
+}
+
+impl From<std::io::Error> for MutateError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IOError(value)
+    }
+}
+
+impl From<GuardError> for MutateError {
+    fn from(value: GuardError) -> Self {
+        Self::GuardOperationError(value)
+    }
+}
+
+impl From<CodeGeneratorError> for MutateError {
+    fn from(value: CodeGeneratorError) -> Self {
+        Self::MutantGeneration(value)
+    }
+}
+
+/// A mutation of a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mutant {
+    pub source_file: PathBuf,
+    /// Line of the mutated code in the original file.
+    pub line: usize,
+    pub description: String,
+    /// The mutated file.
+    code: String,
+}
+
+impl std::fmt::Display for Mutant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            portable(&self.source_file),
+            self.line,
+            self.description
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MutantOutcome {
+    /// The outcome of a test changed.
+    Killed(TestChange),
+    Survived,
+    /// The tests did not build with the mutant.
+    Unviable,
+}
+
+impl std::fmt::Display for MutantOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MutantOutcome::Killed(change) => write!(f, "killed ({change})"),
+            MutantOutcome::Survived => write!(f, "survived"),
+            MutantOutcome::Unviable => write!(f, "unviable"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MutationReport {
+    /// Number of tests run against every mutant.
+    pub tests: usize,
+    pub mutants: Vec<(Mutant, MutantOutcome)>,
+}
+
+impl MutationReport {
+    fn count(&self, matches: impl Fn(&MutantOutcome) -> bool) -> usize {
+        self.mutants
+            .iter()
+            .filter(|(_, outcome)| matches(outcome))
+            .count()
+    }
+}
+
+impl std::fmt::Display for MutationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let survived = self.count(|outcome| *outcome == MutantOutcome::Survived);
+        if survived > 0 {
+            writeln!(f, "Mutants not killed by the {} tests:", self.tests)?;
+            for (mutant, _) in self
+                .mutants
+                .iter()
+                .filter(|(_, outcome)| *outcome == MutantOutcome::Survived)
+            {
+                writeln!(f, "  {mutant}")?;
+            }
+        }
+        writeln!(
+            f,
+            "{} mutants: {} killed, {survived} survived, {} unviable",
+            self.mutants.len(),
+            self.count(|outcome| matches!(outcome, MutantOutcome::Killed(_))),
+            self.count(|outcome| *outcome == MutantOutcome::Unviable)
+        )
+    }
+}
+
+pub struct Mutate<'a> {
+    target: &'a Path,
+    config: SearchConfig,
+}
+
+impl<'a> Mutate<'a> {
+    /// Creates the mutation testing of the target file of `config`, or of every source file if
+    /// none is given, against the tests matching its guarded filters.
+    pub fn new(target: &'a Path, config: SearchConfig) -> Self {
+        Self { target, config }
+    }
+
+    /// Runs the tests against every mutant, leaving the target untouched.
+    pub fn run(&self) -> Result<MutationReport, MutateError> {
+        let workspace = ScratchWorkspace::copy_of(self.target, "mutate")?;
+        let guard = TestGuard::new(
+            workspace.path(),
+            self.config.guard_tests.clone().unwrap_or_default(),
+            self.config.run_timeout,
+        )?;
+        let source_files = match &self.config.target_file {
+            Some(target_file) => vec![target_file.clone()],
+            None => source_files(self.target)?,
+        };
+
+        let mut mutants = vec![];
+        for source_file in source_files {
+            let path = workspace.path().join(&source_file);
+            let source = std::fs::read_to_string(&path)?;
+            for mutant in mutants_of(&source_file, &source)? {
+                std::fs::write(&path, &mutant.code)?;
+                let outcome = match guard.check()? {
+                    Some(TestChange::NotBuilt) => MutantOutcome::Unviable,
+                    Some(change) => MutantOutcome::Killed(change),
+                    None => MutantOutcome::Survived,
+                };
+                println!("[{:>4}] {mutant}: {outcome}", mutants.len());
+                mutants.push((mutant, outcome));
+            }
+            std::fs::write(&path, &source)?;
+        }
+        Ok(MutationReport {
+            tests: guard.test_count(),
+            mutants,
+        })
+    }
+}
+
+/// Returns the mutants of the source file.
+fn mutants_of(source_file: &Path, source: &str) -> Result<Vec<Mutant>, CodeGeneratorError> {
+    let printer = Printer::new(EmitMode::Verbatim, true);
+    let file = AbstractSyntaxTree::parse(source).syn_file();
+    let mutant = |line: usize, description: String, code: String| Mutant {
+        source_file: source_file.to_path_buf(),
+        line,
+        description,
+        code,
+    };
+
+    let mut mutants = vec![];
+    let mut graph_builder = GraphBuilder::new();
+    graph_builder.visit_file(&file);
+    if let Some(root) = graph_builder.root_node() {
+        let graph = graph_builder.graph().clone();
+        let code_generator = CodeGenerator::new();
+        for node in graph.node_indices() {
+            if !is_deletable(&graph[node]) {
+                continue;
+            }
+            let mut mutated = graph.clone();
+            NodeRemover::remove_node(&mut mutated, node);
+            let code = printer.print(source, &code_generator.generate(&mutated, root)?);
+            let description = format!("deleted `{}`", graph[node].to_token_stream());
+            mutants.push(mutant(graph[node].span().start().line, description, code));
+        }
+    }
+
+    for (index, (line, description)) in negated_conditions(&mut file.clone(), None)
+        .into_iter()
+        .enumerate()
+    {
+        let mut mutated = file.clone();
+        negated_conditions(&mut mutated, Some(index));
+        mutants.push(mutant(line, description, printer.print(source, &mutated)));
+    }
+    for (index, (line, description)) in swapped_operators(&mut file.clone(), None)
+        .into_iter()
+        .enumerate()
+    {
+        let mut mutated = file.clone();
+        swapped_operators(&mut mutated, Some(index));
+        mutants.push(mutant(line, description, printer.print(source, &mutated)));
+    }
+    Ok(mutants)
+}
+
+/// Returns true if the statement can be deleted. The `let` statements are kept, the code using
+/// their bindings would not build without them.
+fn is_deletable(node: &AstNode<'_>) -> bool {
+    node.kinds().contains(&NodeKind::Stmt) && !matches!(node, AstNode::LocalStmt(_))
+}
+
+/// Enumerates the sites of a mutation, applying the mutation to the site with the index if one is
+/// given.
+struct Sites {
+    apply: Option<usize>,
+    /// Line and description of every site visited so far.
+    sites: Vec<(usize, String)>,
+}
+
+impl Sites {
+    /// Records a site, returning true if it is the one to mutate.
+    fn visit(&mut self, line: usize, description: String) -> bool {
+        let apply = self.apply == Some(self.sites.len());
+        self.sites.push((line, description));
+        apply
+    }
+}
+
+/// Negates the conditions of `if` and `while`, the `let` conditions aside.
+fn negated_conditions(file: &mut File, apply: Option<usize>) -> Vec<(usize, String)> {
+    struct Negate(Sites);
+
+    impl Negate {
+        fn negate(&mut self, cond: &mut Expr) {
+            if matches!(cond, Expr::Let(_)) {
+                return;
+            }
+            let description = format!("negated `{}`", cond.to_token_stream());
+            if self.0.visit(cond.span().start().line, description) {
+                *cond = syn::parse_quote!(!(#cond));
+            }
+        }
+    }
+
+    impl VisitMut for Negate {
+        fn visit_expr_if_mut(&mut self, expr_if: &mut syn::ExprIf) {
+            self.negate(&mut expr_if.cond);
+            syn::visit_mut::visit_expr_if_mut(self, expr_if);
+        }
+
+        fn visit_expr_while_mut(&mut self, expr_while: &mut syn::ExprWhile) {
+            self.negate(&mut expr_while.cond);
+            syn::visit_mut::visit_expr_while_mut(self, expr_while);
+        }
+    }
+
+    let mut negate = Negate(Sites {
+        apply,
+        sites: vec![],
+    });
+    negate.visit_file_mut(file);
+    negate.0.sites
+}
+
+/// Swaps the binary operators with their opposites, such as `+` with `-` or `<` with `>=`.
+fn swapped_operators(file: &mut File, apply: Option<usize>) -> Vec<(usize, String)> {
+    struct Swap(Sites);
+
+    impl VisitMut for Swap {
+        fn visit_bin_op_mut(&mut self, op: &mut BinOp) {
+            if let Some(swapped) = swapped(op) {
+                let description = format!(
+                    "replaced `{}` with `{}`",
+                    op.to_token_stream(),
+                    swapped.to_token_stream()
+                );
+                if self.0.visit(op.span().start().line, description) {
+                    *op = swapped;
+                }
+            }
+        }
+    }
+
+    let mut swap = Swap(Sites {
+        apply,
+        sites: vec![],
+    });
+    swap.visit_file_mut(file);
+    swap.0.sites
+}
+
+/// Returns the opposite of the operator, if it has one.
+fn swapped(op: &BinOp) -> Option<BinOp> {
+    use BinOp::*;
+    Some(match op {
+        Add(_) => Sub(Default::default()),
+        Sub(_) => Add(Default::default()),
+        Mul(_) => Div(Default::default()),
+        Div(_) => Mul(Default::default()),
+        And(_) => Or(Default::default()),
+        Or(_) => And(Default::default()),
+        Eq(_) => Ne(Default::default()),
+        Ne(_) => Eq(Default::default()),
+        Lt(_) => Ge(Default::default()),
+        Ge(_) => Lt(Default::default()),
+        Gt(_) => Le(Default::default()),
+        Le(_) => Gt(Default::default()),
+        AddAssign(_) => SubAssign(Default::default()),
+        SubAssign(_) => AddAssign(Default::default()),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::mutants_of;
+
+    #[test]
+    fn mutants_of_statements_conditions_and_operators() {
+        let source = "\
+fn count(items: &[u32]) -> u32 {
+    let mut total = 0;
+    for item in items {
+        if *item > 1 {
+            total = total + item;
+        }
+    }
+    total
+}
+";
+        let mutants = mutants_of(Path::new("src/lib.rs"), source).unwrap();
+        let descriptions = mutants
+            .iter()
+            .map(|mutant| format!("{}: {}", mutant.line, mutant.description))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            descriptions,
+            [
+                "5: deleted `total = total + item`",
+                "4: negated `* item > 1`",
+                "4: replaced `>` with `<=`",
+                "5: replaced `+` with `-`",
+            ]
+        );
+
+        // The rest of the file stays at its lines.
+        let swapped = &mutants[3].code;
+        assert_eq!(swapped.lines().count(), source.lines().count());
+        assert!(swapped.lines().nth(4).unwrap().contains("total - item"));
+    }
+}
@@ -0,0 +1,489 @@
+//! Oracles deciding whether a variant of the target is still interesting, i.e. still reproduces
+//! the behavior being minimized.
+use crate::{
+    builder::{BuildError, BuildErros, RunOutput},
+    pattern::OutputPattern,
+};
+
+/// Boolean expressions composing oracles.
+mod expression;
+
+pub use expression::OracleExpression;
+
+/// Everything observed while building, and if requested running, a variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantOutcome {
+    pub build_errors: BuildErros,
+    /// Output of the executed variant, only present if the oracle requires running and the
+    /// variant built successfully.
+    pub run_output: Option<RunOutput>,
+}
+
+impl VariantOutcome {
+    /// Short description of what was observed, the first error code for a failed build.
+    pub fn summary(&self) -> String {
+        if let Some(error) = self.build_errors.errors.first() {
+            return error.short();
+        }
+        match &self.run_output {
+            Some(run_output) if run_output.timed_out => "timed out".to_owned(),
+            Some(RunOutput {
+                exit_code: Some(exit_code),
+                ..
+            }) => format!("exit code {exit_code}"),
+            Some(_) => "killed".to_owned(),
+            None => "no errors".to_owned(),
+        }
+    }
+}
+
+/// Why a variant was not interesting.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Rejection {
+    /// The variant builds without the error kept.
+    ErrorDisappeared,
+    /// The first error is a different one, from the code or message of the kept error to the
+    /// code or message of the new one.
+    ErrorChanged(String, String),
+    /// The kept error is still reported, after a new one.
+    EarlierError(String),
+    /// The build failed, so the variant could not be run.
+    BuildFailed(String),
+    /// The execution of the variant was killed after the run timeout.
+    TimedOut,
+    /// The variant is interesting, but the outcome of a guarded test changed, as described.
+    TestsChanged(String),
+    /// Anything else the oracle rejects, with a summary of the outcome.
+    NotInteresting(String),
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rejection::ErrorDisappeared => write!(f, "error disappeared"),
+            Rejection::ErrorChanged(from, to) => write!(f, "error changed from {from} to {to}"),
+            Rejection::EarlierError(error) => write!(f, "new earlier error {error}"),
+            Rejection::BuildFailed(error) => write!(f, "build failed with {error}"),
+            Rejection::TimedOut => write!(f, "execution timed out"),
+            Rejection::TestsChanged(change) => write!(f, "{change}"),
+            Rejection::NotInteresting(summary) => write!(f, "not interesting ({summary})"),
+        }
+    }
+}
+
+/// Oracles are `Send` and `Sync` so that a reduction, or several of them, can run on worker
+/// threads.
+pub trait Oracle: Send + Sync {
+    /// Returns true if the variant needs to be executed for the oracle to decide on it.
+    fn requires_run(&self) -> bool {
+        false
+    }
+
+    fn is_interesting(&self, outcome: &VariantOutcome) -> bool;
+
+    /// Explains why the variant is not interesting, only asked for rejected variants.
+    fn explain_rejection(&self, outcome: &VariantOutcome) -> Rejection {
+        match (&outcome.run_output, outcome.build_errors.errors.first()) {
+            (Some(run_output), _) if run_output.timed_out => Rejection::TimedOut,
+            (None, Some(error)) if self.requires_run() => Rejection::BuildFailed(error.short()),
+            _ => Rejection::NotInteresting(outcome.summary()),
+        }
+    }
+}
+
+/// A variant is interesting if its first build error is the same as the master error, or any of
+/// its errors if extra errors are allowed.
+pub struct SameErrorOracle {
+    master_error: BuildError,
+    allow_extra_errors: bool,
+}
+
+impl SameErrorOracle {
+    pub fn new(master_error: BuildError) -> Self {
+        Self {
+            master_error,
+            allow_extra_errors: false,
+        }
+    }
+
+    /// Returns the oracle, also accepting the variants reporting new errors before the master
+    /// error if `allow` is set.
+    pub fn allowing_extra_errors(self, allow: bool) -> Self {
+        Self {
+            allow_extra_errors: allow,
+            ..self
+        }
+    }
+}
+
+impl Oracle for SameErrorOracle {
+    fn is_interesting(&self, outcome: &VariantOutcome) -> bool {
+        let mut errors = outcome.build_errors.errors.iter();
+        if self.allow_extra_errors {
+            errors.any(|error| error.is_same_error(&self.master_error))
+        } else {
+            errors
+                .next()
+                .is_some_and(|error| error.is_same_error(&self.master_error))
+        }
+    }
+
+    fn explain_rejection(&self, outcome: &VariantOutcome) -> Rejection {
+        let errors = &outcome.build_errors.errors;
+        match errors.first() {
+            None => Rejection::ErrorDisappeared,
+            Some(first)
+                if errors
+                    .iter()
+                    .any(|error| error.is_same_error(&self.master_error)) =>
+            {
+                Rejection::EarlierError(first.short())
+            }
+            Some(first) => Rejection::ErrorChanged(self.master_error.short(), first.short()),
+        }
+    }
+}
+
+/// A variant is interesting if it builds and its execution still prints the expected output,
+/// either to stdout or stderr.
+pub struct OutputOracle {
+    expected_output: OutputPattern,
+}
+
+impl OutputOracle {
+    pub fn new(expected_output: OutputPattern) -> Self {
+        Self { expected_output }
+    }
+}
+
+impl Oracle for OutputOracle {
+    fn requires_run(&self) -> bool {
+        true
+    }
+
+    fn is_interesting(&self, outcome: &VariantOutcome) -> bool {
+        outcome
+            .run_output
+            .as_ref()
+            .is_some_and(|run_output| self.expected_output.is_match(&run_output.combined()))
+    }
+}
+
+/// A variant is interesting if it builds and its execution behaves differently from the reference
+/// binary, i.e. its stdout or exit code differ. A variant that times out is never interesting, as
+/// the reduction may just have made it loop.
+pub struct DifferentialOracle {
+    reference_output: RunOutput,
+}
+
+impl DifferentialOracle {
+    pub fn new(reference_output: RunOutput) -> Self {
+        Self { reference_output }
+    }
+}
+
+impl Oracle for DifferentialOracle {
+    fn requires_run(&self) -> bool {
+        true
+    }
+
+    fn is_interesting(&self, outcome: &VariantOutcome) -> bool {
+        outcome.run_output.as_ref().is_some_and(|run_output| {
+            !run_output.timed_out
+                && (run_output.stdout != self.reference_output.stdout
+                    || run_output.exit_code != self.reference_output.exit_code)
+        })
+    }
+}
+
+/// A variant is interesting if it builds without errors.
+pub struct BuildsOracle;
+
+impl Oracle for BuildsOracle {
+    fn is_interesting(&self, outcome: &VariantOutcome) -> bool {
+        outcome.build_errors.errors.is_empty()
+    }
+
+    fn explain_rejection(&self, outcome: &VariantOutcome) -> Rejection {
+        match outcome.build_errors.errors.first() {
+            Some(error) => Rejection::BuildFailed(error.short()),
+            None => Rejection::NotInteresting(outcome.summary()),
+        }
+    }
+}
+
+/// A variant is interesting if one of its errors has the code or contains the text.
+pub struct ErrorOracle {
+    pattern: String,
+}
+
+impl ErrorOracle {
+    pub fn new(pattern: String) -> Self {
+        Self { pattern }
+    }
+
+    fn matches(pattern: &str, error: &BuildError) -> bool {
+        error.error_code.as_deref() == Some(pattern) || error.error_src.contains(pattern)
+    }
+}
+
+impl Oracle for ErrorOracle {
+    fn is_interesting(&self, outcome: &VariantOutcome) -> bool {
+        outcome
+            .build_errors
+            .errors
+            .iter()
+            .any(|error| Self::matches(&self.pattern, error))
+    }
+}
+
+/// A variant is interesting if one of its warnings is emitted by the lint or contains the text.
+pub struct WarningOracle {
+    pattern: String,
+}
+
+impl WarningOracle {
+    pub fn new(pattern: String) -> Self {
+        Self { pattern }
+    }
+}
+
+impl Oracle for WarningOracle {
+    fn is_interesting(&self, outcome: &VariantOutcome) -> bool {
+        outcome.build_errors.warnings.iter().any(|warning| {
+            warning.lint.as_deref() == Some(self.pattern.as_str())
+                || warning.warning_src.contains(&self.pattern)
+        })
+    }
+}
+
+/// A variant is interesting if the inner oracle rejects it.
+pub struct NotOracle {
+    oracle: Box<dyn Oracle>,
+}
+
+impl NotOracle {
+    pub fn new(oracle: Box<dyn Oracle>) -> Self {
+        Self { oracle }
+    }
+}
+
+impl Oracle for NotOracle {
+    fn requires_run(&self) -> bool {
+        self.oracle.requires_run()
+    }
+
+    fn is_interesting(&self, outcome: &VariantOutcome) -> bool {
+        !self.oracle.is_interesting(outcome)
+    }
+}
+
+/// A variant is interesting if all the oracles accept it.
+pub struct AllOracle {
+    oracles: Vec<Box<dyn Oracle>>,
+}
+
+impl AllOracle {
+    pub fn new(oracles: Vec<Box<dyn Oracle>>) -> Self {
+        Self { oracles }
+    }
+}
+
+impl Oracle for AllOracle {
+    fn requires_run(&self) -> bool {
+        self.oracles.iter().any(|oracle| oracle.requires_run())
+    }
+
+    fn is_interesting(&self, outcome: &VariantOutcome) -> bool {
+        self.oracles
+            .iter()
+            .all(|oracle| oracle.is_interesting(outcome))
+    }
+}
+
+/// A variant is interesting if any of the oracles accepts it.
+pub struct AnyOracle {
+    oracles: Vec<Box<dyn Oracle>>,
+}
+
+impl AnyOracle {
+    pub fn new(oracles: Vec<Box<dyn Oracle>>) -> Self {
+        Self { oracles }
+    }
+}
+
+impl Oracle for AnyOracle {
+    fn requires_run(&self) -> bool {
+        self.oracles.iter().any(|oracle| oracle.requires_run())
+    }
+
+    fn is_interesting(&self, outcome: &VariantOutcome) -> bool {
+        self.oracles
+            .iter()
+            .any(|oracle| oracle.is_interesting(outcome))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DifferentialOracle, Oracle, OracleExpression, OutputOracle, Rejection, SameErrorOracle,
+        VariantOutcome,
+    };
+    use crate::builder::{BuildError, BuildErros, BuildWarning, RunOutput};
+
+    fn run_outcome(stdout: &str) -> VariantOutcome {
+        VariantOutcome {
+            build_errors: BuildErros {
+                errors: vec![],
+                warnings: vec![],
+                summary: vec![],
+            },
+            run_output: Some(RunOutput {
+                stdout: stdout.to_owned(),
+                stderr: String::new(),
+                exit_code: Some(0),
+                timed_out: false,
+            }),
+        }
+    }
+
+    #[test]
+    fn same_error_oracle_compares_first_error() {
+        let master_error = BuildError {
+            error_code: Some("E0384".to_owned()),
+            source_file: Some("src/main.rs".into()),
+            line: Some(3),
+            error_src: "error[E0384]: cannot assign twice to immutable variable `b`".to_owned(),
+        };
+        let oracle = SameErrorOracle::new(master_error.clone());
+
+        let same = VariantOutcome {
+            build_errors: BuildErros {
+                errors: vec![BuildError {
+                    line: Some(2),
+                    ..master_error.clone()
+                }],
+                warnings: vec![],
+                summary: vec![],
+            },
+            run_output: None,
+        };
+        let changed = VariantOutcome {
+            build_errors: BuildErros {
+                errors: vec![BuildError {
+                    error_code: Some("E0425".to_owned()),
+                    ..master_error
+                }],
+                warnings: vec![],
+                summary: vec![],
+            },
+            run_output: None,
+        };
+
+        assert!(oracle.is_interesting(&same));
+        assert!(!oracle.is_interesting(&changed));
+    }
+
+    #[test]
+    fn same_error_oracle_explains_rejections() {
+        let master_error = BuildError {
+            error_code: Some("E0384".to_owned()),
+            source_file: Some("src/main.rs".into()),
+            line: Some(3),
+            error_src: "error[E0384]: cannot assign twice to immutable variable `b`".to_owned(),
+        };
+        let new_error = BuildError {
+            error_code: Some("E0425".to_owned()),
+            source_file: Some("src/main.rs".into()),
+            line: Some(2),
+            error_src: "error[E0425]: cannot find value `a` in this scope".to_owned(),
+        };
+        let oracle = SameErrorOracle::new(master_error.clone());
+        let outcome = |errors| VariantOutcome {
+            build_errors: BuildErros {
+                errors,
+                warnings: vec![],
+                summary: vec![],
+            },
+            run_output: None,
+        };
+
+        assert_eq!(
+            oracle.explain_rejection(&outcome(vec![])),
+            Rejection::ErrorDisappeared
+        );
+        assert_eq!(
+            oracle
+                .explain_rejection(&outcome(vec![new_error.clone()]))
+                .to_string(),
+            "error changed from E0384 to E0425"
+        );
+        let earlier_error = outcome(vec![new_error, master_error.clone()]);
+        assert_eq!(
+            oracle.explain_rejection(&earlier_error),
+            Rejection::EarlierError("E0425".to_owned())
+        );
+        assert!(SameErrorOracle::new(master_error)
+            .allowing_extra_errors(true)
+            .is_interesting(&earlier_error));
+    }
+
+    #[test]
+    fn output_oracle_requires_expected_output() {
+        let oracle = OutputOracle::new("wrong: 3".parse().unwrap());
+
+        assert!(oracle.is_interesting(&run_outcome("wrong: 3\n")));
+        assert!(!oracle.is_interesting(&run_outcome("wrong: 4\n")));
+        assert!(!oracle.is_interesting(&VariantOutcome {
+            build_errors: BuildErros {
+                errors: vec![],
+                warnings: vec![],
+                summary: vec![],
+            },
+            run_output: None,
+        }));
+    }
+
+    #[test]
+    fn differential_oracle_requires_different_behavior() {
+        let reference_output = run_outcome("3\n").run_output.unwrap();
+        let oracle = DifferentialOracle::new(reference_output);
+        let mut timed_out = run_outcome("");
+        timed_out.run_output.as_mut().unwrap().timed_out = true;
+
+        assert!(oracle.is_interesting(&run_outcome("4\n")));
+        assert!(!oracle.is_interesting(&run_outcome("3\n")));
+        assert!(!oracle.is_interesting(&timed_out));
+    }
+
+    #[test]
+    fn expression_oracle_composes_atoms() {
+        let oracle = "error:E0308 AND NOT warning:unused_mut"
+            .parse::<OracleExpression>()
+            .unwrap()
+            .to_oracle();
+        let error = BuildError {
+            error_code: Some("E0308".to_owned()),
+            source_file: Some("src/main.rs".into()),
+            line: Some(3),
+            error_src: "error[E0308]: mismatched types".to_owned(),
+        };
+        let warning = BuildWarning {
+            lint: Some("unused_mut".to_owned()),
+            source_file: Some("src/main.rs".into()),
+            warning_src: "warning: variable does not need to be mutable".to_owned(),
+        };
+        let outcome = |warnings| VariantOutcome {
+            build_errors: BuildErros {
+                errors: vec![error.clone()],
+                warnings,
+                summary: vec![],
+            },
+            run_output: None,
+        };
+
+        assert!(oracle.is_interesting(&outcome(vec![])));
+        assert!(!oracle.is_interesting(&outcome(vec![warning])));
+    }
+}
@@ -0,0 +1,180 @@
+//! Boolean expressions over oracles, such as `error:E0308 AND NOT warning:unused_mut`.
+//!
+//! An atom is either `error:<CODE_OR_TEXT>`, holding if an error has the code or contains the
+//! text, or `warning:<LINT_OR_TEXT>`, holding if a warning is emitted by the lint or contains the
+//! text. Atoms are combined with `NOT`, `AND` and `OR`, in decreasing order of precedence, and
+//! parentheses.
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use super::{AllOracle, AnyOracle, ErrorOracle, NotOracle, Oracle, WarningOracle};
+use crate::builder::BuildError;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum OracleExpressionError {
+    #[error("unexpected end of oracle expression")]
+    UnexpectedEnd,
+    #[error("unexpected `{0}` in oracle expression")]
+    UnexpectedToken(String),
+    #[error("unknown oracle atom `{0}`, expected `error:..` or `warning:..`")]
+    UnknownAtom(String),
+}
+
+/// A parsed oracle expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OracleExpression {
+    Error(String),
+    Warning(String),
+    Not(Box<OracleExpression>),
+    And(Box<OracleExpression>, Box<OracleExpression>),
+    Or(Box<OracleExpression>, Box<OracleExpression>),
+}
+
+impl OracleExpression {
+    /// Builds the oracle deciding on variants according to the expression.
+    pub fn to_oracle(&self) -> Box<dyn Oracle> {
+        match self {
+            OracleExpression::Error(pattern) => Box::new(ErrorOracle::new(pattern.clone())),
+            OracleExpression::Warning(pattern) => Box::new(WarningOracle::new(pattern.clone())),
+            OracleExpression::Not(expression) => Box::new(NotOracle::new(expression.to_oracle())),
+            OracleExpression::And(lhs, rhs) => {
+                Box::new(AllOracle::new(vec![lhs.to_oracle(), rhs.to_oracle()]))
+            }
+            OracleExpression::Or(lhs, rhs) => {
+                Box::new(AnyOracle::new(vec![lhs.to_oracle(), rhs.to_oracle()]))
+            }
+        }
+    }
+
+    /// Returns true if a non negated `error:` atom of the expression matches the error, i.e. the
+    /// error is one the expression asks to keep.
+    pub fn mentions_error(&self, error: &BuildError) -> bool {
+        self.mentions_error_with_polarity(error, true)
+    }
+
+    fn mentions_error_with_polarity(&self, error: &BuildError, positive: bool) -> bool {
+        match self {
+            OracleExpression::Error(pattern) => positive && ErrorOracle::matches(pattern, error),
+            OracleExpression::Warning(_) => false,
+            OracleExpression::Not(expression) => {
+                expression.mentions_error_with_polarity(error, !positive)
+            }
+            OracleExpression::And(lhs, rhs) | OracleExpression::Or(lhs, rhs) => {
+                lhs.mentions_error_with_polarity(error, positive)
+                    || rhs.mentions_error_with_polarity(error, positive)
+            }
+        }
+    }
+}
+
+impl FromStr for OracleExpression {
+    type Err = OracleExpressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let spaced = s.replace('(', " ( ").replace(')', " ) ");
+        let tokens = spaced.split_whitespace().collect::<Vec<_>>();
+        let mut pos = 0;
+        let expression = parse_or(&tokens, &mut pos)?;
+        match tokens.get(pos) {
+            None => Ok(expression),
+            Some(token) => Err(OracleExpressionError::UnexpectedToken(token.to_string())),
+        }
+    }
+}
+
+fn parse_or(tokens: &[&str], pos: &mut usize) -> Result<OracleExpression, OracleExpressionError> {
+    let mut expression = parse_and(tokens, pos)?;
+    while tokens.get(*pos) == Some(&"OR") {
+        *pos += 1;
+        let rhs = parse_and(tokens, pos)?;
+        expression = OracleExpression::Or(Box::new(expression), Box::new(rhs));
+    }
+    Ok(expression)
+}
+
+fn parse_and(tokens: &[&str], pos: &mut usize) -> Result<OracleExpression, OracleExpressionError> {
+    let mut expression = parse_not(tokens, pos)?;
+    while tokens.get(*pos) == Some(&"AND") {
+        *pos += 1;
+        let rhs = parse_not(tokens, pos)?;
+        expression = OracleExpression::And(Box::new(expression), Box::new(rhs));
+    }
+    Ok(expression)
+}
+
+fn parse_not(tokens: &[&str], pos: &mut usize) -> Result<OracleExpression, OracleExpressionError> {
+    let token = tokens
+        .get(*pos)
+        .ok_or(OracleExpressionError::UnexpectedEnd)?;
+    *pos += 1;
+    match *token {
+        "NOT" => Ok(OracleExpression::Not(Box::new(parse_not(tokens, pos)?))),
+        "(" => {
+            let expression = parse_or(tokens, pos)?;
+            match tokens.get(*pos) {
+                Some(&")") => {
+                    *pos += 1;
+                    Ok(expression)
+                }
+                Some(token) => Err(OracleExpressionError::UnexpectedToken(token.to_string())),
+                None => Err(OracleExpressionError::UnexpectedEnd),
+            }
+        }
+        ")" | "AND" | "OR" => Err(OracleExpressionError::UnexpectedToken(token.to_string())),
+        atom => parse_atom(atom),
+    }
+}
+
+fn parse_atom(atom: &str) -> Result<OracleExpression, OracleExpressionError> {
+    match atom.split_once(':') {
+        Some(("error", pattern)) if !pattern.is_empty() => {
+            Ok(OracleExpression::Error(pattern.to_owned()))
+        }
+        Some(("warning", pattern)) if !pattern.is_empty() => {
+            Ok(OracleExpression::Warning(pattern.to_owned()))
+        }
+        _ => Err(OracleExpressionError::UnknownAtom(atom.to_owned())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OracleExpression, OracleExpressionError};
+
+    #[test]
+    fn parse_precedence() {
+        let expression = "error:E0308 AND NOT warning:unused_mut OR error:E0384"
+            .parse::<OracleExpression>()
+            .unwrap();
+
+        assert_eq!(
+            expression,
+            OracleExpression::Or(
+                Box::new(OracleExpression::And(
+                    Box::new(OracleExpression::Error("E0308".to_owned())),
+                    Box::new(OracleExpression::Not(Box::new(OracleExpression::Warning(
+                        "unused_mut".to_owned()
+                    )))),
+                )),
+                Box::new(OracleExpression::Error("E0384".to_owned())),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            "error:E0308 AND".parse::<OracleExpression>(),
+            Err(OracleExpressionError::UnexpectedEnd)
+        );
+        assert_eq!(
+            "(error:E0308".parse::<OracleExpression>(),
+            Err(OracleExpressionError::UnexpectedEnd)
+        );
+        assert_eq!(
+            "output:3".parse::<OracleExpression>(),
+            Err(OracleExpressionError::UnknownAtom("output:3".to_owned()))
+        );
+    }
+}
@@ -1,4 +1,12 @@
-use syn::{Block, ExprArray, ExprAssign, ExprLet, File, Item, ItemFn, Local};
+use std::str::FromStr;
+
+use quote::{quote, ToTokens};
+use syn::{
+    spanned::Spanned, Arm, Attribute, Block, Expr, ExprArray, ExprAssign, ExprBinary, ExprIf,
+    ExprLet, ExprMatch, ExprStruct, ExprTuple, ExprUnary, ExprWhile, FieldValue, File, ImplItem,
+    Item, ItemFn, Local, Stmt, Visibility,
+};
+use thiserror::Error;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 /// Representation of the syntax generated from parsing a rust code piece.
@@ -18,6 +26,501 @@ pub enum AstNode<'a> {
     ExprArray(&'a ExprArray),
     ExprAssign(&'a ExprAssign),
     ExprLet(&'a ExprLet),
+    /// An `if let` expression, `if` expressions with other conditions are not modeled.
+    ExprIfLet(&'a ExprIf),
+    /// A `while let` loop, `while` loops with other conditions are not modeled.
+    ExprWhileLet(&'a ExprWhile),
+    ExprMatch(&'a ExprMatch),
+    /// An arm of a modeled `match`.
+    Arm(&'a Arm),
+    /// An outer attribute of a modeled expression or match arm, such as `#[allow(unused)]`.
+    /// Attributes of items are kept with their items.
+    Attribute(&'a Attribute),
+    /// A binary operation whose operands are its children. Without one of its operands, the
+    /// operation is replaced with the other one, and without the operation itself the expression
+    /// becomes a `todo!()` unless it is a statement.
+    ExprBinary(&'a ExprBinary),
+    /// An operand of a modeled binary operation, or an element of a modeled tuple.
+    Operand(&'a Expr),
+    /// A unary operation, replaced with its operand once removed unless it is a statement.
+    ExprUnary(&'a ExprUnary),
+    /// A struct literal whose fields are its children. Once a field is removed, the others are
+    /// completed with `..Default::default()` unless the literal already has a base.
+    ExprStruct(&'a ExprStruct),
+    /// A field of a modeled struct literal.
+    FieldValue(&'a FieldValue),
+    /// A non-empty tuple whose elements are its children.
+    ExprTuple(&'a ExprTuple),
+}
+
+impl<'a> AstNode<'a> {
+    /// Returns the node representing the statement in the syntax tree, if the statement is
+    /// modeled.
+    pub fn from_stmt(stmt: &'a Stmt) -> Option<Self> {
+        match stmt {
+            Stmt::Local(local_stmt) => Some(AstNode::LocalStmt(local_stmt)),
+            Stmt::Item(item) => Some(AstNode::Item(item)),
+            Stmt::Expr(Expr::Array(expr_array), _) => Some(AstNode::ExprArray(expr_array)),
+            Stmt::Expr(Expr::Assign(expr_assign), _) => Some(AstNode::ExprAssign(expr_assign)),
+            Stmt::Expr(Expr::Let(expr_let), _) => Some(AstNode::ExprLet(expr_let)),
+            Stmt::Expr(Expr::If(expr_if), _) if is_let_condition(&expr_if.cond) => {
+                Some(AstNode::ExprIfLet(expr_if))
+            }
+            Stmt::Expr(Expr::While(expr_while), _) if is_let_condition(&expr_while.cond) => {
+                Some(AstNode::ExprWhileLet(expr_while))
+            }
+            Stmt::Expr(Expr::Match(expr_match), _) => Some(AstNode::ExprMatch(expr_match)),
+            Stmt::Expr(Expr::Binary(expr_binary), _) => Some(AstNode::ExprBinary(expr_binary)),
+            Stmt::Expr(Expr::Unary(expr_unary), _) => Some(AstNode::ExprUnary(expr_unary)),
+            Stmt::Expr(Expr::Struct(expr_struct), _) => Some(AstNode::ExprStruct(expr_struct)),
+            Stmt::Expr(Expr::Tuple(expr_tuple), _) if !expr_tuple.elems.is_empty() => {
+                Some(AstNode::ExprTuple(expr_tuple))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if both nodes refer to the same syntax node, rather than to equal ones.
+    pub fn is_same_node(&self, other: &AstNode<'_>) -> bool {
+        use std::ptr::eq;
+        match (self, other) {
+            (AstNode::SourceRoot(a), AstNode::SourceRoot(b)) => eq(*a, *b),
+            (AstNode::Item(a), AstNode::Item(b)) => eq(*a, *b),
+            (AstNode::ItemFn(a), AstNode::ItemFn(b)) => eq(*a, *b),
+            (AstNode::Block(a), AstNode::Block(b)) => eq(*a, *b),
+            (AstNode::LocalStmt(a), AstNode::LocalStmt(b)) => eq(*a, *b),
+            (AstNode::ExprArray(a), AstNode::ExprArray(b)) => eq(*a, *b),
+            (AstNode::ExprAssign(a), AstNode::ExprAssign(b)) => eq(*a, *b),
+            (AstNode::ExprLet(a), AstNode::ExprLet(b)) => eq(*a, *b),
+            (AstNode::ExprIfLet(a), AstNode::ExprIfLet(b)) => eq(*a, *b),
+            (AstNode::ExprWhileLet(a), AstNode::ExprWhileLet(b)) => eq(*a, *b),
+            (AstNode::ExprMatch(a), AstNode::ExprMatch(b)) => eq(*a, *b),
+            (AstNode::Arm(a), AstNode::Arm(b)) => eq(*a, *b),
+            (AstNode::Attribute(a), AstNode::Attribute(b)) => eq(*a, *b),
+            (AstNode::ExprBinary(a), AstNode::ExprBinary(b)) => eq(*a, *b),
+            (AstNode::Operand(a), AstNode::Operand(b)) => eq(*a, *b),
+            (AstNode::ExprUnary(a), AstNode::ExprUnary(b)) => eq(*a, *b),
+            (AstNode::ExprStruct(a), AstNode::ExprStruct(b)) => eq(*a, *b),
+            (AstNode::FieldValue(a), AstNode::FieldValue(b)) => eq(*a, *b),
+            (AstNode::ExprTuple(a), AstNode::ExprTuple(b)) => eq(*a, *b),
+            _ => false,
+        }
+    }
+
+    /// Returns the span of the node in the file it was parsed from.
+    pub fn span(&self) -> proc_macro2::Span {
+        match self {
+            AstNode::SourceRoot(file) => file.span(),
+            AstNode::Item(item) => item.span(),
+            AstNode::ItemFn(item_fn) => item_fn.span(),
+            AstNode::Block(block) => block.span(),
+            AstNode::LocalStmt(local) => local.span(),
+            AstNode::ExprArray(expr_array) => expr_array.span(),
+            AstNode::ExprAssign(expr_assign) => expr_assign.span(),
+            AstNode::ExprLet(expr_let) => expr_let.span(),
+            AstNode::ExprIfLet(expr_if) => expr_if.span(),
+            AstNode::ExprWhileLet(expr_while) => expr_while.span(),
+            AstNode::ExprMatch(expr_match) => expr_match.span(),
+            AstNode::Arm(arm) => arm.span(),
+            AstNode::Attribute(attr) => attr.span(),
+            AstNode::ExprBinary(expr_binary) => expr_binary.span(),
+            AstNode::Operand(expr) => expr.span(),
+            AstNode::ExprUnary(expr_unary) => expr_unary.span(),
+            AstNode::ExprStruct(expr_struct) => expr_struct.span(),
+            AstNode::FieldValue(field_value) => field_value.span(),
+            AstNode::ExprTuple(expr_tuple) => expr_tuple.span(),
+        }
+    }
+
+    /// Returns true if this node is part of the public API of the crate, i.e. removing it or
+    /// changing it would change what downstream crates can compile against.
+    ///
+    /// Trait implementations and inherent impls with `pub` items are considered public as well.
+    pub fn is_public_api(&self) -> bool {
+        match self {
+            AstNode::Item(item) => match item {
+                Item::Impl(item_impl) => {
+                    item_impl.trait_.is_some()
+                        || item_impl.items.iter().any(|impl_item| match impl_item {
+                            ImplItem::Const(item) => is_public(&item.vis),
+                            ImplItem::Fn(item) => is_public(&item.vis),
+                            ImplItem::Type(item) => is_public(&item.vis),
+                            _ => false,
+                        })
+                }
+                item => item_visibility(item).is_some_and(is_public),
+            },
+            AstNode::ItemFn(item_fn) => is_public(&item_fn.vis),
+            _ => false,
+        }
+    }
+
+    /// Returns the kinds the node belongs to.
+    pub fn kinds(&self) -> Vec<NodeKind> {
+        let mut kinds = vec![];
+        let has_attrs = match self {
+            AstNode::SourceRoot(_) => false,
+            AstNode::Item(item) => {
+                kinds.push(NodeKind::Item);
+                kinds.extend(match item {
+                    Item::Use(_) => Some(NodeKind::Use),
+                    Item::Impl(_) => Some(NodeKind::Impl),
+                    Item::Fn(_) => Some(NodeKind::Fn),
+                    Item::Struct(_) => Some(NodeKind::Struct),
+                    Item::Enum(_) => Some(NodeKind::Enum),
+                    Item::Trait(_) | Item::TraitAlias(_) => Some(NodeKind::Trait),
+                    Item::Mod(_) => Some(NodeKind::Mod),
+                    Item::Const(_) => Some(NodeKind::Const),
+                    Item::Static(_) => Some(NodeKind::Static),
+                    Item::Type(_) => Some(NodeKind::Type),
+                    Item::Macro(_) => Some(NodeKind::Macro),
+                    _ => None,
+                });
+                item_attrs(item).is_some_and(|attrs| !attrs.is_empty())
+            }
+            AstNode::ItemFn(item_fn) => {
+                kinds.push(NodeKind::Fn);
+                !item_fn.attrs.is_empty()
+            }
+            AstNode::Block(_) => {
+                kinds.push(NodeKind::Block);
+                false
+            }
+            AstNode::LocalStmt(local) => {
+                kinds.push(NodeKind::Stmt);
+                !local.attrs.is_empty()
+            }
+            AstNode::ExprArray(ExprArray { attrs, .. })
+            | AstNode::ExprAssign(ExprAssign { attrs, .. })
+            | AstNode::ExprLet(ExprLet { attrs, .. })
+            | AstNode::ExprIfLet(ExprIf { attrs, .. })
+            | AstNode::ExprWhileLet(ExprWhile { attrs, .. })
+            | AstNode::ExprMatch(ExprMatch { attrs, .. }) => {
+                kinds.extend([NodeKind::Stmt, NodeKind::Expr]);
+                !attrs.is_empty() || !expr_attrs(self).is_empty()
+            }
+            AstNode::Arm(arm) => {
+                kinds.push(NodeKind::Expr);
+                !arm.attrs.is_empty()
+            }
+            AstNode::ExprBinary(_)
+            | AstNode::Operand(_)
+            | AstNode::ExprUnary(_)
+            | AstNode::ExprStruct(_)
+            | AstNode::FieldValue(_)
+            | AstNode::ExprTuple(_) => {
+                kinds.push(NodeKind::Expr);
+                false
+            }
+            // Attributes belong with the nodes carrying them.
+            AstNode::Attribute(_) => true,
+        };
+        if has_attrs {
+            kinds.push(NodeKind::Attrs);
+        }
+        kinds
+    }
+}
+
+impl ToTokens for AstNode<'_> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            AstNode::SourceRoot(file) => file.to_tokens(tokens),
+            AstNode::Item(item) => item.to_tokens(tokens),
+            AstNode::ItemFn(item_fn) => item_fn.to_tokens(tokens),
+            AstNode::Block(block) => block.to_tokens(tokens),
+            AstNode::LocalStmt(local) => local.to_tokens(tokens),
+            AstNode::ExprArray(expr_array) => expr_array.to_tokens(tokens),
+            AstNode::ExprAssign(expr_assign) => expr_assign.to_tokens(tokens),
+            AstNode::ExprLet(expr_let) => expr_let.to_tokens(tokens),
+            AstNode::ExprIfLet(expr_if) => expr_if.to_tokens(tokens),
+            AstNode::ExprWhileLet(expr_while) => expr_while.to_tokens(tokens),
+            AstNode::ExprMatch(expr_match) => expr_match.to_tokens(tokens),
+            AstNode::Arm(arm) => arm.to_tokens(tokens),
+            AstNode::Attribute(attr) => attr.to_tokens(tokens),
+            AstNode::ExprBinary(expr_binary) => expr_binary.to_tokens(tokens),
+            AstNode::Operand(expr) => expr.to_tokens(tokens),
+            AstNode::ExprUnary(expr_unary) => expr_unary.to_tokens(tokens),
+            AstNode::ExprStruct(expr_struct) => expr_struct.to_tokens(tokens),
+            AstNode::FieldValue(field_value) => field_value.to_tokens(tokens),
+            AstNode::ExprTuple(expr_tuple) => expr_tuple.to_tokens(tokens),
+        }
+    }
+}
+
+/// Categories of syntax nodes the user can exclude from or restrict the reduction to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    /// Any item.
+    Item,
+    Use,
+    Impl,
+    Fn,
+    Struct,
+    Enum,
+    Trait,
+    Mod,
+    Const,
+    Static,
+    Type,
+    Macro,
+    Block,
+    Stmt,
+    Expr,
+    /// Any node carrying attributes.
+    Attrs,
+}
+
+/// Names of the node kinds as written on the command line.
+const NODE_KIND_NAMES: &[(&str, NodeKind)] = &[
+    ("item", NodeKind::Item),
+    ("use", NodeKind::Use),
+    ("impl", NodeKind::Impl),
+    ("fn", NodeKind::Fn),
+    ("struct", NodeKind::Struct),
+    ("enum", NodeKind::Enum),
+    ("trait", NodeKind::Trait),
+    ("mod", NodeKind::Mod),
+    ("const", NodeKind::Const),
+    ("static", NodeKind::Static),
+    ("type", NodeKind::Type),
+    ("macro", NodeKind::Macro),
+    ("block", NodeKind::Block),
+    ("stmt", NodeKind::Stmt),
+    ("expr", NodeKind::Expr),
+    ("attrs", NodeKind::Attrs),
+];
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("unknown node kind `{0}`, expected one of: {}", node_kind_names())]
+pub struct UnknownNodeKind(String);
+
+fn node_kind_names() -> String {
+    NODE_KIND_NAMES
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl FromStr for NodeKind {
+    type Err = UnknownNodeKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NODE_KIND_NAMES
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, kind)| *kind)
+            .ok_or_else(|| UnknownNodeKind(s.to_owned()))
+    }
+}
+
+/// A short description of an item and the lines it spans in its source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemSummary {
+    pub description: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl From<&Item> for ItemSummary {
+    fn from(item: &Item) -> Self {
+        let name = |ident: &syn::Ident| ident.to_string();
+        let description = match item {
+            Item::Const(item) => format!("const {}", name(&item.ident)),
+            Item::Enum(item) => format!("enum {}", name(&item.ident)),
+            Item::ExternCrate(item) => format!("extern crate {}", name(&item.ident)),
+            Item::Fn(item) => format!("fn {}", name(&item.sig.ident)),
+            Item::ForeignMod(_) => "extern block".to_owned(),
+            Item::Impl(item) => {
+                let self_ty = &item.self_ty;
+                match &item.trait_ {
+                    Some((_, trait_path, _)) => format!(
+                        "impl {} for {}",
+                        tokens_to_string(quote!(#trait_path)),
+                        tokens_to_string(quote!(#self_ty))
+                    ),
+                    None => format!("impl {}", tokens_to_string(quote!(#self_ty))),
+                }
+            }
+            Item::Macro(item) => {
+                let path = &item.mac.path;
+                format!("{}!", tokens_to_string(quote!(#path)))
+            }
+            Item::Mod(item) => format!("mod {}", name(&item.ident)),
+            Item::Static(item) => format!("static {}", name(&item.ident)),
+            Item::Struct(item) => format!("struct {}", name(&item.ident)),
+            Item::Trait(item) => format!("trait {}", name(&item.ident)),
+            Item::TraitAlias(item) => format!("trait {}", name(&item.ident)),
+            Item::Type(item) => format!("type {}", name(&item.ident)),
+            Item::Union(item) => format!("union {}", name(&item.ident)),
+            Item::Use(item) => {
+                let tree = &item.tree;
+                format!("use {}", tokens_to_string(quote!(#tree)))
+            }
+            _ => "item".to_owned(),
+        };
+        let span = item.span();
+        Self {
+            description,
+            start_line: span.start().line,
+            end_line: span.end().line,
+        }
+    }
+}
+
+/// Prints tokens the way they are usually written, without the spaces `quote` puts around
+/// punctuation.
+fn tokens_to_string(tokens: proc_macro2::TokenStream) -> String {
+    tokens
+        .to_string()
+        .replace(" :: ", "::")
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+}
+
+impl std::fmt::Display for ItemSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (lines {}-{})",
+            self.description, self.start_line, self.end_line
+        )
+    }
+}
+
+/// Returns the identifier the item declares, if the item kind has one.
+pub fn item_ident(item: &Item) -> Option<&syn::Ident> {
+    match item {
+        Item::Const(item) => Some(&item.ident),
+        Item::Enum(item) => Some(&item.ident),
+        Item::ExternCrate(item) => Some(&item.ident),
+        Item::Fn(item) => Some(&item.sig.ident),
+        Item::Macro(item) => item.ident.as_ref(),
+        Item::Mod(item) => Some(&item.ident),
+        Item::Static(item) => Some(&item.ident),
+        Item::Struct(item) => Some(&item.ident),
+        Item::Trait(item) => Some(&item.ident),
+        Item::TraitAlias(item) => Some(&item.ident),
+        Item::Type(item) => Some(&item.ident),
+        Item::Union(item) => Some(&item.ident),
+        _ => None,
+    }
+}
+
+/// Returns the visibility of the item, if the item kind has one.
+fn item_visibility(item: &Item) -> Option<&Visibility> {
+    match item {
+        Item::Const(item) => Some(&item.vis),
+        Item::Enum(item) => Some(&item.vis),
+        Item::ExternCrate(item) => Some(&item.vis),
+        Item::Fn(item) => Some(&item.vis),
+        Item::Mod(item) => Some(&item.vis),
+        Item::Static(item) => Some(&item.vis),
+        Item::Struct(item) => Some(&item.vis),
+        Item::Trait(item) => Some(&item.vis),
+        Item::TraitAlias(item) => Some(&item.vis),
+        Item::Type(item) => Some(&item.vis),
+        Item::Union(item) => Some(&item.vis),
+        Item::Use(item) => Some(&item.vis),
+        _ => None,
+    }
+}
+
+fn item_attrs(item: &Item) -> Option<&[syn::Attribute]> {
+    match item {
+        Item::Const(item) => Some(&item.attrs),
+        Item::Enum(item) => Some(&item.attrs),
+        Item::ExternCrate(item) => Some(&item.attrs),
+        Item::Fn(item) => Some(&item.attrs),
+        Item::ForeignMod(item) => Some(&item.attrs),
+        Item::Impl(item) => Some(&item.attrs),
+        Item::Macro(item) => Some(&item.attrs),
+        Item::Mod(item) => Some(&item.attrs),
+        Item::Static(item) => Some(&item.attrs),
+        Item::Struct(item) => Some(&item.attrs),
+        Item::Trait(item) => Some(&item.attrs),
+        Item::TraitAlias(item) => Some(&item.attrs),
+        Item::Type(item) => Some(&item.attrs),
+        Item::Union(item) => Some(&item.attrs),
+        Item::Use(item) => Some(&item.attrs),
+        _ => None,
+    }
+}
+
+/// Returns the outer attributes of the expression node, which are modeled as its children.
+pub fn expr_attrs<'a>(node: &AstNode<'a>) -> &'a [Attribute] {
+    match node {
+        // The attributes of an assignment statement are attached to its left side.
+        AstNode::ExprAssign(ExprAssign { attrs, left, .. }) if attrs.is_empty() => {
+            leading_attrs(left)
+        }
+        AstNode::ExprArray(ExprArray { attrs, .. })
+        | AstNode::ExprAssign(ExprAssign { attrs, .. })
+        | AstNode::ExprLet(ExprLet { attrs, .. })
+        | AstNode::ExprIfLet(ExprIf { attrs, .. })
+        | AstNode::ExprWhileLet(ExprWhile { attrs, .. })
+        | AstNode::ExprMatch(ExprMatch { attrs, .. })
+        | AstNode::Arm(Arm { attrs, .. }) => attrs,
+        _ => &[],
+    }
+}
+
+/// Returns the attributes syn attaches to the leftmost operand of the expression when it is a
+/// statement, e.g. to `a` in `#[allow(unused)] a = b;`. Only the usual operands are covered.
+fn leading_attrs(expr: &Expr) -> &[Attribute] {
+    match expr {
+        Expr::Assign(expr) => leading_attrs(&expr.left),
+        Expr::Binary(expr) => leading_attrs(&expr.left),
+        Expr::Cast(expr) => leading_attrs(&expr.expr),
+        Expr::Path(syn::ExprPath { attrs, .. })
+        | Expr::Lit(syn::ExprLit { attrs, .. })
+        | Expr::Field(syn::ExprField { attrs, .. })
+        | Expr::Index(syn::ExprIndex { attrs, .. })
+        | Expr::Call(syn::ExprCall { attrs, .. })
+        | Expr::MethodCall(syn::ExprMethodCall { attrs, .. })
+        | Expr::Paren(syn::ExprParen { attrs, .. })
+        | Expr::Unary(syn::ExprUnary { attrs, .. }) => attrs,
+        _ => &[],
+    }
+}
+
+/// Mutable version of [`leading_attrs`].
+pub fn leading_attrs_mut(expr: &mut Expr) -> Option<&mut Vec<Attribute>> {
+    match expr {
+        Expr::Assign(expr) => leading_attrs_mut(&mut expr.left),
+        Expr::Binary(expr) => leading_attrs_mut(&mut expr.left),
+        Expr::Cast(expr) => leading_attrs_mut(&mut expr.expr),
+        Expr::Path(syn::ExprPath { attrs, .. })
+        | Expr::Lit(syn::ExprLit { attrs, .. })
+        | Expr::Field(syn::ExprField { attrs, .. })
+        | Expr::Index(syn::ExprIndex { attrs, .. })
+        | Expr::Call(syn::ExprCall { attrs, .. })
+        | Expr::MethodCall(syn::ExprMethodCall { attrs, .. })
+        | Expr::Paren(syn::ExprParen { attrs, .. })
+        | Expr::Unary(syn::ExprUnary { attrs, .. }) => Some(attrs),
+        _ => None,
+    }
+}
+
+/// Returns the expression inside the parentheses around it, if any.
+pub fn without_parens(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Paren(expr_paren) => without_parens(&expr_paren.expr),
+        expr => expr,
+    }
+}
+
+/// Returns true if the condition of an `if` or `while` is a `let`, i.e. it is an `if let` or a
+/// `while let`.
+pub fn is_let_condition(cond: &Expr) -> bool {
+    matches!(cond, Expr::Let(_))
+}
+
+fn is_public(vis: &Visibility) -> bool {
+    matches!(vis, Visibility::Public(_))
 }
 
 impl AbstractSyntaxTree {
@@ -46,7 +549,7 @@ impl AbstractSyntaxTree {
 
 #[cfg(test)]
 mod tests {
-    use super::AbstractSyntaxTree;
+    use super::{AbstractSyntaxTree, AstNode, ItemSummary, NodeKind};
 
     #[test]
     fn parser_empty_string() {
@@ -77,4 +580,82 @@ fn main() {}"#;
         let parsed_ast = AbstractSyntaxTree::parse(test_code);
         assert_eq!(parsed_ast.items.len(), 2);
     }
+
+    #[test]
+    fn public_api_items() {
+        let test_code = r#"
+pub fn public_fn() {}
+pub(crate) fn crate_fn() {}
+fn private_fn() {}
+pub struct PublicStruct;
+struct PrivateStruct;
+impl PrivateStruct { fn private_method() {} }
+impl PrivateStruct { pub fn public_method() {} }
+impl Default for PrivateStruct { fn default() -> Self { PrivateStruct } }"#;
+        let parsed_ast = AbstractSyntaxTree::parse(test_code);
+        let is_public_api = parsed_ast
+            .items
+            .iter()
+            .map(|item| AstNode::Item(item).is_public_api())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            is_public_api,
+            vec![true, false, false, true, false, false, true, true]
+        );
+    }
+
+    #[test]
+    fn node_kinds() {
+        let test_code = r#"
+use std::fmt;
+#[derive(Debug)]
+struct Point;
+impl Point {}"#;
+        let parsed_ast = AbstractSyntaxTree::parse(test_code);
+        let kinds = parsed_ast
+            .items
+            .iter()
+            .map(|item| AstNode::Item(item).kinds())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                vec![NodeKind::Item, NodeKind::Use],
+                vec![NodeKind::Item, NodeKind::Struct, NodeKind::Attrs],
+                vec![NodeKind::Item, NodeKind::Impl],
+            ]
+        );
+        assert_eq!("stmt".parse(), Ok(NodeKind::Stmt));
+        assert!("statement".parse::<NodeKind>().is_err());
+    }
+
+    #[test]
+    fn item_summaries() {
+        let test_code = r#"use std::collections::HashMap;
+struct Point {
+    x: u32,
+}
+impl Default for Point {
+    fn default() -> Self {
+        Point { x: 0 }
+    }
+}"#;
+        let parsed_ast = AbstractSyntaxTree::parse(test_code);
+        let summaries = parsed_ast
+            .items
+            .iter()
+            .map(|item| ItemSummary::from(item).to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summaries,
+            vec![
+                "use std::collections::HashMap (lines 1-1)",
+                "struct Point (lines 2-4)",
+                "impl Default for Point (lines 5-9)",
+            ]
+        );
+    }
 }
@@ -0,0 +1,52 @@
+//! Rewriting passes, run over the file once the graph guided search is done.
+//!
+//! Removing nodes cannot simplify everything, some constructs have to be rewritten into simpler
+//! ones instead. A pass enumerates the rewrites it can apply to a file, the searcher tries them
+//! one at a time and keeps the ones the oracle still accepts.
+use syn::File;
+
+use crate::config::SearchConfig;
+
+/// Rewrites of `return`, `break` and `continue`.
+mod control_flow;
+/// Replacement of trait objects with concrete types and back.
+mod dyn_trait;
+/// Removal of the unneeded `#![feature(...)]` gates.
+mod feature_gates;
+/// Simplification of `impl Trait` types in function signatures.
+mod impl_trait;
+/// Removal of the arguments of macro invocations.
+mod macro_args;
+/// Stubbing of the function bodies not containing the error. Not a pass, it is run once before the
+/// graph guided search.
+mod stub_bodies;
+
+pub use control_flow::ControlFlowPass;
+pub use dyn_trait::DynTraitPass;
+pub use feature_gates::FeatureGatePass;
+pub use impl_trait::ImplTraitPass;
+pub use macro_args::MacroArgsPass;
+pub use stub_bodies::stub_bodies_except;
+
+/// Passes are `Send` and `Sync` for the same reason as the [oracles](crate::oracle::Oracle).
+pub trait Pass: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Number of rewrites the pass can apply to `file`.
+    fn candidates(&self, file: &File) -> usize;
+
+    /// Applies the rewrite with index `candidate` to `file`.
+    fn apply(&self, file: &mut File, candidate: usize);
+}
+
+/// Returns the passes run by the searcher, in the order they are run.
+pub fn default_passes(config: &SearchConfig) -> Vec<Box<dyn Pass>> {
+    vec![
+        Box::new(ImplTraitPass::new(config.preserve_pub_api)),
+        Box::new(DynTraitPass::new(config.preserve_pub_api)),
+        Box::new(ControlFlowPass),
+        Box::new(MacroArgsPass),
+        // Last, as the code needing a feature may have been rewritten by the other passes.
+        Box::new(FeatureGatePass),
+    ]
+}
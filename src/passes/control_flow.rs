@@ -0,0 +1,174 @@
+//! Removing statements often changes the control flow of a function, for instance deleting the
+//! only `return` changes the type of its body. `return expr;` ending a function body is turned
+//! into the tail expression `expr`, `break` and `continue` lose their labels or are replaced with
+//! `()`.
+use syn::{
+    parse_quote,
+    visit_mut::{self, VisitMut},
+    Block, Expr, ExprBreak, ExprContinue, ImplItemFn, ItemFn, Stmt,
+};
+
+use super::Pass;
+
+pub struct ControlFlowPass;
+
+impl Pass for ControlFlowPass {
+    fn name(&self) -> &'static str {
+        "control-flow"
+    }
+
+    fn candidates(&self, file: &syn::File) -> usize {
+        let mut visitor = RewriteVisitor::new(None);
+        visitor.visit_file_mut(&mut file.clone());
+        visitor.seen
+    }
+
+    fn apply(&self, file: &mut syn::File, candidate: usize) {
+        let mut visitor = RewriteVisitor::new(Some(candidate));
+        visitor.visit_file_mut(file);
+    }
+}
+
+/// Walks the file, counting the possible rewrites and applying the targeted one.
+struct RewriteVisitor {
+    target: Option<usize>,
+    seen: usize,
+}
+
+impl RewriteVisitor {
+    fn new(target: Option<usize>) -> Self {
+        Self { target, seen: 0 }
+    }
+
+    /// Counts a candidate rewrite, returns true if it is the targeted one.
+    fn next_candidate(&mut self) -> bool {
+        let is_target = self.target == Some(self.seen);
+        self.seen += 1;
+        is_target
+    }
+
+    /// Turns a `return` ending the function body into its tail expression.
+    fn rewrite_fn_body(&mut self, block: &mut Block) {
+        let Some(Stmt::Expr(Expr::Return(expr_return), _)) = block.stmts.last() else {
+            return;
+        };
+        let tail = expr_return.expr.clone();
+        if self.next_candidate() {
+            block.stmts.pop();
+            if let Some(tail) = tail {
+                block.stmts.push(Stmt::Expr(*tail, None));
+            }
+        }
+    }
+}
+
+impl VisitMut for RewriteVisitor {
+    fn visit_item_fn_mut(&mut self, item_fn: &mut ItemFn) {
+        self.rewrite_fn_body(&mut item_fn.block);
+        visit_mut::visit_item_fn_mut(self, item_fn);
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, impl_item_fn: &mut ImplItemFn) {
+        self.rewrite_fn_body(&mut impl_item_fn.block);
+        visit_mut::visit_impl_item_fn_mut(self, impl_item_fn);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Break(ExprBreak { label, .. }) | Expr::Continue(ExprContinue { label, .. }) => {
+                if label.is_some() && self.next_candidate() {
+                    *label = None;
+                }
+                if self.next_candidate() {
+                    *expr = parse_quote!(());
+                    return;
+                }
+            }
+            _ => {}
+        }
+        visit_mut::visit_expr_mut(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ControlFlowPass;
+    use crate::passes::Pass;
+
+    fn apply(code: &str, candidate: usize) -> String {
+        let mut file = syn::parse_file(code).unwrap();
+        ControlFlowPass.apply(&mut file, candidate);
+        prettyplease::unparse(&file)
+    }
+
+    fn unparse(code: &str) -> String {
+        prettyplease::unparse(&syn::parse_file(code).unwrap())
+    }
+
+    const TEST_CODE: &str = r#"
+fn first_even(values: &[u32]) -> u32 {
+    'outer: for value in values {
+        if value % 2 == 1 {
+            continue 'outer;
+        }
+        break;
+    }
+    return 0;
+}"#;
+
+    #[test]
+    fn control_flow_candidates() {
+        let file = syn::parse_file(TEST_CODE).unwrap();
+        // The return, the label and `()` for continue, `()` for break.
+        assert_eq!(ControlFlowPass.candidates(&file), 4);
+    }
+
+    #[test]
+    fn control_flow_rewrites() {
+        assert_eq!(
+            apply(TEST_CODE, 0),
+            unparse(
+                r#"
+fn first_even(values: &[u32]) -> u32 {
+    'outer: for value in values {
+        if value % 2 == 1 {
+            continue 'outer;
+        }
+        break;
+    }
+    0
+}"#
+            )
+        );
+        assert_eq!(
+            apply(TEST_CODE, 1),
+            unparse(
+                r#"
+fn first_even(values: &[u32]) -> u32 {
+    'outer: for value in values {
+        if value % 2 == 1 {
+            continue;
+        }
+        break;
+    }
+    return 0;
+}"#
+            )
+        );
+        assert_eq!(
+            apply(TEST_CODE, 3),
+            unparse(
+                r#"
+fn first_even(values: &[u32]) -> u32 {
+    'outer: for value in values {
+        if value % 2 == 1 {
+            continue 'outer;
+        }
+        ();
+    }
+    return 0;
+}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,288 @@
+//! Trait objects are often only plumbing around the types actually involved in an error, and
+//! object safety errors in particular tend to minimize further once they are gone. `Box<dyn
+//! Trait>` and `&dyn Trait` are replaced with the implementors of `Trait` found in the file, and
+//! `Box<Type>` with `Box<dyn Trait>` for the traits the file implements for `Type`, whichever
+//! still reproduces.
+use std::collections::BTreeMap;
+
+use quote::ToTokens;
+use syn::{
+    parse_quote,
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+    GenericArgument, ImplItemFn, Item, ItemFn, ItemImpl, PathArguments, Type, TypeParamBound,
+    TypeTraitObject, Visibility,
+};
+
+use super::Pass;
+
+pub struct DynTraitPass {
+    preserve_pub_api: bool,
+}
+
+impl DynTraitPass {
+    /// Creates the pass, leaving the signatures of `pub` items untouched if `preserve_pub_api` is
+    /// set.
+    pub fn new(preserve_pub_api: bool) -> Self {
+        Self { preserve_pub_api }
+    }
+}
+
+impl Pass for DynTraitPass {
+    fn name(&self) -> &'static str {
+        "dyn-trait"
+    }
+
+    fn candidates(&self, file: &syn::File) -> usize {
+        let mut visitor = RewriteVisitor::new(file, self.preserve_pub_api, None);
+        visitor.visit_file_mut(&mut file.clone());
+        visitor.seen
+    }
+
+    fn apply(&self, file: &mut syn::File, candidate: usize) {
+        let mut visitor = RewriteVisitor::new(file, self.preserve_pub_api, Some(candidate));
+        visitor.visit_file_mut(file);
+    }
+}
+
+/// The `impl Trait for Type` items of a file.
+#[derive(Default)]
+struct Implementations {
+    /// Implementors of each trait, by the name of the trait.
+    implementors: BTreeMap<String, Vec<Type>>,
+    /// Implemented traits of each type, by the tokens of the type.
+    traits: BTreeMap<String, Vec<syn::Path>>,
+}
+
+impl<'ast> Visit<'ast> for Implementations {
+    fn visit_item_impl(&mut self, item_impl: &'ast ItemImpl) {
+        if let Some((None, trait_path, _)) = &item_impl.trait_ {
+            if let Some(segment) = trait_path.segments.last() {
+                self.implementors
+                    .entry(segment.ident.to_string())
+                    .or_default()
+                    .push((*item_impl.self_ty).clone());
+                self.traits
+                    .entry(tokens(&item_impl.self_ty))
+                    .or_default()
+                    .push(trait_path.clone());
+            }
+        }
+        visit::visit_item_impl(self, item_impl);
+    }
+}
+
+/// Walks the types of a file, counting the possible rewrites and applying the targeted one.
+struct RewriteVisitor {
+    implementations: Implementations,
+    preserve_pub_api: bool,
+    target: Option<usize>,
+    seen: usize,
+}
+
+impl RewriteVisitor {
+    fn new(file: &syn::File, preserve_pub_api: bool, target: Option<usize>) -> Self {
+        let mut implementations = Implementations::default();
+        implementations.visit_file(file);
+        Self {
+            implementations,
+            preserve_pub_api,
+            target,
+            seen: 0,
+        }
+    }
+
+    fn skips(&self, vis: &Visibility) -> bool {
+        self.preserve_pub_api && matches!(vis, Visibility::Public(_))
+    }
+
+    /// Returns the types the type can be rewritten into.
+    fn rewrites(&self, ty: &Type) -> Vec<Type> {
+        match ty {
+            Type::Reference(reference) => {
+                let Type::TraitObject(trait_object) = reference.elem.as_ref() else {
+                    return vec![];
+                };
+                self.implementors(trait_object)
+                    .into_iter()
+                    .map(|implementor| {
+                        let mut reference = reference.clone();
+                        *reference.elem = implementor;
+                        Type::Reference(reference)
+                    })
+                    .collect()
+            }
+            Type::Path(_) => {
+                let Some(boxed) = boxed_type(ty) else {
+                    return vec![];
+                };
+                match boxed {
+                    Type::TraitObject(trait_object) => self
+                        .implementors(trait_object)
+                        .into_iter()
+                        .map(|implementor| parse_quote!(Box<#implementor>))
+                        .collect(),
+                    boxed => self
+                        .implementations
+                        .traits
+                        .get(&tokens(boxed))
+                        .into_iter()
+                        .flatten()
+                        .map(|trait_path| parse_quote!(Box<dyn #trait_path>))
+                        .collect(),
+                }
+            }
+            _ => vec![],
+        }
+    }
+
+    fn implementors(&self, trait_object: &TypeTraitObject) -> Vec<Type> {
+        let mut traits = trait_object.bounds.iter().filter_map(|bound| match bound {
+            TypeParamBound::Trait(trait_bound) => trait_bound.path.segments.last(),
+            _ => None,
+        });
+        // Only objects of a single trait, besides lifetimes, have a concrete counterpart.
+        let (Some(segment), None) = (traits.next(), traits.next()) else {
+            return vec![];
+        };
+        self.implementations
+            .implementors
+            .get(&segment.ident.to_string())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+impl VisitMut for RewriteVisitor {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        for rewrite in self.rewrites(ty) {
+            if self.target == Some(self.seen) {
+                *ty = rewrite.clone();
+            }
+            self.seen += 1;
+        }
+        visit_mut::visit_type_mut(self, ty);
+    }
+
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        let skipped = match item {
+            Item::Struct(item) => self.skips(&item.vis),
+            Item::Enum(item) => self.skips(&item.vis),
+            Item::Type(item) => self.skips(&item.vis),
+            Item::Trait(item) => self.skips(&item.vis),
+            Item::Const(item) => self.skips(&item.vis),
+            Item::Static(item) => self.skips(&item.vis),
+            _ => false,
+        };
+        if !skipped {
+            visit_mut::visit_item_mut(self, item);
+        }
+    }
+
+    fn visit_item_fn_mut(&mut self, item_fn: &mut ItemFn) {
+        if self.skips(&item_fn.vis) {
+            self.visit_block_mut(&mut item_fn.block);
+        } else {
+            visit_mut::visit_item_fn_mut(self, item_fn);
+        }
+    }
+
+    fn visit_item_impl_mut(&mut self, item_impl: &mut ItemImpl) {
+        if item_impl.trait_.is_some() {
+            // Signatures of trait implementations have to match the trait, only bodies change.
+            for impl_item in &mut item_impl.items {
+                if let syn::ImplItem::Fn(impl_item_fn) = impl_item {
+                    self.visit_block_mut(&mut impl_item_fn.block);
+                }
+            }
+        } else {
+            visit_mut::visit_item_impl_mut(self, item_impl);
+        }
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, impl_item_fn: &mut ImplItemFn) {
+        if self.skips(&impl_item_fn.vis) {
+            self.visit_block_mut(&mut impl_item_fn.block);
+        } else {
+            visit_mut::visit_impl_item_fn_mut(self, impl_item_fn);
+        }
+    }
+}
+
+/// Returns `T` if the type is `Box<T>`.
+fn boxed_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Box" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first()? {
+        GenericArgument::Type(boxed) => Some(boxed),
+        _ => None,
+    }
+}
+
+fn tokens(ty: &Type) -> String {
+    ty.to_token_stream().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DynTraitPass;
+    use crate::passes::Pass;
+
+    fn apply(code: &str, candidate: usize) -> String {
+        let mut file = syn::parse_file(code).unwrap();
+        DynTraitPass::new(false).apply(&mut file, candidate);
+        prettyplease::unparse(&file)
+    }
+
+    fn unparse(code: &str) -> String {
+        prettyplease::unparse(&syn::parse_file(code).unwrap())
+    }
+
+    const TEST_CODE: &str = r#"
+trait Shape {}
+struct Square;
+impl Shape for Square {}
+fn draw(shape: &dyn Shape, other: Box<Square>) {}"#;
+
+    #[test]
+    fn dyn_trait_candidates() {
+        let file = syn::parse_file(TEST_CODE).unwrap();
+        // &Square for &dyn Shape, Box<dyn Shape> for Box<Square>.
+        assert_eq!(DynTraitPass::new(false).candidates(&file), 2);
+
+        let public_file = syn::parse_file(&TEST_CODE.replace("fn draw", "pub fn draw")).unwrap();
+        assert_eq!(DynTraitPass::new(true).candidates(&public_file), 0);
+    }
+
+    #[test]
+    fn dyn_trait_rewrites() {
+        assert_eq!(
+            apply(TEST_CODE, 0),
+            unparse(
+                r#"
+trait Shape {}
+struct Square;
+impl Shape for Square {}
+fn draw(shape: &Square, other: Box<Square>) {}"#
+            )
+        );
+        assert_eq!(
+            apply(TEST_CODE, 1),
+            unparse(
+                r#"
+trait Shape {}
+struct Square;
+impl Shape for Square {}
+fn draw(shape: &dyn Shape, other: Box<dyn Shape>) {}"#
+            )
+        );
+    }
+}
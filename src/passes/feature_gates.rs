@@ -0,0 +1,112 @@
+//! Nightly reproductions often carry every feature gate of the crate they were cut from, while
+//! the compiler issue needs a few of them. Each feature of the `#![feature(...)]` attributes is
+//! removed on its own, and an attribute left without features is removed as well.
+use syn::{
+    parse::Parser, parse_quote, punctuated::Punctuated, AttrStyle, Attribute, Meta, Path, Token,
+};
+
+use super::Pass;
+
+pub struct FeatureGatePass;
+
+impl Pass for FeatureGatePass {
+    fn name(&self) -> &'static str {
+        "feature-gates"
+    }
+
+    fn candidates(&self, file: &syn::File) -> usize {
+        file.attrs
+            .iter()
+            .filter_map(features)
+            .map(|features| features.len())
+            .sum()
+    }
+
+    fn apply(&self, file: &mut syn::File, candidate: usize) {
+        let mut seen = 0;
+        for ix in 0..file.attrs.len() {
+            let Some(mut features) = features(&file.attrs[ix]) else {
+                continue;
+            };
+            if candidate >= seen + features.len() {
+                seen += features.len();
+                continue;
+            }
+            features.remove(candidate - seen);
+            if features.is_empty() {
+                file.attrs.remove(ix);
+            } else {
+                file.attrs[ix] = parse_quote!(#![feature(#(#features),*)]);
+            }
+            return;
+        }
+    }
+}
+
+/// Returns the features enabled by the attribute, if it is a `#![feature(...)]` one.
+fn features(attr: &Attribute) -> Option<Vec<Path>> {
+    if !matches!(attr.style, AttrStyle::Inner(_)) || !attr.path().is_ident("feature") {
+        return None;
+    }
+    let Meta::List(list) = &attr.meta else {
+        return None;
+    };
+    Punctuated::<Path, Token![,]>::parse_terminated
+        .parse2(list.tokens.clone())
+        .ok()
+        .map(|features| features.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FeatureGatePass;
+    use crate::passes::Pass;
+
+    fn apply(code: &str, candidate: usize) -> String {
+        let mut file = syn::parse_file(code).unwrap();
+        FeatureGatePass.apply(&mut file, candidate);
+        prettyplease::unparse(&file)
+    }
+
+    fn unparse(code: &str) -> String {
+        prettyplease::unparse(&syn::parse_file(code).unwrap())
+    }
+
+    const TEST_CODE: &str = r#"
+#![feature(never_type, let_chains)]
+#![feature(negative_impls)]
+#![allow(dead_code)]
+
+fn main() {}"#;
+
+    #[test]
+    fn feature_gate_candidates() {
+        let file = syn::parse_file(TEST_CODE).unwrap();
+        assert_eq!(FeatureGatePass.candidates(&file), 3);
+    }
+
+    #[test]
+    fn feature_gate_removals() {
+        assert_eq!(
+            apply(TEST_CODE, 1),
+            unparse(
+                r#"
+#![feature(never_type)]
+#![feature(negative_impls)]
+#![allow(dead_code)]
+
+fn main() {}"#
+            )
+        );
+        assert_eq!(
+            apply(TEST_CODE, 2),
+            unparse(
+                r#"
+#![feature(never_type, let_chains)]
+#![allow(dead_code)]
+
+fn main() {}"#
+            )
+        );
+    }
+}
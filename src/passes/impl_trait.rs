@@ -0,0 +1,271 @@
+//! `impl Trait` types are frequent in trait solver errors but often irrelevant to them. Return
+//! types are replaced with `Box<dyn Trait>`, `()` or, for iterators, `std::iter::Empty<T>`, and
+//! `impl Trait` parameters are removed.
+use syn::{
+    parse_quote,
+    visit_mut::{self, VisitMut},
+    Block, FnArg, GenericArgument, ImplItemFn, ItemFn, ItemImpl, PathArguments, ReturnType,
+    Signature, Stmt, Type, TypeImplTrait, TypeParamBound, Visibility,
+};
+
+use super::Pass;
+
+pub struct ImplTraitPass {
+    preserve_pub_api: bool,
+}
+
+impl ImplTraitPass {
+    /// Creates the pass, leaving `pub` functions untouched if `preserve_pub_api` is set.
+    pub fn new(preserve_pub_api: bool) -> Self {
+        Self { preserve_pub_api }
+    }
+}
+
+impl Pass for ImplTraitPass {
+    fn name(&self) -> &'static str {
+        "impl-trait"
+    }
+
+    fn candidates(&self, file: &syn::File) -> usize {
+        let mut visitor = RewriteVisitor::new(self.preserve_pub_api, None);
+        visitor.visit_file_mut(&mut file.clone());
+        visitor.seen
+    }
+
+    fn apply(&self, file: &mut syn::File, candidate: usize) {
+        let mut visitor = RewriteVisitor::new(self.preserve_pub_api, Some(candidate));
+        visitor.visit_file_mut(file);
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Rewrite {
+    /// Return `Box<dyn Trait>` and box the tail expression.
+    BoxDyn,
+    /// Return nothing and turn the tail expression into a statement.
+    Unit,
+    /// Return `std::iter::Empty<T>` from a function returning `impl Iterator<Item = T>`.
+    EmptyIterator(Box<Type>),
+    /// Remove the parameter at the given position.
+    RemoveParam(usize),
+}
+
+/// Walks the functions of a file, counting the possible rewrites and applying the targeted one.
+struct RewriteVisitor {
+    preserve_pub_api: bool,
+    target: Option<usize>,
+    seen: usize,
+    in_trait_impl: bool,
+}
+
+impl RewriteVisitor {
+    fn new(preserve_pub_api: bool, target: Option<usize>) -> Self {
+        Self {
+            preserve_pub_api,
+            target,
+            seen: 0,
+            in_trait_impl: false,
+        }
+    }
+
+    fn rewrite_fn(&mut self, vis: &Visibility, sig: &mut Signature, block: &mut Block) {
+        // Signatures of trait implementations have to match the trait.
+        if self.in_trait_impl || (self.preserve_pub_api && matches!(vis, Visibility::Public(_))) {
+            return;
+        }
+        for rewrite in applicable_rewrites(sig) {
+            if self.target == Some(self.seen) {
+                apply_rewrite(rewrite, sig, block);
+            }
+            self.seen += 1;
+        }
+    }
+}
+
+impl VisitMut for RewriteVisitor {
+    fn visit_item_fn_mut(&mut self, item_fn: &mut ItemFn) {
+        let ItemFn {
+            vis, sig, block, ..
+        } = item_fn;
+        self.rewrite_fn(vis, sig, block);
+        visit_mut::visit_item_fn_mut(self, item_fn);
+    }
+
+    fn visit_item_impl_mut(&mut self, item_impl: &mut ItemImpl) {
+        let in_trait_impl = self.in_trait_impl;
+        self.in_trait_impl = item_impl.trait_.is_some();
+        visit_mut::visit_item_impl_mut(self, item_impl);
+        self.in_trait_impl = in_trait_impl;
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, impl_item_fn: &mut ImplItemFn) {
+        let ImplItemFn {
+            vis, sig, block, ..
+        } = impl_item_fn;
+        self.rewrite_fn(vis, sig, block);
+        visit_mut::visit_impl_item_fn_mut(self, impl_item_fn);
+    }
+}
+
+fn applicable_rewrites(sig: &Signature) -> Vec<Rewrite> {
+    let mut rewrites = vec![];
+    if let ReturnType::Type(_, ty) = &sig.output {
+        if let Type::ImplTrait(impl_trait) = ty.as_ref() {
+            rewrites.push(Rewrite::BoxDyn);
+            rewrites.push(Rewrite::Unit);
+            if let Some(item) = iterator_item(impl_trait) {
+                rewrites.push(Rewrite::EmptyIterator(Box::new(item)));
+            }
+        }
+    }
+    for (ix, input) in sig.inputs.iter().enumerate() {
+        if let FnArg::Typed(pat_type) = input {
+            if contains_impl_trait(&pat_type.ty) {
+                rewrites.push(Rewrite::RemoveParam(ix));
+            }
+        }
+    }
+    rewrites
+}
+
+fn apply_rewrite(rewrite: Rewrite, sig: &mut Signature, block: &mut Block) {
+    match rewrite {
+        Rewrite::BoxDyn => {
+            if let ReturnType::Type(_, ty) = &mut sig.output {
+                if let Type::ImplTrait(impl_trait) = ty.as_ref() {
+                    let bounds = &impl_trait.bounds;
+                    **ty = parse_quote!(Box<dyn #bounds>);
+                }
+            }
+            if let Some(Stmt::Expr(tail, None)) = block.stmts.last_mut() {
+                *tail = parse_quote!(Box::new(#tail));
+            }
+        }
+        Rewrite::Unit => {
+            sig.output = ReturnType::Default;
+            if let Some(Stmt::Expr(_, semi @ None)) = block.stmts.last_mut() {
+                *semi = Some(Default::default());
+            }
+        }
+        Rewrite::EmptyIterator(item) => {
+            sig.output = parse_quote!(-> std::iter::Empty<#item>);
+            block.stmts = vec![Stmt::Expr(parse_quote!(std::iter::empty()), None)];
+        }
+        Rewrite::RemoveParam(param_ix) => {
+            sig.inputs = sig
+                .inputs
+                .iter()
+                .enumerate()
+                .filter(|(ix, _)| *ix != param_ix)
+                .map(|(_, input)| input.clone())
+                .collect();
+        }
+    }
+}
+
+fn contains_impl_trait(ty: &Type) -> bool {
+    match ty {
+        Type::ImplTrait(_) => true,
+        Type::Reference(reference) => contains_impl_trait(&reference.elem),
+        Type::Paren(paren) => contains_impl_trait(&paren.elem),
+        _ => false,
+    }
+}
+
+/// Returns `T` if the bounds contain `Iterator<Item = T>`.
+fn iterator_item(impl_trait: &TypeImplTrait) -> Option<Type> {
+    impl_trait.bounds.iter().find_map(|bound| {
+        let TypeParamBound::Trait(trait_bound) = bound else {
+            return None;
+        };
+        let segment = trait_bound.path.segments.last()?;
+        if segment.ident != "Iterator" {
+            return None;
+        }
+        let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+            return None;
+        };
+        arguments.args.iter().find_map(|argument| match argument {
+            GenericArgument::AssocType(assoc_type) if assoc_type.ident == "Item" => {
+                Some(assoc_type.ty.clone())
+            }
+            _ => None,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImplTraitPass;
+    use crate::passes::Pass;
+
+    fn apply(code: &str, candidate: usize) -> String {
+        let mut file = syn::parse_file(code).unwrap();
+        ImplTraitPass::new(false).apply(&mut file, candidate);
+        prettyplease::unparse(&file)
+    }
+
+    fn unparse(code: &str) -> String {
+        prettyplease::unparse(&syn::parse_file(code).unwrap())
+    }
+
+    const TEST_CODE: &str = r#"
+fn evens(limit: u32, filter: impl Fn(&u32) -> bool) -> impl Iterator<Item = u32> {
+    (0..limit).filter(filter)
+}"#;
+
+    #[test]
+    fn impl_trait_candidates() {
+        let file = syn::parse_file(TEST_CODE).unwrap();
+        // Box<dyn ..>, (), Empty<u32> and the removal of `filter`.
+        assert_eq!(ImplTraitPass::new(false).candidates(&file), 4);
+        assert_eq!(ImplTraitPass::new(true).candidates(&file), 4);
+
+        let public_file = syn::parse_file(&format!("pub {}", TEST_CODE.trim())).unwrap();
+        assert_eq!(ImplTraitPass::new(true).candidates(&public_file), 0);
+    }
+
+    #[test]
+    fn impl_trait_return_rewrites() {
+        assert_eq!(
+            apply(TEST_CODE, 0),
+            unparse(
+                r#"
+fn evens(limit: u32, filter: impl Fn(&u32) -> bool) -> Box<dyn Iterator<Item = u32>> {
+    Box::new((0..limit).filter(filter))
+}"#
+            )
+        );
+        assert_eq!(
+            apply(TEST_CODE, 1),
+            unparse(
+                r#"
+fn evens(limit: u32, filter: impl Fn(&u32) -> bool) {
+    (0..limit).filter(filter);
+}"#
+            )
+        );
+        assert_eq!(
+            apply(TEST_CODE, 2),
+            unparse(
+                r#"
+fn evens(limit: u32, filter: impl Fn(&u32) -> bool) -> std::iter::Empty<u32> {
+    std::iter::empty()
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn impl_trait_param_removal() {
+        assert_eq!(
+            apply(TEST_CODE, 3),
+            unparse(
+                r#"
+fn evens(limit: u32) -> impl Iterator<Item = u32> {
+    (0..limit).filter(filter)
+}"#
+            )
+        );
+    }
+}
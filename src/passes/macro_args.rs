@@ -0,0 +1,150 @@
+//! Macro invocations are atomic for the graph guided search, their arguments are tokens that `syn`
+//! does not parse. Their top-level comma-separated arguments, such as the elements of `vec![...]`
+//! or the operands of `assert_eq!(...)`, are removed one at a time instead, together with their
+//! comma. The invocations inside the arguments of another one are left alone.
+use proc_macro2::{TokenStream, TokenTree};
+use syn::{
+    visit_mut::{self, VisitMut},
+    Macro,
+};
+
+use super::Pass;
+
+pub struct MacroArgsPass;
+
+impl Pass for MacroArgsPass {
+    fn name(&self) -> &'static str {
+        "macro-args"
+    }
+
+    fn candidates(&self, file: &syn::File) -> usize {
+        let mut visitor = RewriteVisitor::new(None);
+        visitor.visit_file_mut(&mut file.clone());
+        visitor.seen
+    }
+
+    fn apply(&self, file: &mut syn::File, candidate: usize) {
+        let mut visitor = RewriteVisitor::new(Some(candidate));
+        visitor.visit_file_mut(file);
+    }
+}
+
+/// Walks the file, counting the possible rewrites and applying the targeted one.
+struct RewriteVisitor {
+    target: Option<usize>,
+    seen: usize,
+}
+
+impl RewriteVisitor {
+    fn new(target: Option<usize>) -> Self {
+        Self { target, seen: 0 }
+    }
+}
+
+impl VisitMut for RewriteVisitor {
+    fn visit_macro_mut(&mut self, mac: &mut Macro) {
+        // The body of a macro definition is not a list of arguments.
+        if mac.path.is_ident("macro_rules") {
+            return;
+        }
+        let args = split_args(mac.tokens.clone());
+        let target = self.target.and_then(|target| target.checked_sub(self.seen));
+        self.seen += args.len();
+        if let Some(index) = target.filter(|index| *index < args.len()) {
+            mac.tokens = join_args(
+                args.into_iter()
+                    .enumerate()
+                    .filter(|(arg_index, _)| *arg_index != index)
+                    .map(|(_, arg)| arg),
+            );
+        }
+        visit_mut::visit_macro_mut(self, mac);
+    }
+}
+
+/// Splits the tokens at their top-level commas, the empty arguments left by a trailing comma
+/// aside. Commas inside groups, such as the ones of a nested call, belong to the argument.
+fn split_args(tokens: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut args = vec![vec![]];
+    for token in tokens {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => args.push(vec![]),
+            _ => args.last_mut().unwrap().push(token),
+        }
+    }
+    args.retain(|arg| !arg.is_empty());
+    args
+}
+
+fn join_args(args: impl Iterator<Item = Vec<TokenTree>>) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    for (index, arg) in args.enumerate() {
+        if index > 0 {
+            tokens.extend(quote::quote!(,));
+        }
+        tokens.extend(arg);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MacroArgsPass;
+    use crate::passes::Pass;
+
+    fn apply(code: &str, candidate: usize) -> String {
+        let mut file = syn::parse_file(code).unwrap();
+        MacroArgsPass.apply(&mut file, candidate);
+        prettyplease::unparse(&file)
+    }
+
+    fn unparse(code: &str) -> String {
+        prettyplease::unparse(&syn::parse_file(code).unwrap())
+    }
+
+    const TEST_CODE: &str = r#"
+macro_rules! first {
+    ($a:expr, $b:expr) => { $a };
+}
+fn main() {
+    let values = vec![1, f(2, 3), 4,];
+    assert_eq!(values.len(), first!(3, 0));
+}"#;
+
+    #[test]
+    fn macro_args_candidates() {
+        let file = syn::parse_file(TEST_CODE).unwrap();
+        // The invocation of `first!` is an argument of `assert_eq!`, its own are not parsed.
+        assert_eq!(MacroArgsPass.candidates(&file), 5);
+    }
+
+    #[test]
+    fn macro_args_removed_with_their_comma() {
+        assert_eq!(
+            apply(TEST_CODE, 1),
+            unparse(
+                r#"
+macro_rules! first {
+    ($a:expr, $b:expr) => { $a };
+}
+fn main() {
+    let values = vec![1, 4];
+    assert_eq!(values.len(), first!(3, 0));
+}"#
+            )
+        );
+        assert_eq!(
+            apply(TEST_CODE, 4),
+            unparse(
+                r#"
+macro_rules! first {
+    ($a:expr, $b:expr) => { $a };
+}
+fn main() {
+    let values = vec![1, f(2, 3), 4,];
+    assert_eq!(values.len());
+}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Replaces the bodies of the functions not containing the error with `todo!()`.
+//!
+//! Run once as the opening move of a search: if the error survives, every other function body
+//! is gone in a single build instead of being reduced node by node.
+use proc_macro2::Span;
+use syn::{
+    parse_quote,
+    spanned::Spanned,
+    visit_mut::{self, VisitMut},
+    Block, File, ImplItemFn, ItemFn, TraitItemFn,
+};
+
+/// Stubs the body of every function of `file` whose span does not contain `error_line`. Returns
+/// false if there was nothing to stub.
+pub fn stub_bodies_except(file: &mut File, error_line: usize) -> bool {
+    let mut visitor = StubVisitor {
+        error_line,
+        stubbed: 0,
+    };
+    visitor.visit_file_mut(file);
+    visitor.stubbed > 0
+}
+
+struct StubVisitor {
+    error_line: usize,
+    stubbed: usize,
+}
+
+impl StubVisitor {
+    fn contains_error(&self, span: Span) -> bool {
+        (span.start().line..=span.end().line).contains(&self.error_line)
+    }
+
+    /// Stubs the block unless it contains the error, returns true if the block has to be visited.
+    fn stub(&mut self, span: Span, block: &mut Block) -> bool {
+        if self.contains_error(span) {
+            return true;
+        }
+        if !is_stub(block) {
+            *block = parse_quote!({ todo!() });
+            self.stubbed += 1;
+        }
+        false
+    }
+}
+
+impl VisitMut for StubVisitor {
+    fn visit_item_fn_mut(&mut self, item_fn: &mut ItemFn) {
+        if self.stub(item_fn.span(), &mut item_fn.block) {
+            visit_mut::visit_item_fn_mut(self, item_fn);
+        }
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, impl_item_fn: &mut ImplItemFn) {
+        if self.stub(impl_item_fn.span(), &mut impl_item_fn.block) {
+            visit_mut::visit_impl_item_fn_mut(self, impl_item_fn);
+        }
+    }
+
+    fn visit_trait_item_fn_mut(&mut self, trait_item_fn: &mut TraitItemFn) {
+        let span = trait_item_fn.span();
+        let visit = match &mut trait_item_fn.default {
+            Some(block) => self.stub(span, block),
+            None => false,
+        };
+        if visit {
+            visit_mut::visit_trait_item_fn_mut(self, trait_item_fn);
+        }
+    }
+}
+
+/// Returns true if the block is already a `todo!()` stub.
+fn is_stub(block: &Block) -> bool {
+    let stub: Block = parse_quote!({ todo!() });
+    *block == stub
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stub_bodies_except;
+
+    #[test]
+    fn stubs_other_function_bodies() {
+        let code = r#"fn helper() -> u32 {
+    1 + 1
+}
+
+fn main() {
+    let b = 0;
+    b = 10;
+}
+
+impl Foo {
+    fn method(&self) {
+        println!("hi");
+    }
+}
+"#;
+        let mut file = syn::parse_file(code).unwrap();
+
+        assert!(stub_bodies_except(&mut file, 7));
+
+        let expected = syn::parse_file(
+            r#"fn helper() -> u32 {
+    todo!()
+}
+
+fn main() {
+    let b = 0;
+    b = 10;
+}
+
+impl Foo {
+    fn method(&self) {
+        todo!()
+    }
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            prettyplease::unparse(&file),
+            prettyplease::unparse(&expected)
+        );
+        assert!(!stub_bodies_except(&mut file, 7));
+    }
+}
@@ -0,0 +1,81 @@
+//! Platform-independent form of the paths persisted in the session and written in the reports.
+//!
+//! The paths are written with `/` separators, which Windows accepts as well, so that a session or
+//! a report created on one platform reads the same on another. The verbatim prefix `\\?\` that
+//! `canonicalize` adds on Windows is stripped, both from the written paths and from the
+//! canonicalized paths compared with the ones cargo reports.
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+/// Prefix of the verbatim paths on Windows.
+const VERBATIM_PREFIX: &str = r"\\?\";
+/// Prefix of the verbatim paths to a network share on Windows, written `\\server\share` as a UNC
+/// path.
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+
+/// Returns the path with `/` separators and without its verbatim prefix.
+pub fn portable(path: &Path) -> String {
+    to_portable(&path.to_string_lossy(), cfg!(windows))
+}
+
+/// Returns the native form of a path written by [`portable`], or by a version of ddebug-rs
+/// writing the native separators of another platform.
+pub fn from_portable(path: &str) -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(path)
+    } else {
+        PathBuf::from(path.replace('\\', "/"))
+    }
+}
+
+/// Canonicalizes the path without the verbatim prefix, so that it can be compared with the paths
+/// cargo reports.
+pub fn canonicalize(path: &Path) -> Result<PathBuf, std::io::Error> {
+    let canonical = std::fs::canonicalize(path)?;
+    Ok(match canonical.to_str() {
+        Some(canonical) => PathBuf::from(strip_verbatim_prefix(canonical).as_ref()),
+        None => canonical,
+    })
+}
+
+fn to_portable(path: &str, windows: bool) -> String {
+    let path = strip_verbatim_prefix(path);
+    if windows {
+        path.replace('\\', "/")
+    } else {
+        path.into_owned()
+    }
+}
+
+fn strip_verbatim_prefix(path: &str) -> Cow<'_, str> {
+    match path.strip_prefix(VERBATIM_UNC_PREFIX) {
+        Some(unc) => Cow::Owned(format!(r"\\{unc}")),
+        None => Cow::Borrowed(path.strip_prefix(VERBATIM_PREFIX).unwrap_or(path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{from_portable, to_portable};
+
+    #[test]
+    fn portable_paths() {
+        assert_eq!(to_portable(r"src\bin\main.rs", true), "src/bin/main.rs");
+        assert_eq!(to_portable(r"\\?\C:\work\repro", true), "C:/work/repro");
+        assert_eq!(
+            to_portable(r"\\?\UNC\server\share\repro", true),
+            "//server/share/repro"
+        );
+        assert_eq!(to_portable("src/main.rs", false), "src/main.rs");
+
+        assert_eq!(
+            from_portable("src/bin/main.rs"),
+            Path::new("src/bin/main.rs")
+        );
+        assert_eq!(from_portable(r"src\bin\main.rs").components().count(), 3);
+    }
+}
@@ -0,0 +1,315 @@
+//! Patterns matched against the output of executed variants.
+//!
+//! A pattern is either a plain string, matched as a substring, or a regular expression written
+//! between slashes (`/panicked at .*main\.rs/`). The regular expressions support literals,
+//! escapes (`\d`, `\w`, `\s` and escaped metacharacters), `.`, character classes, groups,
+//! alternation, the `*`, `+`, `?` quantifiers and the `^`/`$` line anchors.
+use std::str::FromStr;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    #[error("unexpected end of regular expression")]
+    UnexpectedEnd,
+    #[error("unmatched `{0}` in regular expression")]
+    Unmatched(char),
+    #[error("nothing to repeat before `{0}`")]
+    NothingToRepeat(char),
+}
+
+/// An expected output of a program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputPattern {
+    Literal(String),
+    Regex(Regex),
+}
+
+impl OutputPattern {
+    /// Returns true if the pattern occurs anywhere in the output.
+    pub fn is_match(&self, output: &str) -> bool {
+        match self {
+            OutputPattern::Literal(literal) => output.contains(literal.as_str()),
+            OutputPattern::Regex(regex) => regex.is_match(output),
+        }
+    }
+}
+
+impl FromStr for OutputPattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('/').and_then(|rest| rest.strip_suffix('/')) {
+            Some(regex) => Ok(OutputPattern::Regex(regex.parse()?)),
+            None => Ok(OutputPattern::Literal(s.to_owned())),
+        }
+    }
+}
+
+/// A small backtracking regular expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regex {
+    alternatives: Vec<Vec<RegexNode>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RegexNode {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    LineStart,
+    LineEnd,
+    Group(Vec<Vec<RegexNode>>),
+    Repeat {
+        node: Box<RegexNode>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+impl FromStr for Regex {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars = s.chars().collect::<Vec<_>>();
+        let mut pos = 0;
+        let alternatives = parse_alternatives(&chars, &mut pos)?;
+        match chars.get(pos) {
+            Some(unmatched) => Err(PatternError::Unmatched(*unmatched)),
+            None => Ok(Self { alternatives }),
+        }
+    }
+}
+
+impl Regex {
+    /// Returns true if the regular expression matches anywhere in the input.
+    pub fn is_match(&self, input: &str) -> bool {
+        let input = input.chars().collect::<Vec<_>>();
+        (0..=input.len())
+            .any(|start| match_alternatives(&self.alternatives, &input, start, &mut |_| true))
+    }
+}
+
+fn parse_alternatives(
+    chars: &[char],
+    pos: &mut usize,
+) -> Result<Vec<Vec<RegexNode>>, PatternError> {
+    let mut alternatives = vec![parse_sequence(chars, pos)?];
+    while chars.get(*pos) == Some(&'|') {
+        *pos += 1;
+        alternatives.push(parse_sequence(chars, pos)?);
+    }
+    Ok(alternatives)
+}
+
+fn parse_sequence(chars: &[char], pos: &mut usize) -> Result<Vec<RegexNode>, PatternError> {
+    let mut sequence = vec![];
+    while let Some(c) = chars.get(*pos).copied() {
+        let node = match c {
+            '|' | ')' => break,
+            '*' | '+' | '?' => {
+                let node = sequence.pop().ok_or(PatternError::NothingToRepeat(c))?;
+                let (min, max) = match c {
+                    '*' => (0, None),
+                    '+' => (1, None),
+                    _ => (0, Some(1)),
+                };
+                *pos += 1;
+                sequence.push(RegexNode::Repeat {
+                    node: Box::new(node),
+                    min,
+                    max,
+                });
+                continue;
+            }
+            '(' => {
+                *pos += 1;
+                let alternatives = parse_alternatives(chars, pos)?;
+                if chars.get(*pos) != Some(&')') {
+                    return Err(PatternError::Unmatched('('));
+                }
+                RegexNode::Group(alternatives)
+            }
+            '[' => {
+                *pos += 1;
+                parse_class(chars, pos)?
+            }
+            '\\' => {
+                *pos += 1;
+                let escaped = chars.get(*pos).ok_or(PatternError::UnexpectedEnd)?;
+                escape_node(*escaped)
+            }
+            '.' => RegexNode::Any,
+            '^' => RegexNode::LineStart,
+            '$' => RegexNode::LineEnd,
+            c => RegexNode::Char(c),
+        };
+        *pos += 1;
+        sequence.push(node);
+    }
+    Ok(sequence)
+}
+
+/// Parses a character class, `pos` points to the character after `[` and is left at `]`.
+fn parse_class(chars: &[char], pos: &mut usize) -> Result<RegexNode, PatternError> {
+    let negated = chars.get(*pos) == Some(&'^');
+    if negated {
+        *pos += 1;
+    }
+    let mut ranges = vec![];
+    loop {
+        let c = *chars.get(*pos).ok_or(PatternError::Unmatched('['))?;
+        match c {
+            ']' if !ranges.is_empty() => break,
+            '\\' => {
+                *pos += 1;
+                let escaped = *chars.get(*pos).ok_or(PatternError::UnexpectedEnd)?;
+                match escape_node(escaped) {
+                    RegexNode::Class {
+                        ranges: escaped_ranges,
+                        ..
+                    } => ranges.extend(escaped_ranges),
+                    _ => ranges.push((escaped, escaped)),
+                }
+            }
+            start
+                if chars.get(*pos + 1) == Some(&'-')
+                    && chars.get(*pos + 2).is_some_and(|end| *end != ']') =>
+            {
+                ranges.push((start, chars[*pos + 2]));
+                *pos += 2;
+            }
+            c => ranges.push((c, c)),
+        }
+        *pos += 1;
+    }
+    Ok(RegexNode::Class { ranges, negated })
+}
+
+fn escape_node(escaped: char) -> RegexNode {
+    let ranges = match escaped {
+        'd' => vec![('0', '9')],
+        'w' => vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')],
+        's' => vec![(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r')],
+        'n' => return RegexNode::Char('\n'),
+        't' => return RegexNode::Char('\t'),
+        other => return RegexNode::Char(other),
+    };
+    RegexNode::Class {
+        ranges,
+        negated: false,
+    }
+}
+
+/// Continuation called with the position after a successful match.
+type Continuation<'a> = dyn FnMut(usize) -> bool + 'a;
+
+fn match_alternatives(
+    alternatives: &[Vec<RegexNode>],
+    input: &[char],
+    pos: usize,
+    k: &mut Continuation,
+) -> bool {
+    alternatives
+        .iter()
+        .any(|sequence| match_sequence(sequence, input, pos, k))
+}
+
+fn match_sequence(
+    sequence: &[RegexNode],
+    input: &[char],
+    pos: usize,
+    k: &mut Continuation,
+) -> bool {
+    match sequence.split_first() {
+        None => k(pos),
+        Some((first, rest)) => match_node(first, input, pos, &mut |next| {
+            match_sequence(rest, input, next, k)
+        }),
+    }
+}
+
+fn match_node(node: &RegexNode, input: &[char], pos: usize, k: &mut Continuation) -> bool {
+    match node {
+        RegexNode::Char(c) => input.get(pos) == Some(c) && k(pos + 1),
+        RegexNode::Any => input.get(pos).is_some_and(|c| *c != '\n') && k(pos + 1),
+        RegexNode::Class { ranges, negated } => {
+            input.get(pos).is_some_and(|c| {
+                ranges.iter().any(|(start, end)| start <= c && c <= end) != *negated
+            }) && k(pos + 1)
+        }
+        RegexNode::LineStart => (pos == 0 || input[pos - 1] == '\n') && k(pos),
+        RegexNode::LineEnd => (pos == input.len() || input[pos] == '\n') && k(pos),
+        RegexNode::Group(alternatives) => match_alternatives(alternatives, input, pos, k),
+        RegexNode::Repeat { node, min, max } => match_repeat(node, *min, *max, 0, input, pos, k),
+    }
+}
+
+/// Greedily matches `node` repeatedly, backtracking to fewer repetitions when the rest fails.
+fn match_repeat(
+    node: &RegexNode,
+    min: usize,
+    max: Option<usize>,
+    count: usize,
+    input: &[char],
+    pos: usize,
+    k: &mut Continuation,
+) -> bool {
+    let can_repeat = max.is_none_or(|max| count < max);
+    if can_repeat
+        && match_node(node, input, pos, &mut |next| {
+            // Stop repeating empty matches, they would never end.
+            next != pos && match_repeat(node, min, max, count + 1, input, next, k)
+        })
+    {
+        return true;
+    }
+    count >= min && k(pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OutputPattern, PatternError, Regex};
+
+    #[test]
+    fn literal_pattern_matches_substring() {
+        let pattern: OutputPattern = "result: 42".parse().unwrap();
+
+        assert!(pattern.is_match("computing\nresult: 42\n"));
+        assert!(!pattern.is_match("result: 41\n"));
+    }
+
+    #[test]
+    fn regex_pattern_between_slashes() {
+        let pattern: OutputPattern = r"/^result: \d+$/".parse().unwrap();
+
+        assert!(matches!(pattern, OutputPattern::Regex(_)));
+        assert!(pattern.is_match("computing\nresult: 42\n"));
+        assert!(!pattern.is_match("result: forty two\n"));
+    }
+
+    #[test]
+    fn regex_groups_alternation_and_classes() {
+        let regex: Regex = "(left|right) = [^0-9]+(ab)?c*$".parse().unwrap();
+
+        assert!(regex.is_match("left = xyzabcc"));
+        assert!(regex.is_match("right = x"));
+        assert!(!regex.is_match("right = 1"));
+        assert!(!regex.is_match("up = x"));
+    }
+
+    #[test]
+    fn regex_errors() {
+        assert_eq!(
+            "(a".parse::<Regex>().unwrap_err(),
+            PatternError::Unmatched('(')
+        );
+        assert_eq!(
+            "*a".parse::<Regex>().unwrap_err(),
+            PatternError::NothingToRepeat('*')
+        );
+    }
+}
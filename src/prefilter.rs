@@ -0,0 +1,279 @@
+//! Cheap syntactic checks skipping the removals that cannot keep the same error.
+//!
+//! A node is required, and never tried, if removing it would change the first error anyway:
+//!
+//! - it defines `main` in the root of a binary, unless the error is about the missing `main`,
+//! - it is the only item of the file and contains the error line,
+//! - it declares an identifier used on the error line, before it for local variables.
+//!
+//! The checks on the error line only hold while the file keeps the lines of the build that
+//! reported the error.
+use std::{collections::HashSet, ops::RangeInclusive, path::Path};
+
+use syn::{
+    spanned::Spanned,
+    visit::{self, Visit},
+    File, ImplItemFn, Item, ItemFn, PatIdent, TraitItemFn,
+};
+
+use crate::{
+    builder::BuildError,
+    parser::{item_ident, AstNode},
+};
+
+/// Error code of a binary without a `main` function.
+const MISSING_MAIN_ERROR: &str = "E0601";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Prefilter {
+    /// The file is the root of a binary and the error is not about the missing `main`.
+    main_required: bool,
+    /// Line of the error in the file, if it is still valid.
+    error_line: Option<usize>,
+    /// Identifiers used on the error line, except fields and methods.
+    error_identifiers: HashSet<String>,
+    /// Lines of the innermost function containing the error line.
+    error_scope: Option<RangeInclusive<usize>>,
+    item_count: usize,
+}
+
+impl Prefilter {
+    /// Creates the prefilter of the file `root_file`, parsed from `source`, keeping `error`.
+    pub fn new(file: &File, source: &str, root_file: &Path, error: &BuildError) -> Self {
+        let main_required =
+            is_binary_root(root_file) && error.error_code.as_deref() != Some(MISSING_MAIN_ERROR);
+        let error_line = error
+            .line
+            .filter(|_| error.source_file.as_deref() == Some(root_file));
+        let error_identifiers = error_line
+            .and_then(|line| source.lines().nth(line.checked_sub(1)?))
+            .map(used_identifiers)
+            .unwrap_or_default();
+        let error_scope = error_line.and_then(|line| {
+            let mut finder = ScopeFinder { line, scope: None };
+            finder.visit_file(file);
+            finder.scope
+        });
+        Self {
+            main_required,
+            error_line,
+            error_identifiers,
+            error_scope,
+            item_count: file.items.len(),
+        }
+    }
+
+    /// Returns the prefilter of the file once reprinted, when its lines no longer match the
+    /// error's.
+    pub fn without_positions(&self) -> Self {
+        Self {
+            main_required: self.main_required,
+            ..Self::default()
+        }
+    }
+
+    /// Returns why the node is required, if it is.
+    pub fn required(&self, node: &AstNode<'_>) -> Option<&'static str> {
+        let item_fn = match node {
+            AstNode::Item(Item::Fn(item_fn)) => Some(item_fn),
+            AstNode::ItemFn(item_fn) => Some(*item_fn),
+            _ => None,
+        };
+        if self.main_required && item_fn.is_some_and(|item_fn| item_fn.sig.ident == "main") {
+            return Some("defines `main`");
+        }
+
+        let error_line = self.error_line?;
+        let (span, declared, is_local) = match node {
+            AstNode::Item(item) => (item.span(), item_ident(item).into_iter().collect(), false),
+            AstNode::ItemFn(item_fn) => (item_fn.span(), vec![&item_fn.sig.ident], false),
+            AstNode::LocalStmt(local) => {
+                let mut collector = IdentCollector::default();
+                collector.visit_pat(&local.pat);
+                (local.span(), collector.idents, true)
+            }
+            _ => return None,
+        };
+        let lines = span.start().line..=span.end().line;
+        if matches!(node, AstNode::Item(_)) && self.item_count == 1 && lines.contains(&error_line) {
+            return Some("only item of the file");
+        }
+        // Items can be used before they are declared, local variables only after.
+        let in_scope = if is_local {
+            *lines.end() < error_line
+                && self
+                    .error_scope
+                    .as_ref()
+                    .is_some_and(|scope| scope.contains(lines.start()))
+        } else {
+            !lines.contains(&error_line)
+        };
+        let used = declared
+            .iter()
+            .any(|ident| self.error_identifiers.contains(&ident.to_string()));
+        (in_scope && used).then_some("declares an identifier used on the error line")
+    }
+}
+
+/// Returns true if the file is the root of a binary, which needs a `main` function.
+fn is_binary_root(file: &Path) -> bool {
+    let components = file
+        .components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .collect::<Vec<_>>();
+    matches!(
+        components.as_slice(),
+        ["src", "main.rs"]
+            | ["src", "bin", _]
+            | ["src", "bin", _, "main.rs"]
+            | ["examples", _]
+            | ["examples", _, "main.rs"]
+            | ["build.rs"]
+    )
+}
+
+/// Returns the identifiers of a line of code, skipping string literals and the names following a
+/// dot, which are fields and methods.
+fn used_identifiers(line: &str) -> HashSet<String> {
+    let mut identifiers = HashSet::new();
+    let mut chars = line.char_indices().peekable();
+    let mut after_dot = false;
+    let mut in_string = false;
+    while let Some((start, c)) = chars.next() {
+        if in_string {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        if c.is_alphabetic() || c == '_' {
+            let mut end = start + c.len_utf8();
+            while let Some((ix, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                end = ix + c.len_utf8();
+            }
+            if !after_dot {
+                identifiers.insert(line[start..end].to_owned());
+            }
+            after_dot = false;
+        } else if !c.is_whitespace() {
+            in_string = c == '"';
+            after_dot = c == '.';
+        }
+    }
+    identifiers
+}
+
+/// Collects the identifiers bound by a pattern.
+#[derive(Default)]
+struct IdentCollector<'a> {
+    idents: Vec<&'a syn::Ident>,
+}
+
+impl<'a> Visit<'a> for IdentCollector<'a> {
+    fn visit_pat_ident(&mut self, pat_ident: &'a PatIdent) {
+        self.idents.push(&pat_ident.ident);
+        visit::visit_pat_ident(self, pat_ident);
+    }
+}
+
+/// Finds the lines of the innermost function containing a line.
+struct ScopeFinder {
+    line: usize,
+    scope: Option<RangeInclusive<usize>>,
+}
+
+impl ScopeFinder {
+    fn enter(&mut self, span: proc_macro2::Span) {
+        let lines = span.start().line..=span.end().line;
+        if lines.contains(&self.line) {
+            self.scope = Some(lines);
+        }
+    }
+}
+
+impl<'a> Visit<'a> for ScopeFinder {
+    fn visit_item_fn(&mut self, item_fn: &'a ItemFn) {
+        self.enter(item_fn.span());
+        visit::visit_item_fn(self, item_fn);
+    }
+
+    fn visit_impl_item_fn(&mut self, impl_item_fn: &'a ImplItemFn) {
+        self.enter(impl_item_fn.span());
+        visit::visit_impl_item_fn(self, impl_item_fn);
+    }
+
+    fn visit_trait_item_fn(&mut self, trait_item_fn: &'a TraitItemFn) {
+        self.enter(trait_item_fn.span());
+        visit::visit_trait_item_fn(self, trait_item_fn);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use syn::{Item, Stmt};
+
+    use super::Prefilter;
+    use crate::{builder::BuildError, parser::AstNode};
+
+    #[test]
+    fn prefilter_keeps_declarations_used_on_error_line() {
+        let source = "fn main() {
+    let b = 0;
+    let a = 0;
+    b = 10;
+}
+
+fn helper() {
+    let b = 1;
+}
+";
+        let file = syn::parse_file(source).unwrap();
+        let error = BuildError {
+            error_code: Some("E0384".to_owned()),
+            source_file: Some("src/main.rs".into()),
+            line: Some(4),
+            error_src: "error[E0384]: cannot assign twice to immutable variable `b`".to_owned(),
+        };
+        let prefilter = Prefilter::new(&file, source, Path::new("src/main.rs"), &error);
+        let locals = |prefilter: &Prefilter, item: &Item| {
+            let Item::Fn(item_fn) = item else {
+                unreachable!()
+            };
+            item_fn
+                .block
+                .stmts
+                .iter()
+                .map(|stmt| match stmt {
+                    Stmt::Local(local) => prefilter.required(&AstNode::LocalStmt(local)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            prefilter.required(&AstNode::Item(&file.items[0])),
+            Some("defines `main`")
+        );
+        assert_eq!(prefilter.required(&AstNode::Item(&file.items[1])), None);
+        assert_eq!(
+            locals(&prefilter, &file.items[0]),
+            vec![
+                Some("declares an identifier used on the error line"),
+                None,
+                None
+            ]
+        );
+        // The variable of the same name in another function is not the one used.
+        assert_eq!(locals(&prefilter, &file.items[1]), vec![None]);
+        assert_eq!(
+            locals(&prefilter.without_positions(), &file.items[0]),
+            vec![None, None, None]
+        );
+    }
+}
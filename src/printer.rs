@@ -0,0 +1,380 @@
+//! Printing of the code variants.
+//!
+//! By default the variants are formatted with prettyplease, which moves the surviving code to
+//! other lines and columns. As some diagnostics depend on them, the code can be printed verbatim
+//! from the source it was parsed from instead: every token that comes from the source is copied
+//! together with the whitespace and comments around it, and the regions of removed tokens are
+//! collapsed. Tokens that were introduced by a rewrite have no place in the source, they are
+//! printed on their own. The mixed mode prints the untouched items verbatim and formats the
+//! others.
+//!
+//! When positions are preserved, the removed regions are blanked instead of collapsed: their lines
+//! are kept empty and the code following them on the same line is padded with spaces, so that the
+//! surviving code stays at its original line and column.
+use std::ops::Range;
+
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::File;
+
+/// How the variants are printed.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmitMode {
+    /// Format the whole file with prettyplease.
+    #[default]
+    Pretty,
+    /// Copy every token from the source, collapsing the removed regions.
+    Verbatim,
+    /// Copy the untouched items from the source and format the others.
+    Mixed,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Printer {
+    mode: EmitMode,
+    /// Blank the removed regions instead of collapsing them, only in the verbatim mode.
+    preserve_positions: bool,
+}
+
+impl Printer {
+    pub fn new(mode: EmitMode, preserve_positions: bool) -> Self {
+        Self {
+            mode,
+            preserve_positions,
+        }
+    }
+
+    /// Prints the file, whose spans point into `source` if it was parsed from it.
+    pub fn print(&self, source: &str, file: &File) -> String {
+        match self.mode {
+            EmitMode::Pretty => prettyplease::unparse(file),
+            EmitMode::Verbatim => {
+                print_verbatim(source, file.to_token_stream(), self.preserve_positions)
+            }
+            EmitMode::Mixed => print_mixed(source, file),
+        }
+    }
+}
+
+/// A token, or one side of a delimiter, in the order it is printed.
+struct Leaf {
+    text: String,
+    /// Range of the token in the source, `None` if it does not come from the source.
+    range: Option<Range<usize>>,
+    /// The token is a punctuation joined with the next one, such as the first `:` of `::`.
+    joint: bool,
+}
+
+fn leaves(source: &str, tokens: TokenStream, leaves_out: &mut Vec<Leaf>) {
+    for token in tokens {
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+                if !open.is_empty() {
+                    leaves_out.push(leaf(source, open.to_owned(), group.span_open(), false));
+                }
+                leaves(source, group.stream(), leaves_out);
+                if !close.is_empty() {
+                    leaves_out.push(leaf(source, close.to_owned(), group.span_close(), false));
+                }
+            }
+            TokenTree::Punct(punct) => {
+                let joint = punct.spacing() == Spacing::Joint;
+                leaves_out.push(leaf(source, punct.to_string(), punct.span(), joint));
+            }
+            token => {
+                let span = token.span();
+                leaves_out.push(leaf(source, token.to_string(), span, false));
+            }
+        }
+    }
+}
+
+fn leaf(source: &str, text: String, span: proc_macro2::Span, joint: bool) -> Leaf {
+    let range = span.byte_range();
+    let range = source.get(range.clone()).and_then(|source_text| {
+        // The tokens of a doc comment all point to the comment.
+        let matches = source_text == text || is_doc_comment(source_text);
+        (!range.is_empty() && matches).then_some(range)
+    });
+    Leaf { text, range, joint }
+}
+
+fn print_verbatim(source: &str, tokens: TokenStream, preserve_positions: bool) -> String {
+    let mut all_leaves = vec![];
+    leaves(source, tokens, &mut all_leaves);
+
+    let mut printed = String::new();
+    // Starting from an empty range keeps the comments heading the source.
+    let mut last_range: Option<Range<usize>> = Some(0..0);
+    // Tokens not coming from the source were printed since the last one that does.
+    let mut after_rewrite = false;
+    // Length of the code printed up to the last token coming from the source.
+    let mut source_printed_len = 0;
+    let mut joint = false;
+    for leaf in all_leaves {
+        let Some(range) = leaf.range else {
+            if !joint && needs_space(&printed, &leaf.text) {
+                printed.push(' ');
+            }
+            printed.push_str(&leaf.text);
+            after_rewrite = true;
+            joint = leaf.joint;
+            continue;
+        };
+        let gap = match &last_range {
+            // Doc comments are printed once.
+            Some(last_range) if *last_range == range => continue,
+            Some(last_range) if last_range.end <= range.start => {
+                Some(&source[last_range.end..range.start])
+            }
+            _ => None,
+        };
+        match gap {
+            Some(gap) if is_trivia(gap) && !after_rewrite => printed.push_str(gap),
+            Some(gap) if preserve_positions => {
+                let trivia = &gap[..trivia_len(gap)];
+                let kept = trivia.rsplit_once('\n').map_or("", |(line, _)| line);
+                let (kept, blanked) = if after_rewrite {
+                    ("", gap)
+                } else {
+                    (kept, &gap[kept.len()..])
+                };
+                printed.push_str(kept);
+                // The rewritten code already takes some of the columns of the removed one.
+                let rewritten = &printed[source_printed_len..];
+                let taken = if rewritten.contains('\n') {
+                    0
+                } else {
+                    rewritten.chars().count()
+                };
+                printed.push_str(&blank(blanked, taken));
+                if needs_space(&printed, &source[range.clone()]) {
+                    printed.push(' ');
+                }
+            }
+            Some(gap) => {
+                if !after_rewrite {
+                    // Comments ending the line of the last token stay with it.
+                    let trivia = &gap[..trivia_len(gap)];
+                    printed.push_str(trivia.rsplit_once('\n').map_or(trivia, |(line, _)| line));
+                }
+                let whitespace = preceding_whitespace(source, range.start);
+                if whitespace.is_empty() && needs_space(&printed, &source[range.clone()]) {
+                    printed.push(' ');
+                } else {
+                    printed.push_str(whitespace);
+                }
+            }
+            None if !joint && needs_space(&printed, &source[range.clone()]) => printed.push(' '),
+            None => {}
+        }
+        printed.push_str(&source[range.clone()]);
+        source_printed_len = printed.len();
+        last_range = Some(range);
+        after_rewrite = false;
+        joint = leaf.joint;
+    }
+    if !printed.ends_with('\n') {
+        printed.push('\n');
+    }
+    printed
+}
+
+/// Returns true if a space is needed between the printed code and the next token, for them to
+/// stay separate tokens or for readability.
+fn needs_space(printed: &str, next: &str) -> bool {
+    let (Some(last), Some(first)) = (printed.chars().last(), next.chars().next()) else {
+        return false;
+    };
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    if last.is_whitespace() || matches!(last, '(' | '[' | '.' | '!') {
+        return false;
+    }
+    !(matches!(first, ')' | ']' | ',' | ';' | '.' | '?')
+        || (is_word(last) && matches!(first, '!' | '(')))
+}
+
+/// Returns the blank text taking the place of the removed text: its line breaks, then as many
+/// spaces as it has characters on its last line, minus the `taken` ones.
+fn blank(removed: &str, taken: usize) -> String {
+    let line_breaks = removed.matches('\n').count();
+    let last_line = removed.rsplit('\n').next().unwrap_or_default();
+    let columns = last_line.chars().count();
+    let columns = if line_breaks == 0 {
+        columns.saturating_sub(taken)
+    } else {
+        columns
+    };
+    format!("{}{}", "\n".repeat(line_breaks), " ".repeat(columns))
+}
+
+/// Returns the whitespace right before `start` in the source.
+fn preceding_whitespace(source: &str, start: usize) -> &str {
+    let code = source[..start].trim_end();
+    &source[code.len()..start]
+}
+
+/// Returns the range of the tokens in the source if they are all there, untouched.
+fn untouched_range(source: &str, tokens: TokenStream) -> Option<Range<usize>> {
+    let mut all_leaves = vec![];
+    leaves(source, tokens, &mut all_leaves);
+    let mut ranges = all_leaves.into_iter().map(|leaf| leaf.range);
+    let first = ranges.next()??;
+    let mut last = first.clone();
+    for range in ranges {
+        let range = range?;
+        if range == last {
+            continue;
+        }
+        if last.end > range.start || !is_trivia(&source[last.end..range.start]) {
+            return None;
+        }
+        last = range;
+    }
+    Some(first.start..last.end)
+}
+
+fn print_mixed(source: &str, file: &File) -> String {
+    let attrs = File {
+        shebang: file.shebang.clone(),
+        attrs: file.attrs.clone(),
+        items: vec![],
+    };
+    let mut printed = prettyplease::unparse(&attrs);
+    for item in &file.items {
+        match untouched_range(source, item.to_token_stream()) {
+            Some(range) => {
+                printed.push_str(&source[range]);
+                printed.push('\n');
+            }
+            None => printed.push_str(&prettyplease::unparse(&File {
+                shebang: None,
+                attrs: vec![],
+                items: vec![item.clone()],
+            })),
+        }
+    }
+    printed
+}
+
+fn is_doc_comment(text: &str) -> bool {
+    (text.starts_with("///") && !text.starts_with("////"))
+        || text.starts_with("//!")
+        || (text.starts_with("/**") && !text.starts_with("/***") && text != "/**/")
+        || text.starts_with("/*!")
+}
+
+/// Returns true if the text only holds whitespace and comments, doc comments excluded as they are
+/// attributes.
+fn is_trivia(text: &str) -> bool {
+    trivia_len(text) == text.len()
+}
+
+/// Returns the length of the whitespace and comments the text starts with.
+fn trivia_len(text: &str) -> usize {
+    let mut rest = text;
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() || is_doc_comment(rest) {
+            break;
+        }
+        if let Some(comment) = rest.strip_prefix("//") {
+            rest = comment.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if rest.starts_with("/*") {
+            let Some(end) = block_comment_len(rest) else {
+                break;
+            };
+            rest = &rest[end..];
+        } else {
+            break;
+        }
+    }
+    text.len() - rest.len()
+}
+
+/// Returns the length of the block comment the text starts with, nested comments included.
+fn block_comment_len(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+    let mut ix = 0;
+    while ix + 1 < bytes.len() {
+        match &bytes[ix..ix + 2] {
+            b"/*" => {
+                depth += 1;
+                ix += 2;
+            }
+            b"*/" => {
+                depth -= 1;
+                ix += 2;
+                if depth == 0 {
+                    return Some(ix);
+                }
+            }
+            _ => ix += 1,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EmitMode, Printer};
+
+    const SOURCE: &str = r#"fn main() {
+    let   a = 0; // spacing kept
+    let b = 0;
+    b = 10;
+}
+
+fn other() -> u32   { 1 }
+"#;
+
+    #[test]
+    fn verbatim_collapses_removed_statements() {
+        let mut file = syn::parse_file(SOURCE).unwrap();
+        let syn::Item::Fn(main) = &mut file.items[0] else {
+            unreachable!()
+        };
+        main.block.stmts.remove(1);
+
+        assert_eq!(
+            Printer::new(EmitMode::Verbatim, false).print(SOURCE, &file),
+            "fn main() {\n    let   a = 0; // spacing kept\n    b = 10;\n}\n\nfn other() -> u32   { 1 }\n"
+        );
+    }
+
+    #[test]
+    fn mixed_formats_changed_items_only() {
+        let mut file = syn::parse_file(SOURCE).unwrap();
+        let syn::Item::Fn(main) = &mut file.items[0] else {
+            unreachable!()
+        };
+        main.block.stmts = vec![syn::parse_quote!(todo!();)];
+
+        assert_eq!(
+            Printer::new(EmitMode::Mixed, false).print(SOURCE, &file),
+            "fn main() {\n    todo!();\n}\nfn other() -> u32   { 1 }\n"
+        );
+    }
+
+    #[test]
+    fn preserved_positions_blank_removed_code() {
+        let mut file = syn::parse_file(SOURCE).unwrap();
+        let syn::Item::Fn(main) = &mut file.items[0] else {
+            unreachable!()
+        };
+        main.block.stmts.remove(1);
+        file.items.remove(1);
+
+        assert_eq!(
+            Printer::new(EmitMode::Verbatim, true).print(SOURCE, &file),
+            "fn main() {\n    let   a = 0; // spacing kept\n\n    b = 10;\n}\n"
+        );
+    }
+}
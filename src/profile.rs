@@ -0,0 +1,71 @@
+//! Named reduction profiles, presets of the search options for common trade-offs between the
+//! time spent and the size of the reproduction.
+//!
+//! - `fast` tries the most productive removals first, in a single round, without the rewriting
+//!   passes, and escalates to finer nodes when the removals stall.
+//! - `thorough` repeats the removals and the passes until a round accepts nothing.
+//! - `paranoid` is thorough without the batched opening move and the prefilter, and builds every
+//!   accepted variant a second time before keeping it, for flaky targets.
+use crate::config::{SearchConfig, DEFAULT_ESCALATE_AFTER};
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Fast,
+    Thorough,
+    Paranoid,
+}
+
+impl Profile {
+    /// Sets the options of the profile in the config, leaving the others untouched.
+    pub fn apply(self, config: &mut SearchConfig) {
+        match self {
+            Profile::Fast => {
+                config.adaptive_order = true;
+                config.stub_bodies = true;
+                config.run_passes = false;
+                config.max_rounds = Some(1);
+                config.prefilter = true;
+                config.verify_accepted = false;
+                config.escalate_after = Some(DEFAULT_ESCALATE_AFTER);
+            }
+            Profile::Thorough => {
+                config.adaptive_order = false;
+                config.stub_bodies = true;
+                config.run_passes = true;
+                config.max_rounds = None;
+                config.prefilter = true;
+                config.verify_accepted = false;
+                config.escalate_after = None;
+            }
+            Profile::Paranoid => {
+                config.adaptive_order = false;
+                config.stub_bodies = false;
+                config.run_passes = true;
+                config.max_rounds = None;
+                config.prefilter = false;
+                config.verify_accepted = true;
+                config.escalate_after = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Profile;
+    use crate::config::{SearchConfig, DEFAULT_ESCALATE_AFTER};
+
+    #[test]
+    fn profiles_trade_time_for_size() {
+        let mut fast = SearchConfig::default();
+        Profile::Fast.apply(&mut fast);
+        let mut paranoid = SearchConfig::default();
+        Profile::Paranoid.apply(&mut paranoid);
+
+        assert!(fast.adaptive_order && !fast.run_passes);
+        assert_eq!(fast.max_rounds, Some(1));
+        assert_eq!(fast.escalate_after, Some(DEFAULT_ESCALATE_AFTER));
+        assert!(paranoid.run_passes && paranoid.verify_accepted && !paranoid.stub_bodies);
+        assert_eq!(paranoid.max_rounds, None);
+    }
+}
@@ -0,0 +1,212 @@
+//! Dead-code finder, removing the code of the target that neither its build nor its tests need.
+//!
+//! Every source file under `src/` is minimized in turn with the oracle "the crate still builds",
+//! while guarding the outcomes of the test suite. Only removals are tried, the rewriting passes
+//! are not run, so that the pruned code is the original one with the dead parts deleted. The
+//! removed lines of every file are reported.
+//!
+//! Every file is minimized in a journal of its own, replacing the journal of the previous one, so
+//! the originals of all the files are backed up under `backups/prune/` of the session before any
+//! of them is pruned.
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::{
+    config::SearchConfig,
+    exclude::Exclusions,
+    paths::portable,
+    report::{diff_lines, formatted},
+    searcher::{ASTGuidedSearcher, Search, SearcherError, Target},
+    session::Session,
+};
+
+/// Directory of the pruned sources, relative to the target.
+const SOURCES_DIR: &str = "src";
+
+/// Directory of the originals of the pruned files in the backups of the session, laid out like
+/// the target.
+const PRUNE_BACKUPS_DIR: &str = "prune";
+
+#[derive(Error, Debug)]
+pub enum PruneError {
+    #[error("IO error emitted from prune: {0}")]
+    IOError(std::io::Error),
+    #[error("Error while pruning {0}: {1}")]
+    SearchError(PathBuf, SearcherError),
+}
+
+impl From<std::io::Error> for PruneError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IOError(value)
+    }
+}
+
+/// The lines removed from a source file, as formatted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrunedFile {
+    pub path: PathBuf,
+    pub removed: Vec<String>,
+    /// Number of lines of the formatted original.
+    pub original_lines: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PruneReport {
+    pub files: Vec<PrunedFile>,
+}
+
+impl std::fmt::Display for PruneReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for file in self.files.iter().filter(|file| !file.removed.is_empty()) {
+            writeln!(
+                f,
+                "{}: {} of {} lines removed",
+                portable(&file.path),
+                file.removed.len(),
+                file.original_lines
+            )?;
+            for line in &file.removed {
+                writeln!(f, "  - {line}")?;
+            }
+        }
+        let removed = self
+            .files
+            .iter()
+            .map(|file| file.removed.len())
+            .sum::<usize>();
+        writeln!(f, "{removed} lines removed from {} files", self.files.len())
+    }
+}
+
+pub struct Prune<'a> {
+    target: &'a Path,
+    config: SearchConfig,
+}
+
+impl<'a> Prune<'a> {
+    pub fn new(target: &'a Path, config: SearchConfig) -> Self {
+        Self { target, config }
+    }
+
+    /// Prunes every source file of the target in place, once they are all backed up.
+    pub fn run(&self) -> Result<PruneReport, PruneError> {
+        let source_files = source_files(self.target)?;
+        let backups_dir = self.back_up(&source_files)?;
+        println!(
+            "The original sources are backed up in {0}, restore them with `cp -r {0}/. {1}`",
+            backups_dir.display(),
+            self.target.display()
+        );
+        let mut files = vec![];
+        for source_file in source_files {
+            println!("Pruning {}", source_file.display());
+            let config = self.config.clone().pruning(&source_file);
+            let minimized = ASTGuidedSearcher::new(Target::Path(self.target), config)
+                .search()
+                .map_err(|error| PruneError::SearchError(source_file.clone(), error))?;
+            if let Some(minimized) = minimized {
+                let original = formatted(&minimized.original_code);
+                files.push(PrunedFile {
+                    path: source_file,
+                    removed: removed_lines(&original, &minimized.code),
+                    original_lines: original.lines().count(),
+                });
+            }
+        }
+        Ok(PruneReport { files })
+    }
+
+    /// Copies the source files into the backups of the session, returning the directory of the
+    /// copies.
+    fn back_up(&self, source_files: &[PathBuf]) -> Result<PathBuf, std::io::Error> {
+        let session = Session::new(self.target).named(self.config.session.as_deref());
+        let backups_dir = session.backups_dir().join(PRUNE_BACKUPS_DIR);
+        for source_file in source_files {
+            let backup = backups_dir.join(source_file);
+            if let Some(parent) = backup.parent() {
+                session.create_dir(parent)?;
+            }
+            std::fs::copy(self.target.join(source_file), backup)?;
+        }
+        Ok(backups_dir)
+    }
+}
+
+/// Returns the `.rs` files under `src/`, relative to the target, that are not excluded.
+pub fn source_files(target: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let exclusions = Exclusions::load(target);
+    let mut files = rs_files(target, Path::new(SOURCES_DIR))?;
+    files.retain(|file| !exclusions.is_excluded(file));
+    Ok(files)
+}
+
+/// Returns the `.rs` files under `dir`, relative to the target, sorted by path.
+fn rs_files(target: &Path, dir: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut files = vec![];
+    let Ok(entries) = std::fs::read_dir(target.join(dir)) else {
+        return Ok(files);
+    };
+    for entry in entries {
+        let path = dir.join(entry?.file_name());
+        if target.join(&path).is_dir() {
+            files.extend(rs_files(target, &path)?);
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Returns the non-blank lines of the original missing from the pruned code.
+fn removed_lines(original: &str, pruned: &str) -> Vec<String> {
+    let original = original.lines().collect::<Vec<_>>();
+    let pruned = pruned.lines().collect::<Vec<_>>();
+    let (kept, _) = diff_lines(&original, &pruned);
+    original
+        .iter()
+        .zip(kept)
+        .filter(|(line, kept)| !kept && !line.trim().is_empty())
+        .map(|(line, _)| line.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{removed_lines, source_files, Prune};
+    use crate::{config::SearchConfig, test_dir::TestDir};
+
+    #[test]
+    fn sources_backed_up_before_pruning() {
+        let test_dir = TestDir::new("prune");
+        let target = test_dir.path();
+        std::fs::create_dir_all(target.join("src/shapes")).unwrap();
+        std::fs::write(target.join("src/lib.rs"), "mod shapes;\n").unwrap();
+        std::fs::write(target.join("src/shapes/square.rs"), "pub struct Square;\n").unwrap();
+
+        let prune = Prune::new(target, SearchConfig::default());
+        let backups_dir = prune.back_up(&source_files(target).unwrap()).unwrap();
+
+        assert!(backups_dir.starts_with(target.join(".ddebug/backups")));
+        for (file, contents) in [
+            ("src/lib.rs", "mod shapes;\n"),
+            ("src/shapes/square.rs", "pub struct Square;\n"),
+        ] {
+            assert_eq!(
+                std::fs::read_to_string(backups_dir.join(file)).unwrap(),
+                contents
+            );
+        }
+    }
+
+    #[test]
+    fn removed_lines_of_pruned_code() {
+        let original = "fn main() {\n    let a = 1;\n\n    println!(\"{a}\");\n}\nfn unused() {}\n";
+        let pruned = "fn main() {\n    println!(\"{}\", 1);\n}\n";
+        assert_eq!(
+            removed_lines(original, pruned),
+            vec!["    let a = 1;", "    println!(\"{a}\");", "fn unused() {}"]
+        );
+    }
+}
@@ -0,0 +1,281 @@
+//! Self-contained HTML report of a minimization.
+//!
+//! The original and the minimized code are shown side by side, both formatted the same way so
+//! that the removed lines of the original and the rewritten lines of the minimized code can be
+//! highlighted from a line diff. The error output and the statistics of the run are shown below,
+//! along with the constructs of the minimized code the reducer cannot model, if any.
+use std::path::PathBuf;
+
+use crate::{paths::portable, searcher::Minimized, session::Session};
+
+/// Formats of the report written after a minimization.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Html,
+}
+
+/// Name of the HTML report in the reports of the session.
+const HTML_REPORT_FILE: &str = "report.html";
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+.columns { display: flex; gap: 1em; }
+.column { flex: 1; min-width: 0; }
+pre { background: #f6f8fa; padding: 0.5em; overflow-x: auto; }
+.line { display: block; min-height: 1.2em; }
+.removed { background: #ffd7d5; }
+.changed { background: #d2f4d3; }
+table { border-collapse: collapse; }
+td { padding: 0.2em 1em 0.2em 0; }";
+
+pub struct HtmlReport<'a> {
+    minimized: &'a Minimized,
+    /// Compiler output of the minimized code.
+    stderr: &'a str,
+}
+
+impl<'a> HtmlReport<'a> {
+    pub fn new(minimized: &'a Minimized, stderr: &'a str) -> Self {
+        Self { minimized, stderr }
+    }
+
+    /// Writes the report into the reports of the session, returning its path.
+    pub fn write(&self, session: &Session) -> Result<PathBuf, std::io::Error> {
+        session.create_dir(&session.reports_dir())?;
+        let path = session.reports_dir().join(HTML_REPORT_FILE);
+        std::fs::write(&path, self.render())?;
+        Ok(path)
+    }
+
+    pub fn render(&self) -> String {
+        let original = formatted(&self.minimized.original_code);
+        let original_lines = original.lines().collect::<Vec<_>>();
+        let minimized_lines = self.minimized.code.lines().collect::<Vec<_>>();
+        let (original_kept, minimized_kept) = diff_lines(&original_lines, &minimized_lines);
+
+        let error = self
+            .minimized
+            .master_error
+            .as_ref()
+            .map(|error| error.error_src.as_str())
+            .unwrap_or("output of the program");
+        let file = portable(&self.minimized.source_file);
+        let statistics = [
+            ("File", file),
+            ("Kept", error.to_owned()),
+            (
+                "Lines",
+                format!("{} → {}", original_lines.len(), minimized_lines.len()),
+            ),
+            ("Variants tested", self.minimized.variants.to_string()),
+            ("Reductions accepted", self.minimized.accepted.to_string()),
+            (
+                "Duration",
+                format!("{:.1}s", self.minimized.duration.as_secs_f64()),
+            ),
+            ("Rustc", self.minimized.toolchain.rustc_version().to_owned()),
+            (
+                "Cargo",
+                self.minimized.toolchain.cargo.trim_end().to_owned(),
+            ),
+        ];
+
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!(
+            "<title>ddebug-rs report: {}</title>\n",
+            escape(error)
+        ));
+        html.push_str(&format!("<style>\n{STYLE}\n</style>\n</head>\n<body>\n"));
+        html.push_str(&format!("<h1>{}</h1>\n<table>\n", escape(error)));
+        for (name, value) in statistics {
+            html.push_str(&format!(
+                "<tr><td>{name}</td><td>{}</td></tr>\n",
+                escape(&value)
+            ));
+        }
+        html.push_str("</table>\n<div class=\"columns\">\n");
+        html.push_str(&code_column(
+            "Original",
+            &original_lines,
+            &original_kept,
+            "removed",
+        ));
+        html.push_str(&code_column(
+            "Minimized",
+            &minimized_lines,
+            &minimized_kept,
+            "changed",
+        ));
+        html.push_str("</div>\n");
+        if !self.minimized.unsupported.is_empty() {
+            html.push_str("<h2>Not reduced because unsupported</h2>\n<ul>\n");
+            for construct in &self.minimized.unsupported {
+                html.push_str(&format!("<li>{}</li>\n", escape(&construct.to_string())));
+            }
+            html.push_str("</ul>\n");
+        }
+        html.push_str("<h2>Compiler output</h2>\n");
+        html.push_str(&format!("<pre>{}</pre>\n", escape(self.stderr)));
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+fn code_column(title: &str, lines: &[&str], kept: &[bool], highlight: &str) -> String {
+    let mut column = format!("<div class=\"column\">\n<h2>{title}</h2>\n<pre>");
+    for (line, kept) in lines.iter().zip(kept) {
+        let class = if *kept {
+            "line".to_owned()
+        } else {
+            format!("line {highlight}")
+        };
+        column.push_str(&format!("<span class=\"{class}\">{}</span>", escape(line)));
+    }
+    column.push_str("</pre>\n</div>\n");
+    column
+}
+
+/// Formats the code the way the minimized code is, falling back to the code itself if it does
+/// not parse.
+pub fn formatted(code: &str) -> String {
+    syn::parse_file(code)
+        .map(|file| prettyplease::unparse(&file))
+        .unwrap_or_else(|_| code.to_owned())
+}
+
+/// Returns, for each line of both sides, whether it is part of their longest common subsequence.
+/// It is found with Hirschberg's algorithm, in space linear in the number of lines.
+pub fn diff_lines(a: &[&str], b: &[&str]) -> (Vec<bool>, Vec<bool>) {
+    let mut a_kept = vec![false; a.len()];
+    let mut b_kept = vec![false; b.len()];
+    mark_common_lines(a, b, &mut a_kept, &mut b_kept);
+    (a_kept, b_kept)
+}
+
+/// Marks the lines of a longest common subsequence of both sides as kept.
+fn mark_common_lines(a: &[&str], b: &[&str], a_kept: &mut [bool], b_kept: &mut [bool]) {
+    // The common prefix and suffix, most of the lines of similar sides, are kept as they are.
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_end, b_end) = (a.len() - suffix, b.len() - suffix);
+    a_kept[..prefix].fill(true);
+    a_kept[a_end..].fill(true);
+    b_kept[..prefix].fill(true);
+    b_kept[b_end..].fill(true);
+    let (a, a_kept) = (&a[prefix..a_end], &mut a_kept[prefix..a_end]);
+    let (b, b_kept) = (&b[prefix..b_end], &mut b_kept[prefix..b_end]);
+
+    if a.is_empty() || b.is_empty() {
+        return;
+    }
+    if a.len() == 1 {
+        if let Some(j) = b.iter().position(|line| *line == a[0]) {
+            a_kept[0] = true;
+            b_kept[j] = true;
+        }
+        return;
+    }
+    // The subsequence goes through the split of `b` maximizing the common lengths of both halves
+    // of `a`, each half is then diffed on its own.
+    let mid = a.len() / 2;
+    let forward = common_lengths(a[..mid].iter(), b.iter());
+    let backward = common_lengths(a[mid..].iter().rev(), b.iter().rev());
+    let split = (0..=b.len())
+        .max_by_key(|&j| (forward[j] + backward[b.len() - j], std::cmp::Reverse(j)))
+        .unwrap_or_default();
+    let (a_first, a_second) = a_kept.split_at_mut(mid);
+    let (b_first, b_second) = b_kept.split_at_mut(split);
+    mark_common_lines(&a[..mid], &b[..split], a_first, b_first);
+    mark_common_lines(&a[mid..], &b[split..], a_second, b_second);
+}
+
+/// Returns, for each `j`, the length of the longest common subsequence of `a` and the first `j`
+/// lines of `b`.
+fn common_lengths<T: PartialEq>(
+    a: impl Iterator<Item = T>,
+    b: impl Iterator<Item = T> + Clone,
+) -> Vec<usize> {
+    let mut lengths = vec![0; b.clone().count() + 1];
+    for x in a {
+        // Length for the previous line of `a` and the previous line of `b`.
+        let mut diagonal = 0;
+        for (j, y) in b.clone().enumerate() {
+            let above = lengths[j + 1];
+            lengths[j + 1] = if x == y {
+                diagonal + 1
+            } else {
+                above.max(lengths[j])
+            };
+            diagonal = above;
+        }
+    }
+    lengths
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_lines, escape};
+
+    #[test]
+    fn diff_marks_removed_lines() {
+        let original = ["fn main() {", "    let a = 0;", "    let b = 0;", "}"];
+        let minimized = ["fn main() {", "    let b = 0;", "}"];
+
+        let (original_kept, minimized_kept) = diff_lines(&original, &minimized);
+
+        assert_eq!(original_kept, vec![true, false, true, true]);
+        assert_eq!(minimized_kept, vec![true, true, true]);
+    }
+
+    #[test]
+    fn diff_keeps_a_longest_common_subsequence() {
+        let a = ["a", "b", "c", "b", "d", "a", "b"];
+        let b = ["b", "d", "c", "a", "b", "a"];
+
+        let (a_kept, b_kept) = diff_lines(&a, &b);
+
+        let kept = |lines: &[&'static str], kept: &[bool]| {
+            lines
+                .iter()
+                .zip(kept)
+                .filter_map(|(line, kept)| kept.then_some(*line))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(kept(&a, &a_kept).len(), 4);
+        assert_eq!(kept(&a, &a_kept), kept(&b, &b_kept));
+    }
+
+    #[test]
+    fn diff_of_large_files() {
+        let a = (0..4_000).map(|i| format!("line {i}")).collect::<Vec<_>>();
+        let b = a.iter().step_by(2).cloned().collect::<Vec<_>>();
+        let a = a.iter().map(String::as_str).collect::<Vec<_>>();
+        let b = b.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let (a_kept, b_kept) = diff_lines(&a, &b);
+
+        assert!(a_kept.iter().step_by(2).all(|kept| *kept));
+        assert!(a_kept.iter().skip(1).step_by(2).all(|kept| !kept));
+        assert!(b_kept.iter().all(|kept| *kept));
+    }
+
+    #[test]
+    fn escape_html() {
+        assert_eq!(
+            escape("Vec<&str> \"a\""),
+            "Vec&lt;&amp;str&gt; &quot;a&quot;"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! Nodes whose removal the oracle rejected in previous minimizations of the same file and error
+//! under the same oracle, persisted in `.ddebug/required/` so that minimizing a slightly changed
+//! version of the code does not test them again.
+//!
+//! A node is identified by its structural path: the items enclosing it, its kind and a
+//! fingerprint of its code. Only the nodes still present in the minimized code when a
+//! minimization ends are persisted.
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use petgraph::{graph::NodeIndex, stable_graph::StableDiGraph, Direction};
+use quote::ToTokens;
+use syn::visit::Visit;
+
+use crate::{
+    builder::BuildError,
+    graph::GraphBuilder,
+    parser::{AstNode, ItemSummary},
+    paths::portable,
+    session::Session,
+};
+
+#[derive(Debug, Default)]
+pub struct RequiredNodes {
+    /// File the nodes are persisted to, nothing is persisted if not set.
+    path: Option<PathBuf>,
+    /// Nodes found required by the previous minimizations.
+    known: HashSet<String>,
+    /// Nodes found required by this minimization.
+    found: HashSet<String>,
+}
+
+impl RequiredNodes {
+    /// Loads the nodes the previous minimizations keeping `error` in `source_file`, allowing
+    /// extra errors or not, found required. Unless `skip` is set, they are not reported as
+    /// required, only recorded again.
+    pub fn load(
+        session: &Session,
+        source_file: &Path,
+        error: &BuildError,
+        allow_extra_errors: bool,
+        skip: bool,
+    ) -> Self {
+        let path = session
+            .required_dir()
+            .join(namespace(source_file, error, allow_extra_errors));
+        let known = if skip {
+            std::fs::read_to_string(&path)
+                .unwrap_or_default()
+                .lines()
+                .map(str::to_owned)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        Self {
+            path: Some(path),
+            known,
+            found: HashSet::new(),
+        }
+    }
+
+    /// Returns the number of nodes the previous minimizations found required.
+    pub fn known(&self) -> usize {
+        self.known.len()
+    }
+
+    /// Returns true if a previous minimization found the node required.
+    pub fn contains(&self, graph: &StableDiGraph<AstNode<'_>, ()>, node: NodeIndex) -> bool {
+        !self.known.is_empty() && self.known.contains(&node_path(graph, node))
+    }
+
+    /// Records that the oracle rejected removing the node.
+    pub fn record(&mut self, graph: &StableDiGraph<AstNode<'_>, ()>, node: NodeIndex) {
+        if self.path.is_some() {
+            self.found.insert(node_path(graph, node));
+        }
+    }
+
+    /// Persists the nodes found required, by this minimization or the previous ones, that are
+    /// still present in the minimized code.
+    pub fn save(&self, session: &Session, code: &str) -> Result<(), std::io::Error> {
+        let (Some(path), Ok(file)) = (&self.path, syn::parse_file(code)) else {
+            return Ok(());
+        };
+        let mut paths = node_paths(&file)
+            .into_iter()
+            .filter(|path| self.known.contains(path) || self.found.contains(path))
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+        session.create_dir(&session.required_dir())?;
+        std::fs::write(
+            path,
+            paths
+                .iter()
+                .map(|path| format!("{path}\n"))
+                .collect::<String>(),
+        )
+    }
+}
+
+/// Returns the name of the file of the nodes required to keep `error` in `source_file`, such as
+/// `src-main.rs-E0384-first` if it has to stay the first error, or `src-main.rs-E0384-any` if
+/// extra errors are allowed.
+fn namespace(source_file: &Path, error: &BuildError, allow_extra_errors: bool) -> String {
+    let oracle = if allow_extra_errors { "any" } else { "first" };
+    format!("{}-{}-{oracle}", portable(source_file), error.short())
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "._".contains(c) {
+                c
+            } else {
+                '-'
+            }
+        })
+        .take(120)
+        .collect()
+}
+
+/// Returns the structural paths of the nodes of the file, its root excluded.
+fn node_paths(file: &syn::File) -> Vec<String> {
+    let mut graph_builder = GraphBuilder::new();
+    graph_builder.visit_file(file);
+    let root = graph_builder.root_node();
+    let graph = graph_builder.graph().clone();
+    graph
+        .node_indices()
+        .filter(|node| Some(*node) != root)
+        .map(|node| node_path(&graph, node))
+        .collect()
+}
+
+/// Returns the structural path of the node, e.g. `impl Parser/fn parse/loc_stmt@<fingerprint>`.
+/// It does not depend on the position of the node, nor on the code around it besides the items
+/// enclosing it.
+fn node_path(graph: &StableDiGraph<AstNode<'_>, ()>, node: NodeIndex) -> String {
+    let code = graph[node].to_token_stream().to_string();
+    let mut path = format!("{:?}@{:016x}", graph[node], fingerprint(&code));
+    let mut current = node;
+    while let Some(parent) = graph
+        .neighbors_directed(current, Direction::Incoming)
+        .next()
+    {
+        if let AstNode::Item(item) = graph[parent] {
+            path = format!("{}/{path}", ItemSummary::from(item).description);
+        }
+        current = parent;
+    }
+    path
+}
+
+/// FNV-1a hash of the code, which unlike the hasher of the standard library is stable across
+/// toolchains.
+fn fingerprint(code: &str) -> u64 {
+    code.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{namespace, node_paths};
+    use crate::builder::BuildError;
+
+    #[test]
+    fn node_paths_ignore_positions_and_siblings() {
+        let paths = node_paths(
+            &syn::parse_file("fn main() {\n    let b = 0;\n    let a = 0;\n    b = 10;\n}")
+                .unwrap(),
+        );
+        let changed = node_paths(
+            &syn::parse_file("fn helper() {}\n\nfn main() {\n    let b = 0;\n    b = 10;\n}")
+                .unwrap(),
+        );
+
+        let stmt = |paths: &[String], index: usize| {
+            paths
+                .iter()
+                .filter(|path| path.starts_with("fn main/loc_stmt@"))
+                .nth(index)
+                .cloned()
+        };
+        assert!(stmt(&paths, 0).is_some());
+        assert_eq!(stmt(&paths, 0), stmt(&changed, 0));
+        assert_ne!(stmt(&paths, 0), stmt(&paths, 1));
+    }
+
+    #[test]
+    fn namespace_depends_on_the_oracle() {
+        let error = BuildError {
+            error_code: Some("E0384".to_owned()),
+            source_file: Some(PathBuf::from("src/main.rs")),
+            line: Some(4),
+            error_src: "cannot assign twice to immutable variable `b`".to_owned(),
+        };
+        let source_file = Path::new("src/main.rs");
+        assert_eq!(
+            namespace(source_file, &error, false),
+            "src-main.rs-E0384-first"
+        );
+        assert_eq!(
+            namespace(source_file, &error, true),
+            "src-main.rs-E0384-any"
+        );
+    }
+}
@@ -5,31 +5,119 @@
 //! AST guided searcher roughly works as:
 //!
 //! 1. Build target project using `CodeBuilder` and collect error codes.
-//! 2. Find which file causes the user specified error.
-//! 3. Parse the file, to generate AST as a graph.
+//! 2. Find which file causes the user specified error. If it is the root of an integration test,
+//!    e.g. `tests/foo.rs`, only that test is built from then on, with `--test foo`. Generated,
+//!    vendored and ignored files are skipped.
+//! 3. Parse the file, to generate AST as a graph. As an opening move, try stubbing every function
+//!    body not containing the error with `todo!()` at once.
 //! 4. Start doing a BFS over the graph. Remove a node and check if the `same` error code still exists.
+//!    Nodes the error provably requires, such as `main`, are skipped without a build.
 //!    4a. If same error code still exists mark it unncessary and continue with BFS order.
 //!    4b. If error changed or disappeared, start a new BFS from that node.
 //! 5. Continue until all nodes are visited or removing all childs of a node changes the error.
+//! 6. Run the rewriting passes over the reduced code, keeping the rewrites that preserve the error.
+//!    Depending on the profile, steps 4 to 6 are repeated until a round accepts nothing.
+//! 7. If requested, mark the sites of large deletions with comments.
+//! 8. If the program reads an input file, minimize it with ddmin, first by lines then by bytes.
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
+    io::Write,
+    ops::RangeInclusive,
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 use syn::visit::Visit;
 use thiserror::Error;
 
 use crate::{
-    builder::{CodeBuilder, CodeBuilderError},
-    generator::CodeGenerator,
-    graph::{GraphBuilder, SyntaxTree},
-    parser::AbstractSyntaxTree,
+    annotate::annotate,
+    builder::{run_executable, BuildError, BuildOutput, CodeBuilder, CodeBuilderError, ParseError},
+    cancel::CancelToken,
+    config::SearchConfig,
+    ddmin::ddmin,
+    exclude::Exclusions,
+    frontier::{Escalation, Frontier},
+    generator::{CodeGenerator, CodeGeneratorError},
+    graph::{GraphBuilder, Unsupported},
+    guard::{GuardError, TestGuard},
+    journal::{Journal, JournalError},
+    lockfile::{locked_dependencies, lockfile_path, LockedPackage, LockfileSnapshot},
+    manifest::LibTarget,
+    metrics::{Metrics, Snapshot},
+    oracle::{
+        BuildsOracle, DifferentialOracle, Oracle, OutputOracle, Rejection, SameErrorOracle,
+        VariantOutcome,
+    },
+    parser::{AbstractSyntaxTree, AstNode, NodeKind},
+    passes::{default_passes, stub_bodies_except, Pass},
+    prefilter::Prefilter,
+    printer::Printer,
     remover::NodeRemover,
+    report::diff_lines,
+    required::RequiredNodes,
+    session::{Session, SessionError},
+    toolchain::Toolchain,
 };
-pub trait Search {
-    fn search(self) -> Result<(), SearcherError>;
+pub trait Search: Sized {
+    fn search(self) -> Result<Option<Minimized>, SearcherError> {
+        self.search_with_cancel(CancelToken::new())
+    }
+
+    /// Searches until done or until the token is cancelled, returning the minimized code
+    /// accepted so far in the latter case.
+    fn search_with_cancel(self, cancel: CancelToken) -> Result<Option<Minimized>, SearcherError>;
+}
+
+/// The minimized version of the file causing the searched error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Minimized {
+    /// Path of the minimized file, relative to the target path.
+    pub source_file: PathBuf,
+    pub code: String,
+    /// The error preserved through the minimization, if the oracle is based on an error.
+    pub master_error: Option<BuildError>,
+    /// The file before the minimization.
+    pub original_code: String,
+    /// Number of variants tested, the original target included.
+    pub variants: usize,
+    /// Number of reductions accepted.
+    pub accepted: usize,
+    /// Time spent minimizing.
+    pub duration: Duration,
+    /// Dependency versions the minimized code was verified against.
+    pub locked_dependencies: Vec<LockedPackage>,
+    /// Toolchain the minimized code was verified with.
+    pub toolchain: Toolchain,
+    /// Crate types of the library, if the minimized file is its root and they are given.
+    pub crate_types: Vec<String>,
+    /// True if the search was cancelled before it was done.
+    pub cancelled: bool,
+    /// Constructs of the minimized code the reducer cannot model, left as they are.
+    pub unsupported: Vec<Unsupported>,
 }
 
+/// Name of the backup of the original input of the program.
+const INPUT_BACKUP: &str = "input.orig";
+
+/// Name of the toolchain snapshot saved in the journal.
+pub const TOOLCHAIN_COPY: &str = "toolchain";
+
+/// Name of the lockfile snapshot saved in the journal.
+const LOCKFILE_COPY: &str = "Cargo.lock";
+
+/// Name of the arguments of the minimization saved in the journal, separated by
+/// [`ARGUMENTS_SEPARATOR`].
+pub const ARGUMENTS_COPY: &str = "arguments";
+
+/// Separator of the saved arguments, which unlike a newline cannot be part of one.
+pub const ARGUMENTS_SEPARATOR: char = '\0';
+
+/// File minimized when the oracle runs the program and no target file is given, as there is no
+/// error pointing to a file.
+const RUN_TARGET_FILE: &str = "src/main.rs";
+
+#[derive(Clone, Copy)]
 pub enum Target<'a> {
     Path(&'a Path),
 }
@@ -44,11 +132,469 @@ impl<'a> From<Target<'a>> for CodeBuilder<'a> {
 
 pub struct ASTGuidedSearcher<'a> {
     target: Target<'a>,
+    config: SearchConfig,
 }
 
 impl<'a> ASTGuidedSearcher<'a> {
-    pub fn new(target: Target<'a>) -> Self {
-        Self { target }
+    pub fn new(target: Target<'a>, config: SearchConfig) -> Self {
+        Self { target, config }
+    }
+
+    /// Returns true if the searcher is allowed to try removing the node.
+    fn is_candidate(&self, node: &AstNode<'_>) -> bool {
+        !(self.config.preserve_pub_api && node.is_public_api())
+            && self.config.allows_kinds(&node.kinds())
+    }
+
+    /// Removes the nodes of the file's syntax tree in BFS order, keeping the removals the oracle
+    /// accepts and skipping the ones the prefilter or previous minimizations find required. The
+    /// removals the oracle rejects are recorded in `required`. Returns the code generated from the
+    /// reduced tree.
+    ///
+    /// The spans of the file point into `source`, which the variants are printed from unless they
+    /// are formatted. If `focus` is given, only the nodes spanning some of its lines are tried.
+    fn reduce_graph(
+        &self,
+        tester: &mut VariantTester<'_>,
+        file: &syn::File,
+        source: &str,
+        prefilter: &Prefilter,
+        required: &mut RequiredNodes,
+        focus: Option<&[RangeInclusive<usize>]>,
+    ) -> Result<String, SearcherError> {
+        let mut graph_builder = GraphBuilder::new();
+        graph_builder.visit_file(file);
+        let root = graph_builder
+            .root_node()
+            .ok_or(SearcherError::RootNodeFound)?;
+
+        let mut graph = graph_builder.graph().clone();
+        let mut frontier = Frontier::new(self.config.adaptive_order, self.config.escalate_after);
+        // Omit root node of the graph.
+        frontier.extend_children(&graph, root);
+
+        let code_generator = CodeGenerator::new();
+        let mut skip_set = HashSet::new();
+        while let Some(node_to_check) = frontier.pop(&graph) {
+            if tester.out_of_time() {
+                break;
+            }
+            if skip_set.contains(&node_to_check) {
+                continue;
+            }
+            let span = graph[node_to_check].span();
+            let in_focus = focus.is_none_or(|focus| {
+                focus.iter().any(|lines| {
+                    span.start().line <= *lines.end() && *lines.start() <= span.end().line
+                })
+            });
+            if !in_focus {
+                continue;
+            }
+            if !self.is_candidate(&graph[node_to_check])
+                || frontier.is_coarser(&graph[node_to_check])
+            {
+                frontier.extend_children(&graph, node_to_check);
+                continue;
+            }
+            if let Some(reason) = prefilter.required(&graph[node_to_check]) {
+                tester.skip(&format!("removed {:?}", graph[node_to_check]), reason);
+                frontier.extend_children(&graph, node_to_check);
+                continue;
+            }
+            if required.contains(&graph, node_to_check) {
+                tester.skip(
+                    &format!("removed {:?}", graph[node_to_check]),
+                    "required in a previous minimization",
+                );
+                frontier.extend_children(&graph, node_to_check);
+                continue;
+            }
+            let mut invariant_graph = graph.clone();
+            let removed_nodes = NodeRemover::remove_node(&mut invariant_graph, node_to_check);
+            let generated_code = self
+                .printer()
+                .print(source, &code_generator.generate(&invariant_graph, root)?);
+
+            let description = format!("removed {:?}", graph[node_to_check]);
+            let verdict = tester.verdict(&generated_code, &description)?;
+            let accepted = verdict == Verdict::Accepted;
+            match frontier.record(graph[node_to_check].kinds(), accepted) {
+                Some(Escalation::To(granularity)) => tester.note(&format!(
+                    "[escalate] nothing accepted in the last {} builds, trying {granularity}",
+                    self.config.escalate_after.unwrap_or_default()
+                )),
+                Some(Escalation::Exhausted) => {
+                    tester.note(&format!(
+                        "[escalate] nothing accepted in the last {} builds, ending the removals",
+                        self.config.escalate_after.unwrap_or_default()
+                    ));
+                    break;
+                }
+                None => {}
+            }
+            if accepted {
+                // Remove it from the actual graph.
+                skip_set.extend(removed_nodes);
+                graph = invariant_graph;
+            } else {
+                if verdict == Verdict::Rejected {
+                    required.record(&graph, node_to_check);
+                }
+                frontier.extend_children(&graph, node_to_check);
+            }
+            skip_set.insert(node_to_check);
+        }
+
+        Ok(self
+            .printer()
+            .print(source, &code_generator.generate(&graph, root)?))
+    }
+
+    /// Runs the rewriting passes over the code, keeping the rewrites the oracle accepts.
+    fn run_passes(
+        &self,
+        tester: &mut VariantTester<'_>,
+        code: String,
+    ) -> Result<String, SearcherError> {
+        rewrite_with_passes(
+            &default_passes(&self.config),
+            &self.printer(),
+            code,
+            |variant_code, description| {
+                if tester.out_of_time() {
+                    return Ok(None);
+                }
+                tester.test(variant_code, description).map(Some)
+            },
+        )
+    }
+
+    /// Minimizes the input file of the program with ddmin over its lines, then over its bytes,
+    /// keeping the reductions the oracle accepts. The original input is backed up in the session.
+    fn reduce_input(
+        &self,
+        tester: &mut VariantTester<'_>,
+        input_file: &Path,
+    ) -> Result<Vec<u8>, SearcherError> {
+        let input_path = self.base_path().join(input_file);
+        let original = std::fs::read(&input_path)
+            .map_err(|_| SearcherError::InputFileNotFound(input_file.to_path_buf()))?;
+        self.session().backup(INPUT_BACKUP, &original)?;
+
+        let lines = original
+            .split_inclusive(|byte| *byte == b'\n')
+            .collect::<Vec<_>>();
+        let kept_lines = ddmin(&lines, None, |kept| {
+            if tester.out_of_time() {
+                return Ok(false);
+            }
+            std::fs::write(&input_path, kept.concat())?;
+            tester.is_interesting("input line reduction")
+        })?
+        .kept
+        .concat();
+
+        let kept_bytes = ddmin(&kept_lines, None, |kept| {
+            if tester.out_of_time() {
+                return Ok(false);
+            }
+            std::fs::write(&input_path, kept)?;
+            tester.is_interesting("input byte reduction")
+        })?
+        .kept;
+        std::fs::write(&input_path, &kept_bytes)?;
+        Ok(kept_bytes)
+    }
+
+    /// Opens the journal of the previous session and restores its last accepted state, making
+    /// sure it was recorded with the same toolchain and dependencies.
+    fn resume_journal(&self, toolchain: &Toolchain) -> Result<Journal, SearcherError> {
+        let journal = Journal::open(&self.session())?;
+        check_toolchain(&journal, toolchain)?;
+        check_lockfile(&journal, self.base_path())?;
+        let last_checkpoint = journal.checkpoints().len() - 1;
+        journal.rollback(last_checkpoint)?;
+        println!("Resuming from checkpoint {last_checkpoint}");
+        Ok(journal)
+    }
+
+    /// Opens the journal of the previous session and records the file as edited since its last
+    /// accepted state, returning the journal and the edited lines of the file.
+    fn refine_journal(
+        &self,
+        toolchain: &Toolchain,
+    ) -> Result<(Journal, Vec<RangeInclusive<usize>>), SearcherError> {
+        let mut journal = Journal::open(&self.session())?;
+        check_toolchain(&journal, toolchain)?;
+        check_lockfile(&journal, self.base_path())?;
+        let minimized = journal.snapshot(journal.checkpoints().len() - 1)?;
+        let file_path = self.base_path().join(journal.source_file());
+        let edited = std::fs::read_to_string(&file_path)
+            .map_err(|_| SearcherError::ErrorSourceFileNotFound(journal.source_file().into()))?;
+        let edited_lines = edited_lines(&minimized, &edited);
+        if edited_lines.is_empty() {
+            println!("No edits since the last minimization.");
+        } else {
+            journal.record(&edited, "edited")?;
+            let lines = edited_lines
+                .iter()
+                .map(|lines| format!("{}-{}", lines.start(), lines.end()))
+                .collect::<Vec<_>>();
+            println!("Refining around the edited lines {}", lines.join(", "));
+        }
+        Ok((journal, edited_lines))
+    }
+
+    fn printer(&self) -> Printer {
+        Printer::new(self.config.emit_mode, self.config.preserve_positions)
+    }
+
+    fn base_path(&self) -> &Path {
+        let Target::Path(base_path) = self.target;
+        base_path
+    }
+
+    /// Session of the target the minimization is journaled in, the one named with `--session` if
+    /// any.
+    fn session(&self) -> Session {
+        Session::new(self.base_path()).named(self.config.session.as_deref())
+    }
+}
+
+/// Applies the rewrites of the passes to the code one at a time, keeping the ones `test` accepts,
+/// until `test` returns `None` as no more variant can be tested. A rewrite back into a code
+/// accepted before, e.g. undoing the rewrite of a trait object, is not tried again, otherwise two
+/// rewrites the oracle both accepts would take turns forever.
+fn rewrite_with_passes(
+    passes: &[Box<dyn Pass>],
+    printer: &Printer,
+    mut code: String,
+    mut test: impl FnMut(&str, &str) -> Result<Option<bool>, SearcherError>,
+) -> Result<String, SearcherError> {
+    let mut file = AbstractSyntaxTree::parse(&code).syn_file();
+    let mut accepted = HashSet::from([code.clone()]);
+    for pass in passes {
+        let mut candidate = 0;
+        while candidate < pass.candidates(&file) {
+            let mut variant = file.clone();
+            pass.apply(&mut variant, candidate);
+            let variant_code = printer.print(&code, &variant);
+            if accepted.contains(&variant_code) {
+                candidate += 1;
+                continue;
+            }
+
+            let description = format!("{} rewrite #{candidate}", pass.name());
+            match test(&variant_code, &description)? {
+                None => return Ok(code),
+                Some(true) => {
+                    // The accepted rewrite can take other candidates with it, e.g. the
+                    // alternatives of its site, so the candidates of the rewritten file are
+                    // tried from the first one again. The file is parsed again so that its spans
+                    // point into the new code.
+                    file = AbstractSyntaxTree::parse(&variant_code).syn_file();
+                    accepted.insert(variant_code.clone());
+                    code = variant_code;
+                    candidate = 0;
+                }
+                Some(false) => candidate += 1,
+            }
+        }
+    }
+    Ok(code)
+}
+
+/// Outcome of testing a variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Accepted,
+    /// The oracle rejected the variant.
+    Rejected,
+    /// The variant was not tested as the search is out of time, or the oracle accepted it but
+    /// its re-verification or the guarded tests did not.
+    Dismissed,
+}
+
+/// Writes variants of the minimized file, asks the oracle about them and journals the accepted
+/// ones.
+struct VariantTester<'a> {
+    config: &'a SearchConfig,
+    code_builder: CodeBuilder<'a>,
+    oracle: Box<dyn Oracle>,
+    file_path: PathBuf,
+    journal: Journal,
+    /// Log of the session the verdicts are written to.
+    log: std::fs::File,
+    /// Number of variants tested so far, the original target included.
+    variants: usize,
+    /// Number of variants rejected for each reason, only counted if the rejections are explained.
+    rejections: HashMap<Rejection, usize>,
+    /// Size of the last accepted code, see [`code_size`].
+    accepted_size: usize,
+    /// Metrics file the progress is written to, if asked for.
+    metrics: Option<Metrics>,
+    /// Test suite whose outcomes the accepted variants have to keep, if asked for.
+    guard: Option<TestGuard>,
+    target: &'a Path,
+    /// Dependencies resolved when the search started, the verdicts are only valid as long as
+    /// they stay the same.
+    lockfile: LockfileSnapshot,
+    cancel: CancelToken,
+}
+
+impl VariantTester<'_> {
+    /// Builds the current state of the target, runs it if the oracle requires so, and asks the
+    /// oracle whether it is still interesting. A one-line verdict is printed for the variant.
+    fn is_interesting(&mut self, description: &str) -> Result<bool, SearcherError> {
+        let build_output = self.code_builder.build()?;
+        let changes = self
+            .lockfile
+            .changes(&LockfileSnapshot::capture(self.target)?);
+        if !changes.is_empty() {
+            // The verdict of this variant cannot be trusted, the file is left in its last
+            // accepted state to resume from.
+            let last_checkpoint = self.journal.checkpoints().len() - 1;
+            std::fs::write(&self.file_path, self.journal.snapshot(last_checkpoint)?)?;
+            return Err(SearcherError::LockfileChanged(changes.join(", ")));
+        }
+        let run_output = if self.oracle.requires_run() && build_output.success {
+            Some(self.code_builder.run(self.config.run_timeout)?)
+        } else {
+            None
+        };
+        let outcome = VariantOutcome {
+            build_errors: build_output.build_errors.clone(),
+            run_output,
+        };
+        let interesting = self.oracle.is_interesting(&outcome);
+        self.report(description, interesting, &outcome, &build_output);
+        let snapshot = self.snapshot();
+        if let Some(metrics) = &mut self.metrics {
+            metrics.tick(&snapshot);
+        }
+        Ok(interesting)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            variants: self.variants,
+            accepted: self.journal.checkpoints().len() - 1,
+            code_size: self.accepted_size,
+        }
+    }
+
+    /// Returns true if the deadline of the search has passed or the search was cancelled, no
+    /// variant is tested after it.
+    fn out_of_time(&self) -> bool {
+        self.cancel.is_cancelled()
+            || self
+                .config
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Prints and logs the verdict of a variant, together with the full compiler output if it is
+    /// verbose or the verdict is unexpected.
+    fn report(
+        &mut self,
+        description: &str,
+        interesting: bool,
+        outcome: &VariantOutcome,
+        build_output: &BuildOutput,
+    ) {
+        let verdict = if interesting { "accepted" } else { "rejected" };
+        let mut line = format!(
+            "[{:>4}] {description}: {verdict} ({})",
+            self.variants,
+            outcome.summary()
+        );
+        if !interesting && self.config.explain_rejections {
+            let rejection = self.oracle.explain_rejection(outcome);
+            line.push_str(&format!(": {rejection}"));
+            *self.rejections.entry(rejection).or_default() += 1;
+        }
+        println!("{line}");
+        // The log is only informative, failing to write it does not stop the search.
+        let _ = writeln!(self.log, "{line}");
+        // The original target is expected to be interesting, and a failing build is expected to
+        // report some error.
+        let unexpected = (self.variants == 0 && !interesting)
+            || (!build_output.success && build_output.build_errors.errors.is_empty());
+        if self.config.verbose || unexpected {
+            eprint!("{}", build_output.stderr);
+        }
+        self.variants += 1;
+    }
+
+    /// Prints the number of variants rejected for each reason, the most frequent first.
+    fn print_rejections(&self) {
+        let mut rejections = self.rejections.iter().collect::<Vec<_>>();
+        rejections
+            .sort_by_key(|(rejection, count)| (std::cmp::Reverse(**count), rejection.to_string()));
+        println!("Rejection reasons:");
+        for (rejection, count) in rejections {
+            println!("{count:>6}  {rejection}");
+        }
+    }
+
+    /// Prints and logs a removal that is not tried, as the node is required.
+    fn skip(&mut self, description: &str, reason: &str) {
+        self.note(&format!("[skip] {description}: {reason}"));
+    }
+
+    /// Prints and logs a line about the progress of the search.
+    fn note(&mut self, line: &str) {
+        println!("{line}");
+        let _ = writeln!(self.log, "{line}");
+    }
+
+    /// Writes the variant into the minimized file and returns true if it is still interesting,
+    /// recording it in the journal if so.
+    fn test(&mut self, code: &str, description: &str) -> Result<bool, SearcherError> {
+        Ok(self.verdict(code, description)? == Verdict::Accepted)
+    }
+
+    /// Writes the variant into the minimized file and returns whether it is accepted, rejected by
+    /// the oracle or dismissed for another reason, recording it in the journal if accepted.
+    fn verdict(&mut self, code: &str, description: &str) -> Result<Verdict, SearcherError> {
+        if self.out_of_time() {
+            return Ok(Verdict::Dismissed);
+        }
+        std::fs::write(&self.file_path, code)?;
+        if !self.is_interesting(description)? {
+            return Ok(Verdict::Rejected);
+        }
+        let mut interesting = true;
+        let removed = removed_percent(self.accepted_size, code_size(code));
+        if self.config.verify_accepted {
+            interesting = self.is_interesting(&format!("{description}, verified"))?;
+        } else if self
+            .config
+            .max_step_removal
+            .is_some_and(|max_step_removal| removed > usize::from(max_step_removal))
+        {
+            // A large removal is more likely to be accepted by a mistake of the oracle.
+            interesting = self.is_interesting(&format!(
+                "{description}, verified as it removes {removed}% of the code"
+            ))?;
+        }
+        if let Some(guard) = self.guard.as_ref().filter(|_| interesting) {
+            if let Some(change) = guard.check()? {
+                interesting = false;
+                self.note(&format!("[guard] {description}: rejected, {change}"));
+                if self.config.explain_rejections {
+                    let rejection = Rejection::TestsChanged(change.to_string());
+                    *self.rejections.entry(rejection).or_default() += 1;
+                }
+            }
+        }
+        if !interesting {
+            return Ok(Verdict::Dismissed);
+        }
+        self.journal.record(code, description)?;
+        self.accepted_size = code_size(code);
+        Ok(Verdict::Accepted)
     }
 }
 
@@ -60,8 +606,58 @@ pub enum SearcherError {
     ErrorSourceFileIsMissing(String),
     #[error("Cannot find source of error file locally at: {0}")]
     ErrorSourceFileNotFound(PathBuf),
+    #[error("No error is reported in the target file: {0}")]
+    NoErrorInTargetFile(PathBuf),
+    #[error("Cannot find the input file of the program at: {0}")]
+    InputFileNotFound(PathBuf),
+    #[error("Cannot run the reference binary {0}: {1}")]
+    ReferenceRunError(PathBuf, std::io::Error),
+    #[error("The toolchain changed since the resumed session, from `{0}` to `{1}`")]
+    ToolchainChanged(String, String),
+    #[error("The resumed session minimized {0} but the first error is now in {1}, rerun without --resume to start a new minimization")]
+    ResumedFileChanged(PathBuf, PathBuf),
+    #[error("Cargo.lock changed since the resumed session ({0}), its reductions were accepted with the previous dependencies, rerun without --resume to start a new minimization")]
+    LockfileChangedSinceSession(String),
+    #[error("Cargo.lock changed during the minimization ({0}), the file was restored to its last accepted reduction, continue with --resume once the dependencies are settled")]
+    LockfileChanged(String),
     #[error("AST seems to be missing a root node")]
     RootNodeFound,
+    #[error("The original target is not interesting for the oracle")]
+    OriginalNotInteresting,
+    #[error("Error while guarding the tests: {0}")]
+    GuardOperationError(GuardError),
+    #[error("Error while using the session directory: {0}")]
+    SessionOperationError(SessionError),
+    #[error("Error while recording the reduction journal: {0}")]
+    JournalOperationError(JournalError),
+    #[error("Error while generating code variant: {0}")]
+    CodeGenerationError(CodeGeneratorError),
+    #[error("IO error emitted from searcher: {0}")]
+    IOError(std::io::Error),
+}
+
+impl From<JournalError> for SearcherError {
+    fn from(value: JournalError) -> Self {
+        Self::JournalOperationError(value)
+    }
+}
+
+impl From<GuardError> for SearcherError {
+    fn from(value: GuardError) -> Self {
+        Self::GuardOperationError(value)
+    }
+}
+
+impl From<SessionError> for SearcherError {
+    fn from(value: SessionError) -> Self {
+        Self::SessionOperationError(value)
+    }
+}
+
+impl From<ParseError> for SearcherError {
+    fn from(value: ParseError) -> Self {
+        Self::BuildOperationError(value.into())
+    }
 }
 
 impl From<CodeBuilderError> for SearcherError {
@@ -70,68 +666,753 @@ impl From<CodeBuilderError> for SearcherError {
     }
 }
 
+impl From<CodeGeneratorError> for SearcherError {
+    fn from(value: CodeGeneratorError) -> Self {
+        Self::CodeGenerationError(value)
+    }
+}
+
+impl From<std::io::Error> for SearcherError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IOError(value)
+    }
+}
+
 impl Search for ASTGuidedSearcher<'_> {
-    fn search(self) -> Result<(), SearcherError> {
+    fn search_with_cancel(self, cancel: CancelToken) -> Result<Option<Minimized>, SearcherError> {
+        let started = Instant::now();
         let Target::Path(base_path) = self.target;
-        let code_builder = CodeBuilder::from(self.target);
-        let variant_errors = code_builder.collect_errors()?;
-
-        // TODO: Maybe add an option for users to be able to specify this.
-        let master_error = variant_errors.errors.first();
-
-        if let Some(master_error) = master_error {
-            // We are searching the root for this error.
-            let root_file = master_error.source_file.as_ref().ok_or_else(|| {
-                SearcherError::ErrorSourceFileIsMissing(master_error.error_src.clone())
-            })?;
-
-            let file_str = std::fs::read_to_string(root_file)
-                .map_err(|_| SearcherError::ErrorSourceFileNotFound(root_file.to_path_buf()))?;
-            let ast = AbstractSyntaxTree::parse(file_str);
-
-            let file = ast.syn_file();
-
-            let mut syntax_tree = SyntaxTree::new();
-            let mut graph_builder = GraphBuilder::new(&mut syntax_tree, None, None);
-            graph_builder.visit_file(&file);
-            let root = graph_builder
-                .root_node()
-                .ok_or(SearcherError::RootNodeFound)?;
-
-            let mut graph = graph_builder.syntax_tree().graph();
-            let mut bfs = petgraph::visit::Bfs::new(&graph, root);
-            // Omit root node of the graph.
-            let _ = bfs.next(&graph);
-
-            let mut code_generator = CodeGenerator::new();
-            let file_path = base_path.join(root_file).canonicalize().unwrap();
-            let code_builder = CodeBuilder::Path(base_path);
-            let mut skip_set = HashSet::new();
-            while let Some(node_to_check) = bfs.next(&graph) {
-                if skip_set.contains(&node_to_check) {
-                    continue;
+        let session = self.session();
+        let _lock = session.lock()?;
+        let toolchain = Toolchain::capture(base_path)?;
+        let (resumed_journal, edited_lines) = if self.config.refine {
+            let (journal, edited_lines) = self.refine_journal(&toolchain)?;
+            (Some(journal), Some(edited_lines))
+        } else if self.config.resume {
+            (Some(self.resume_journal(&toolchain)?), None)
+        } else {
+            (None, None)
+        };
+        // Integration tests are not built by default, they are built to look for the error if the
+        // default targets build. An oracle running the program does not look for one.
+        let variant_errors = match &self.config.target_file {
+            Some(target_file) => {
+                CodeBuilder::for_root_file(base_path, target_file).collect_errors()?
+            }
+            None => {
+                let variant_errors = CodeBuilder::from(self.target).collect_errors()?;
+                let runs_program =
+                    self.config.reference.is_some() || self.config.expected_output.is_some();
+                if variant_errors.errors.is_empty() && !runs_program {
+                    CodeBuilder::Tests(base_path).collect_errors()?
+                } else {
+                    variant_errors
                 }
-                let mut invariant_graph = graph.clone();
-                let removed_nodes = NodeRemover::remove_node(&mut invariant_graph, node_to_check);
-                let generated_code = code_generator.generate(&invariant_graph, root).unwrap();
-
-                std::fs::write(&file_path, &generated_code).unwrap();
-                let variant_errors = code_builder.collect_errors()?;
-                let variant_master_error = variant_errors.errors.first();
-
-                if variant_master_error == Some(master_error) {
-                    // Remove it from the actual graph.
-                    skip_set.extend(removed_nodes);
-                    graph = invariant_graph;
+            }
+        };
+
+        // Generated, vendored and ignored files are only minimized if asked for.
+        let exclusions = Exclusions::load(base_path);
+        let is_considered =
+            |error: &BuildError| match (&self.config.target_file, &error.source_file) {
+                (Some(target_file), source_file) => source_file.as_ref() == Some(target_file),
+                (None, Some(source_file)) => !exclusions.is_excluded(source_file),
+                (None, None) => true,
+            };
+        let excluded_count = variant_errors
+            .errors
+            .iter()
+            .filter(|error| self.config.target_file.is_none() && !is_considered(error))
+            .count();
+        if excluded_count > 0 {
+            println!("Skipping {excluded_count} errors in generated, vendored or ignored files.");
+        }
+
+        let mut errors_by_file = variant_errors.by_source_file();
+        errors_by_file.retain(|source_file, _| !exclusions.is_excluded(source_file));
+        if errors_by_file.len() > 1 && self.config.target_file.is_none() {
+            println!("Errors are reported in {} files:", errors_by_file.len());
+            for (source_file, errors) in &errors_by_file {
+                println!("  {} ({} errors)", source_file.display(), errors.len());
+            }
+            println!(
+                "Minimizing the file of the first error, use --target-file to choose another."
+            );
+        }
+
+        // Only the errors of the target file are considered if one is given.
+        let target_errors = variant_errors
+            .errors
+            .iter()
+            .filter(|error| is_considered(error))
+            .collect::<Vec<_>>();
+        let master_error = match &self.config.oracle {
+            // Prefer the first error the expression asks to keep.
+            Some(expression) => target_errors
+                .iter()
+                .find(|error| expression.mentions_error(error))
+                .or(target_errors.first()),
+            None => target_errors.first(),
+        }
+        .map(|error| (*error).clone());
+
+        let reference_output = match &self.config.reference {
+            Some(reference) => Some(
+                run_executable(reference, base_path, self.config.run_timeout)
+                    .map_err(|error| SearcherError::ReferenceRunError(reference.clone(), error))?,
+            ),
+            None => None,
+        };
+
+        let (root_file, oracle): (PathBuf, Box<dyn Oracle>) = match (
+            reference_output,
+            &self.config.expected_output,
+            &self.config.oracle,
+            &master_error,
+        ) {
+            // The target file is always given when pruning.
+            (_, _, _, _) if self.config.prune => (
+                self.config
+                    .target_file
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from(RUN_TARGET_FILE)),
+                Box::new(BuildsOracle),
+            ),
+            (Some(reference_output), _, _, _) => (
+                self.config
+                    .target_file
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from(RUN_TARGET_FILE)),
+                Box::new(DifferentialOracle::new(reference_output)),
+            ),
+            (None, Some(expected_output), _, _) => (
+                self.config
+                    .target_file
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from(RUN_TARGET_FILE)),
+                Box::new(OutputOracle::new(expected_output.clone())),
+            ),
+            (None, None, Some(expression), _) => (
+                self.config
+                    .target_file
+                    .clone()
+                    .or_else(|| master_error.as_ref()?.source_file.clone())
+                    .unwrap_or_else(|| PathBuf::from(RUN_TARGET_FILE)),
+                expression.to_oracle(),
+            ),
+            (None, None, None, Some(master_error)) => {
+                // We are searching the root for this error.
+                let root_file = master_error.source_file.clone().ok_or_else(|| {
+                    SearcherError::ErrorSourceFileIsMissing(master_error.error_src.clone())
+                })?;
+                (
+                    root_file,
+                    Box::new(
+                        SameErrorOracle::new(master_error.clone())
+                            .allowing_extra_errors(self.config.allow_extra_errors),
+                    ),
+                )
+            }
+            (None, None, None, None) => match &self.config.target_file {
+                Some(target_file) => {
+                    return Err(SearcherError::NoErrorInTargetFile(target_file.clone()))
                 }
-                skip_set.insert(node_to_check);
+                None => return Ok(None),
+            },
+        };
+
+        let file_path = base_path.join(&root_file);
+        let file_str = std::fs::read_to_string(&file_path)
+            .map_err(|_| SearcherError::ErrorSourceFileNotFound(root_file.clone()))?;
+        let journal = match resumed_journal {
+            // Another journal would replace the one resumed, with its checkpoints.
+            Some(journal) if journal.source_file() != root_file => {
+                return Err(SearcherError::ResumedFileChanged(
+                    journal.source_file().to_path_buf(),
+                    root_file,
+                ))
             }
+            Some(journal) => journal,
+            None => {
+                let journal = Journal::create(&session, &root_file, &file_str)?;
+                journal.save_copy(TOOLCHAIN_COPY, toolchain.to_string().as_bytes())?;
+                if let Ok(lockfile) = std::fs::read(lockfile_path(base_path)) {
+                    journal.save_copy(LOCKFILE_COPY, &lockfile)?;
+                }
+                if !self.config.arguments.is_empty() {
+                    let arguments = self.config.arguments.join(&ARGUMENTS_SEPARATOR.to_string());
+                    journal.save_copy(ARGUMENTS_COPY, arguments.as_bytes())?;
+                }
+                journal
+            }
+        };
+        let original_code = journal.snapshot(0)?;
+        let mut tester = VariantTester {
+            config: &self.config,
+            code_builder: CodeBuilder::for_root_file(base_path, &root_file),
+            oracle,
+            file_path: file_path.clone(),
+            journal,
+            log: session.open_log(self.config.resume)?,
+            variants: 0,
+            rejections: HashMap::new(),
+            accepted_size: code_size(&file_str),
+            metrics: if self.config.metrics {
+                Some(Metrics::open(&session, self.config.resume)?)
+            } else {
+                None
+            },
+            guard: None,
+            target: base_path,
+            // Taken after the first build, which creates the lockfile if the target has none.
+            lockfile: LockfileSnapshot::capture(base_path)?,
+            cancel,
+        };
+        let starting_point = if self.config.refine {
+            "edited"
+        } else if tester.journal.checkpoints().len() > 1 {
+            "resumed"
+        } else {
+            "original"
+        };
+        if !tester.is_interesting(starting_point)? {
+            return Err(SearcherError::OriginalNotInteresting);
+        }
+        if let Some(filters) = &self.config.guard_tests {
+            let guard = TestGuard::new(base_path, filters.clone(), self.config.run_timeout)?;
+            tester.note(&format!(
+                "Guarding the outcomes of {} tests",
+                guard.test_count()
+            ));
+            tester.guard = Some(guard);
+        }
+
+        let ast = AbstractSyntaxTree::parse(&file_str);
+        let mut file = ast.syn_file();
+
+        // Opening move: stub every function body not containing the error in a single variant.
+        let error_line = master_error
+            .as_ref()
+            .filter(|master_error| master_error.source_file.as_ref() == Some(&root_file))
+            .and_then(|master_error| master_error.line);
+        if let Some(error_line) = error_line.filter(|_| {
+            self.config.stub_bodies && self.config.allows_kinds(&[NodeKind::Fn, NodeKind::Block])
+        }) {
+            let mut stubbed = file.clone();
+            if stub_bodies_except(&mut stubbed, error_line)
+                && tester.test(
+                    &self.printer().print(&file_str, &stubbed),
+                    "stubbed function bodies",
+                )?
+            {
+                file = stubbed;
+            }
+        }
+
+        // The prefilter reasons about the first error, it only applies if it is the one kept.
+        let keeps_first_error = self.config.reference.is_none()
+            && self.config.expected_output.is_none()
+            && self.config.oracle.is_none();
+        let prefilter = match &master_error {
+            Some(master_error) if self.config.prefilter && keeps_first_error => {
+                Prefilter::new(&file, &file_str, &root_file, master_error)
+            }
+            _ => Prefilter::default(),
+        };
+        let mut required_nodes = match &master_error {
+            Some(master_error) if keeps_first_error => RequiredNodes::load(
+                &session,
+                &root_file,
+                master_error,
+                self.config.allow_extra_errors,
+                self.config.prefilter,
+            ),
+            _ => RequiredNodes::default(),
+        };
+        if required_nodes.known() > 0 {
+            println!(
+                "Skipping the {} nodes previous minimizations found required",
+                required_nodes.known()
+            );
+        }
+
+        let mut final_answer = self.reduce_graph(
+            &mut tester,
+            &file,
+            &file_str,
+            &prefilter,
+            &mut required_nodes,
+            edited_lines.as_deref(),
+        )?;
+        let mut round = 1;
+        loop {
+            let accepted = tester.journal.checkpoints().len();
+            if self.config.run_passes {
+                final_answer = self.run_passes(&mut tester, final_answer)?;
+            }
+            if self
+                .config
+                .max_rounds
+                .is_some_and(|max_rounds| round >= max_rounds)
+                || tester.out_of_time()
+            {
+                break;
+            }
+            let file = AbstractSyntaxTree::parse(&final_answer).syn_file();
+            final_answer = self.reduce_graph(
+                &mut tester,
+                &file,
+                &final_answer,
+                &prefilter.without_positions(),
+                &mut required_nodes,
+                None,
+            )?;
+            round += 1;
+            if tester.journal.checkpoints().len() == accepted {
+                break;
+            }
+        }
+        required_nodes.save(&session, &final_answer)?;
+        if self.config.annotate {
+            final_answer = annotate(&original_code, &final_answer);
+        }
+        if self.config.explain_rejections {
+            tester.print_rejections();
+        }
+
+        // A cancelled search leaves the last tested variant in the file, the last accepted one
+        // replaces it.
+        std::fs::write(&file_path, &final_answer)?;
+        if tester.cancel.is_cancelled() {
+            println!("Search cancelled, keeping the reductions accepted so far.");
+        }
+        println!("Minimized the code into:");
+        println!("{final_answer}");
+        let unsupported = unsupported_constructs(&final_answer);
+        if !unsupported.is_empty() {
+            println!("Not reduced because unsupported:");
+            for construct in &unsupported {
+                println!("  {construct}");
+            }
+        }
+
+        if let Some(input_file) = &self.config.input_file {
+            let input = self.reduce_input(&mut tester, input_file)?;
+            println!("Minimized the input into:");
+            println!("{}", String::from_utf8_lossy(&input));
+        }
+
+        let snapshot = tester.snapshot();
+        if let Some(metrics) = &mut tester.metrics {
+            metrics.record(&snapshot);
+        }
+
+        let locked_dependencies = match locked_dependencies(base_path)? {
+            Some(locked_dependencies) => locked_dependencies,
+            None => {
+                println!(
+                    "No lockfile found at {}, the versions of the dependencies are not recorded.",
+                    lockfile_path(base_path).display()
+                );
+                vec![]
+            }
+        };
+        if !locked_dependencies.is_empty() {
+            println!("Verified against the dependencies:");
+            for package in &locked_dependencies {
+                println!("{}", package.pinned_requirement());
+            }
+        }
+
+        let crate_types = LibTarget::load(base_path)
+            .filter(|lib| lib.path == root_file)
+            .map(|lib| lib.crate_types)
+            .unwrap_or_default();
+
+        Ok(Some(Minimized {
+            source_file: root_file,
+            code: final_answer,
+            master_error,
+            original_code,
+            variants: tester.variants,
+            accepted: tester.journal.checkpoints().len() - 1,
+            duration: started.elapsed(),
+            toolchain,
+            locked_dependencies,
+            crate_types,
+            cancelled: tester.cancel.is_cancelled(),
+            unsupported,
+        }))
+    }
+}
+
+/// Size of the code ignoring whitespace, so that reformatting it does not change its size.
+fn code_size(code: &str) -> usize {
+    code.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+/// Returns the percentage of the code removed by going from `previous` to `current` in size.
+fn removed_percent(previous: usize, current: usize) -> usize {
+    (previous.saturating_sub(current) * 100)
+        .checked_div(previous)
+        .unwrap_or_default()
+}
+
+/// Returns the constructs of the code the graph does not model, with their positions in it.
+fn unsupported_constructs(code: &str) -> Vec<Unsupported> {
+    let file = AbstractSyntaxTree::parse(code).syn_file();
+    let mut graph_builder = GraphBuilder::new();
+    graph_builder.visit_file(&file);
+    graph_builder.unsupported().to_vec()
+}
+
+/// Makes sure the journal was recorded with the toolchain, if it recorded one.
+fn check_toolchain(journal: &Journal, toolchain: &Toolchain) -> Result<(), SearcherError> {
+    match journal.read_copy(TOOLCHAIN_COPY)? {
+        Some(recorded) => {
+            let Ok(recorded) = recorded.parse::<Toolchain>();
+            if recorded != *toolchain {
+                return Err(SearcherError::ToolchainChanged(
+                    recorded.rustc_version().to_owned(),
+                    toolchain.rustc_version().to_owned(),
+                ));
+            }
+        }
+        None => println!("The previous session did not record its toolchain, continuing anyway."),
+    }
+    Ok(())
+}
 
-            let final_answer = code_generator.generate(&graph, root).unwrap();
-            std::fs::write(&file_path, &final_answer).unwrap();
-            println!("Minimized the code into:");
-            println!("{final_answer}");
+/// Makes sure the dependencies resolved for the target did not change since the journal was
+/// started, the journaled reductions were accepted with the previous ones.
+fn check_lockfile(journal: &Journal, target: &Path) -> Result<(), SearcherError> {
+    if let Some(recorded) = journal.read_copy(LOCKFILE_COPY)? {
+        let changes =
+            LockfileSnapshot::parse(&recorded).changes(&LockfileSnapshot::capture(target)?);
+        if !changes.is_empty() {
+            return Err(SearcherError::LockfileChangedSinceSession(
+                changes.join(", "),
+            ));
         }
-        Ok(())
+    }
+    Ok(())
+}
+
+/// Returns the ranges of the lines of `current`, counted from 1, that are not in `previous`. The
+/// lines around a plain deletion count as edited.
+fn edited_lines(previous: &str, current: &str) -> Vec<RangeInclusive<usize>> {
+    let previous_lines = previous.lines().collect::<Vec<_>>();
+    let current_lines = current.lines().collect::<Vec<_>>();
+    let (previous_kept, current_kept) = diff_lines(&previous_lines, &current_lines);
+
+    let mut edited = vec![false; current_lines.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < previous_lines.len() || j < current_lines.len() {
+        let deletion_start = i;
+        while i < previous_lines.len() && !previous_kept[i] {
+            i += 1;
+        }
+        let insertion_start = j;
+        while j < current_lines.len() && !current_kept[j] {
+            edited[j] = true;
+            j += 1;
+        }
+        if i > deletion_start && j == insertion_start {
+            let around = j.saturating_sub(1)..(j + 1).min(current_lines.len());
+            edited[around].fill(true);
+        }
+        // The next common line.
+        i += 1;
+        j += 1;
+    }
+
+    let mut ranges: Vec<RangeInclusive<usize>> = vec![];
+    for line in (1..=edited.len()).filter(|line| edited[line - 1]) {
+        match ranges.last_mut() {
+            Some(range) if *range.end() + 1 == line => *range = *range.start()..=line,
+            _ => ranges.push(line..=line),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{
+        code_size, edited_lines, removed_percent, rewrite_with_passes, ASTGuidedSearcher,
+        Minimized, Search, SearcherError, Target, ARGUMENTS_COPY, INPUT_BACKUP, LOCKFILE_COPY,
+    };
+    use crate::{
+        builder::CodeBuilder,
+        config::SearchConfig,
+        generator::CodeGenerator,
+        journal::Journal,
+        oracle::Oracle,
+        passes::{ControlFlowPass, DynTraitPass, ImplTraitPass, Pass},
+        pattern::OutputPattern,
+        printer::Printer,
+        session::Session,
+        test_dir::TestDir,
+    };
+
+    fn assert_send<T: Send>() {}
+
+    /// Copies the test project into a new test directory.
+    fn test_project(name: &str) -> TestDir {
+        let test_dir = TestDir::new(name);
+        let project = Path::new(env!("CARGO_MANIFEST_DIR")).join("test/data/test_project");
+        std::fs::create_dir_all(test_dir.path().join("src")).unwrap();
+        for file in ["Cargo.toml", "Cargo.lock", "src/main.rs"] {
+            std::fs::copy(project.join(file), test_dir.path().join(file)).unwrap();
+        }
+        test_dir
+    }
+
+    fn resume(target: &Path) -> Result<Option<Minimized>, SearcherError> {
+        let config = SearchConfig {
+            resume: true,
+            ..SearchConfig::default()
+        };
+        ASTGuidedSearcher::new(Target::Path(target), config).search()
+    }
+
+    #[test]
+    fn resume_keeps_the_journal_of_another_file() {
+        let test_dir = test_project("resume-file");
+        let session = Session::new(test_dir.path());
+        Journal::create(&session, Path::new("src/other.rs"), "fn other() {}\n").unwrap();
+
+        assert!(matches!(
+            resume(test_dir.path()),
+            Err(SearcherError::ResumedFileChanged(..))
+        ));
+        assert_eq!(
+            Journal::open(&session).unwrap().source_file(),
+            Path::new("src/other.rs")
+        );
+    }
+
+    #[test]
+    fn named_session_saves_its_arguments() {
+        let test_dir = test_project("arguments");
+        let config = SearchConfig {
+            session: Some("borrowck".to_owned()),
+            allow_extra_errors: true,
+            arguments: vec![
+                "--session".to_owned(),
+                "borrowck".to_owned(),
+                "--allow-extra-errors".to_owned(),
+            ],
+            ..SearchConfig::default()
+        };
+        ASTGuidedSearcher::new(Target::Path(test_dir.path()), config)
+            .search()
+            .unwrap();
+
+        let session = Session::new(test_dir.path()).named(Some("borrowck"));
+        assert_eq!(
+            Journal::open(&session)
+                .unwrap()
+                .read_copy(ARGUMENTS_COPY)
+                .unwrap()
+                .as_deref(),
+            Some("--session\0borrowck\0--allow-extra-errors")
+        );
+    }
+
+    #[test]
+    fn resume_fails_once_the_lockfile_changed() {
+        let test_dir = test_project("resume-lockfile");
+        let main = std::fs::read_to_string(test_dir.path().join("src/main.rs")).unwrap();
+        let journal = Journal::create(
+            &Session::new(test_dir.path()),
+            Path::new("src/main.rs"),
+            &main,
+        )
+        .unwrap();
+        let lockfile = std::fs::read_to_string(test_dir.path().join("Cargo.lock")).unwrap();
+        journal
+            .save_copy(LOCKFILE_COPY, lockfile.replace("0.0.0", "0.0.1").as_bytes())
+            .unwrap();
+
+        assert!(matches!(
+            resume(test_dir.path()),
+            Err(SearcherError::LockfileChangedSinceSession(changes)) if changes == "test_project 0.0.1 -> 0.0.0"
+        ));
+    }
+
+    #[test]
+    fn input_reduced_against_the_expected_output() {
+        let test_dir = test_project("input");
+        std::fs::write(
+            test_dir.path().join("src/main.rs"),
+            "fn main() {\n    let input = std::fs::read_to_string(\"input.txt\").unwrap();\n    \
+             if input.contains(\"crash\") {\n        panic!(\"crashed on input\");\n    }\n}\n",
+        )
+        .unwrap();
+        let input = "first line\nsecond crash line\nthird line\n";
+        std::fs::write(test_dir.path().join("input.txt"), input).unwrap();
+        let config = SearchConfig {
+            expected_output: Some(OutputPattern::Literal("crashed on input".to_owned())),
+            input_file: Some(PathBuf::from("input.txt")),
+            ..SearchConfig::default()
+        };
+        ASTGuidedSearcher::new(Target::Path(test_dir.path()), config)
+            .search()
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(test_dir.path().join("input.txt")).unwrap(),
+            "crash"
+        );
+        assert_eq!(
+            std::fs::read_to_string(
+                Session::new(test_dir.path())
+                    .backups_dir()
+                    .join(INPUT_BACKUP)
+            )
+            .unwrap(),
+            input
+        );
+    }
+
+    #[test]
+    fn first_error_found_in_integration_test() {
+        let test_dir = test_project("integration-test");
+        std::fs::write(test_dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::create_dir_all(test_dir.path().join("tests")).unwrap();
+        std::fs::write(
+            test_dir.path().join("tests/reassign.rs"),
+            "#[test]\nfn reassign() {\n    let a = 1;\n    let b = 2;\n    println!(\"{a}\");\n    \
+             a = 3;\n}\n",
+        )
+        .unwrap();
+
+        let minimized =
+            ASTGuidedSearcher::new(Target::Path(test_dir.path()), SearchConfig::default())
+                .search()
+                .unwrap()
+                .unwrap();
+
+        assert_eq!(minimized.source_file, Path::new("tests/reassign.rs"));
+        assert!(!minimized.code.contains("let b"));
+    }
+
+    #[test]
+    fn searcher_can_run_on_worker_threads() {
+        assert_send::<ASTGuidedSearcher<'_>>();
+        assert_send::<SearchConfig>();
+        assert_send::<CodeBuilder<'_>>();
+        assert_send::<CodeGenerator>();
+        assert_send::<Box<dyn Oracle>>();
+        assert_send::<Box<dyn Pass>>();
+        assert_send::<Result<Option<Minimized>, SearcherError>>();
+    }
+
+    #[test]
+    fn passes_try_the_site_after_an_accepted_alternative() {
+        let code = r#"
+trait Shape {}
+struct Square;
+impl Shape for Square {}
+fn first() -> impl Shape { Square }
+fn second() -> impl Shape { Square }"#;
+        let passes: Vec<Box<dyn Pass>> = vec![Box::new(ImplTraitPass::new(false))];
+
+        // Only `()` reproduces for `first`, only `Box<dyn Shape>` for `second`.
+        let rewritten = rewrite_with_passes(
+            &passes,
+            &Printer::default(),
+            code.to_owned(),
+            |variant, _| {
+                Ok(Some(
+                    !variant.contains("fn first() -> Box") && !variant.contains("fn second() {"),
+                ))
+            },
+        )
+        .unwrap();
+
+        assert!(rewritten.contains("fn first() {"));
+        assert!(rewritten.contains("fn second() -> Box<dyn Shape>"));
+    }
+
+    #[test]
+    fn passes_try_the_site_after_a_replaced_jump() {
+        let code = r#"
+fn first_even(values: &[u32]) -> u32 {
+    'outer: for value in values {
+        if value % 2 == 1 {
+            continue 'outer;
+        }
+        break 'outer;
+    }
+    0
+}"#;
+        let passes: Vec<Box<dyn Pass>> = vec![Box::new(ControlFlowPass)];
+
+        // The `continue` can only be replaced with `()`, the `break` can only lose its label.
+        let rewritten = rewrite_with_passes(
+            &passes,
+            &Printer::default(),
+            code.to_owned(),
+            |variant, _| {
+                Ok(Some(
+                    !variant.contains("continue;") && variant.contains("break"),
+                ))
+            },
+        )
+        .unwrap();
+
+        assert!(!rewritten.contains("continue"));
+        assert!(rewritten.contains("break;"));
+    }
+
+    #[test]
+    fn passes_do_not_undo_accepted_rewrites() {
+        let code = r#"
+trait Shape { fn area(&self) -> u32; }
+struct Square;
+impl Shape for Square { fn area(&self) -> u32 { 4 } }
+fn draw(s: Box<Square>) -> u32 {
+    let b = 0;
+    b = 10;
+    s.area()
+}"#;
+        let passes: Vec<Box<dyn Pass>> = vec![Box::new(DynTraitPass::new(false))];
+
+        // Both `Box<Square>` and `Box<dyn Shape>` keep the error.
+        let code = prettyplease::unparse(&syn::parse_file(code).unwrap());
+        let mut tested = 0;
+        let rewritten = rewrite_with_passes(&passes, &Printer::default(), code, |_, _| {
+            tested += 1;
+            Ok(Some(true))
+        })
+        .unwrap();
+
+        assert_eq!(tested, 1);
+        assert!(rewritten.contains("fn draw(s: Box<dyn Shape>)"));
+    }
+
+    #[test]
+    fn edited_lines_of_the_minimized_file() {
+        let minimized = "fn main() {\n    let b = 0;\n    let a = 0;\n    b = 10;\n}\n";
+        let edited = "fn main() {\n    let b = 0;\n    let renamed = 0;\n    b = 10;\n}\n";
+        assert_eq!(edited_lines(minimized, edited), vec![3..=3]);
+
+        // A deletion marks the lines around it.
+        let deleted = "fn main() {\n    let b = 0;\n    b = 10;\n}\n";
+        assert_eq!(edited_lines(minimized, deleted), vec![2..=3]);
+        assert!(edited_lines(minimized, minimized).is_empty());
+    }
+
+    #[test]
+    fn removed_percent_ignores_whitespace() {
+        let original = code_size("fn main() {\n    let a = 0;\n}\n");
+        assert_eq!(code_size("fn main() { let a = 0; }"), original);
+        assert_eq!(removed_percent(original, code_size("fn main() {}")), 41);
+        assert_eq!(removed_percent(original, original + 1), 0);
+        assert_eq!(removed_percent(0, 0), 0);
     }
 }
@@ -0,0 +1,352 @@
+//! The session directory, `.ddebug/` inside the target, holding everything ddebug-rs persists
+//! about the target:
+//!
+//! - `state/`: the journal of the last minimization, used to roll back and resume.
+//! - `backups/`: originals of the files modified besides the minimized one, such as the input of
+//!   the program, and of the files pruned by `ddebug prune`.
+//! - `reports/`: reports of the last minimization.
+//! - `logs/`: verdicts of the variants tested by the last minimization.
+//! - `required/`: the nodes previous minimizations found required, per file and error.
+//! - `metrics.csv`: progress snapshots of the last minimization, if asked for with `--metrics`.
+//! - `lock`: held by the running minimization, so that two of them do not modify the target at
+//!   the same time. It contains the pid of the minimization, and is taken over if that process
+//!   is not running anymore.
+//! - `stop`: asks the running minimization to stop, written by `ddebug stop`.
+//! - `sessions/<name>/`: the `state/`, `backups/`, `reports/`, `logs/` and `metrics.csv` of a
+//!   session named with `--session`, so that several minimizations of the target, of different
+//!   errors or with different oracles, keep their own. The other entries are shared by all the
+//!   sessions.
+//!
+//! The directory ignores itself for git, and `ddebug clean` removes it.
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+/// Directory of the session, relative to the target.
+pub const SESSION_DIR: &str = ".ddebug";
+
+const STATE_DIR: &str = "state";
+const BACKUPS_DIR: &str = "backups";
+const REPORTS_DIR: &str = "reports";
+const LOGS_DIR: &str = "logs";
+const REQUIRED_DIR: &str = "required";
+const SESSIONS_DIR: &str = "sessions";
+const LOCK_FILE: &str = "lock";
+const STOP_FILE: &str = "stop";
+/// Log of the variants tested by the last minimization.
+const LOG_FILE: &str = "minimize.log";
+const METRICS_FILE: &str = "metrics.csv";
+
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error("IO error emitted from session: {0}")]
+    IOError(std::io::Error),
+    #[error("Another minimization (pid {1}) holds the lock at {0}, remove only that file if it is not running anymore")]
+    Locked(PathBuf, String),
+    #[error("No session named {0}, run `ddebug sessions list` to list them")]
+    UnknownSession(String),
+}
+
+impl From<std::io::Error> for SessionError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IOError(value)
+    }
+}
+
+pub struct Session {
+    target: PathBuf,
+    dir: PathBuf,
+    /// Directory of the state, backups, reports and logs of the session, the session directory
+    /// itself unless the session is named.
+    root: PathBuf,
+}
+
+impl Session {
+    pub fn new(target: &Path) -> Self {
+        let dir = target.join(SESSION_DIR);
+        Self {
+            target: target.to_path_buf(),
+            root: dir.clone(),
+            dir,
+        }
+    }
+
+    /// Returns the named session of the target, the unnamed one if `name` is `None`.
+    pub fn named(self, name: Option<&str>) -> Self {
+        let root = match name {
+            Some(name) => self.dir.join(SESSIONS_DIR).join(name),
+            None => self.dir.clone(),
+        };
+        Self { root, ..self }
+    }
+
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn state_dir(&self) -> PathBuf {
+        self.root.join(STATE_DIR)
+    }
+
+    pub fn backups_dir(&self) -> PathBuf {
+        self.root.join(BACKUPS_DIR)
+    }
+
+    pub fn reports_dir(&self) -> PathBuf {
+        self.root.join(REPORTS_DIR)
+    }
+
+    pub fn required_dir(&self) -> PathBuf {
+        self.dir.join(REQUIRED_DIR)
+    }
+
+    /// Creates the subdirectory of the session, and the session directory itself if needed.
+    pub fn create_dir(&self, subdir: &Path) -> Result<(), std::io::Error> {
+        if !self.dir.exists() {
+            std::fs::create_dir_all(&self.dir)?;
+            std::fs::write(self.dir.join(".gitignore"), "*\n")?;
+        }
+        std::fs::create_dir_all(subdir)
+    }
+
+    /// Takes the lock of the session, released once the returned guard is dropped. A lock left
+    /// by a minimization that is not running anymore is taken over.
+    pub fn lock(&self) -> Result<SessionLock, SessionError> {
+        self.create_dir(&self.dir)?;
+        let path = self.dir.join(LOCK_FILE);
+        loop {
+            match std::fs::File::create_new(&path) {
+                Ok(mut file) => {
+                    writeln!(file, "{}", std::process::id())?;
+                    return Ok(SessionLock { path });
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if let Some(pid) = self.lock_holder() {
+                        return Err(SessionError::Locked(path, pid));
+                    }
+                    // The minimization holding the lock died without releasing it.
+                    match std::fs::remove_file(&path) {
+                        Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+                            return Err(error.into())
+                        }
+                        _ => {}
+                    }
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    /// Returns the pid written in the lock of the session if its process is still running, or if
+    /// it cannot be told otherwise, e.g. as the pid is not written yet.
+    fn lock_holder(&self) -> Option<String> {
+        let pid = std::fs::read_to_string(self.dir.join(LOCK_FILE)).ok()?;
+        let pid = pid.trim().to_owned();
+        match pid.parse() {
+            Ok(pid) if !is_running(pid) => None,
+            _ => Some(pid),
+        }
+    }
+
+    /// Asks the minimization running on the target to stop, returning false if none is running.
+    pub fn request_stop(&self) -> Result<bool, std::io::Error> {
+        if self.lock_holder().is_none() {
+            return Ok(false);
+        }
+        std::fs::write(self.dir.join(STOP_FILE), "")?;
+        Ok(true)
+    }
+
+    /// Returns true if the running minimization was asked to stop, consuming the request.
+    pub fn take_stop_request(&self) -> bool {
+        std::fs::remove_file(self.dir.join(STOP_FILE)).is_ok()
+    }
+
+    /// Saves the original of a file modified by the minimization, returning the path of the
+    /// backup.
+    pub fn backup(&self, name: &str, contents: &[u8]) -> Result<PathBuf, std::io::Error> {
+        self.create_dir(&self.backups_dir())?;
+        let path = self.backups_dir().join(name);
+        std::fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    /// Opens the log of the minimization, discarding the previous one unless `append` is set.
+    pub fn open_log(&self, append: bool) -> Result<std::fs::File, std::io::Error> {
+        let logs_dir = self.root.join(LOGS_DIR);
+        self.create_dir(&logs_dir)?;
+        std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(logs_dir.join(LOG_FILE))
+    }
+
+    /// Opens the metrics file of the minimization, discarding the previous one unless `append` is
+    /// set.
+    pub fn open_metrics(&self, append: bool) -> Result<std::fs::File, std::io::Error> {
+        self.create_dir(&self.root)?;
+        std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(self.root.join(METRICS_FILE))
+    }
+
+    /// Removes the session directory, returning false if there was none.
+    pub fn clean(&self) -> Result<bool, std::io::Error> {
+        if !self.dir.exists() {
+            return Ok(false);
+        }
+        std::fs::remove_dir_all(&self.dir)?;
+        Ok(true)
+    }
+
+    /// Returns the names of the named sessions of the target, sorted.
+    pub fn names(&self) -> Result<Vec<String>, std::io::Error> {
+        let Ok(entries) = std::fs::read_dir(self.dir.join(SESSIONS_DIR)) else {
+            return Ok(vec![]);
+        };
+        let mut names = vec![];
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Removes the directory of the session named `name`, the entries shared by the sessions are
+    /// kept.
+    pub fn delete(&self, name: &str) -> Result<(), SessionError> {
+        let dir = self.dir.join(SESSIONS_DIR).join(name);
+        if !dir.is_dir() {
+            return Err(SessionError::UnknownSession(name.to_owned()));
+        }
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
+
+/// Parses the name of a session, which names its directory and so is restricted to ASCII
+/// letters, digits, `-`, `_` and `.`.
+pub fn parse_session_name(name: &str) -> Result<String, String> {
+    let valid = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if name.is_empty() || name.starts_with('.') || !valid {
+        return Err(format!(
+            "invalid session name `{name}`, expected letters, digits, `-`, `_` and `.` not leading"
+        ));
+    }
+    Ok(name.to_owned())
+}
+
+/// Returns false if no process has the pid, true if one has or if it cannot be told.
+fn is_running(pid: u32) -> bool {
+    let procfs = Path::new("/proc/self");
+    if procfs.exists() {
+        return procfs.with_file_name(pid.to_string()).exists();
+    }
+    #[cfg(unix)]
+    let mut query = {
+        let mut kill = std::process::Command::new("kill");
+        kill.args(["-0", &pid.to_string()]);
+        kill
+    };
+    #[cfg(not(unix))]
+    let mut query = {
+        let mut tasklist = std::process::Command::new("tasklist");
+        tasklist.args(["/NH", "/FO", "CSV", "/FI", &format!("PID eq {pid}")]);
+        tasklist
+    };
+    match query.stderr(std::process::Stdio::null()).output() {
+        #[cfg(unix)]
+        Ok(output) => output.status.success(),
+        #[cfg(not(unix))]
+        Ok(output) => String::from_utf8_lossy(&output.stdout).contains(&format!("\"{pid}\"")),
+        Err(_) => true,
+    }
+}
+
+/// The lock of a session, removed on drop.
+pub struct SessionLock {
+    path: PathBuf,
+}
+
+impl Drop for SessionLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_session_name, Session, SessionError};
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn session_lock_and_clean() {
+        let test_dir = TestDir::new("session");
+        let target = test_dir.path();
+        let session = Session::new(target);
+
+        let lock = session.lock().unwrap();
+        assert!(matches!(session.lock(), Err(SessionError::Locked(..))));
+        drop(lock);
+        assert!(session.lock().is_ok());
+        assert!(session.dir().join(".gitignore").exists());
+
+        // A lock whose minimization is not running anymore is taken over.
+        std::fs::write(session.dir().join("lock"), "999999999\n").unwrap();
+        assert!(!session.request_stop().unwrap());
+        let lock = session.lock().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(session.dir().join("lock")).unwrap(),
+            format!("{}\n", std::process::id())
+        );
+        drop(lock);
+
+        assert!(session.clean().unwrap());
+        assert!(!session.clean().unwrap());
+    }
+
+    #[test]
+    fn named_sessions_listed_and_deleted() {
+        let test_dir = TestDir::new("sessions");
+        let target = test_dir.path();
+        let sessions = Session::new(target);
+        assert!(sessions.names().unwrap().is_empty());
+
+        for name in ["unsized", "borrowck"] {
+            let session = Session::new(target).named(Some(name));
+            session.create_dir(&session.state_dir()).unwrap();
+            assert!(session
+                .state_dir()
+                .starts_with(sessions.dir().join("sessions")));
+        }
+        assert_eq!(sessions.names().unwrap(), vec!["borrowck", "unsized"]);
+
+        sessions.delete("unsized").unwrap();
+        assert_eq!(sessions.names().unwrap(), vec!["borrowck"]);
+        assert!(matches!(
+            sessions.delete("unsized"),
+            Err(SessionError::UnknownSession(_))
+        ));
+
+        assert!(parse_session_name("issue-42_v2.1").is_ok());
+        assert!(parse_session_name("../other").is_err());
+        assert!(parse_session_name(".hidden").is_err());
+    }
+}
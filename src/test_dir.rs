@@ -0,0 +1,28 @@
+//! Temporary directories of the tests.
+use std::path::{Path, PathBuf};
+
+/// A directory of a test in the temporary directory, removed on drop even if the test panics.
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// Creates the empty directory `ddebug-<name>-<pid>`, removing what a previous run with the
+    /// same pid may have left in it.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("ddebug-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,79 @@
+//! Snapshot of the toolchain building the target.
+//!
+//! Verdicts of the oracle only hold for the compiler that produced them, so the versions are
+//! recorded with every session and shown in the reports.
+use std::{path::Path, process::Command};
+
+/// Versions reported by `rustc -Vv` and `cargo -V` inside the target, so that toolchain
+/// overrides of the target are taken into account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toolchain {
+    pub rustc: String,
+    pub cargo: String,
+}
+
+impl Toolchain {
+    pub fn capture(target: &Path) -> Result<Self, std::io::Error> {
+        Ok(Self {
+            rustc: version_output(target, "rustc", "-Vv")?,
+            cargo: version_output(target, "cargo", "-V")?,
+        })
+    }
+
+    /// First line of `rustc -Vv`, e.g. `rustc 1.76.0 (07dca489a 2024-02-04)`.
+    pub fn rustc_version(&self) -> &str {
+        self.rustc.lines().next().unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for Toolchain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.rustc.trim_end())?;
+        writeln!(f, "{}", self.cargo.trim_end())
+    }
+}
+
+impl std::str::FromStr for Toolchain {
+    type Err = std::convert::Infallible;
+
+    /// Parses the snapshot written by `Display`, the last line being the cargo version.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.trim_end().lines().collect::<Vec<_>>();
+        let (cargo, rustc) = lines.split_last().unwrap_or((&"", &[]));
+        Ok(Self {
+            rustc: format!("{}\n", rustc.join("\n")),
+            cargo: format!("{cargo}\n"),
+        })
+    }
+}
+
+fn version_output(target: &Path, program: &str, arg: &str) -> Result<String, std::io::Error> {
+    let output = Command::new(program)
+        .current_dir(target)
+        .arg(arg)
+        .output()?;
+    String::from_utf8(output.stdout)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Toolchain;
+
+    #[test]
+    fn toolchain_snapshot_roundtrip() {
+        let toolchain = Toolchain {
+            rustc: "rustc 1.76.0 (07dca489a 2024-02-04)\nbinary: rustc\nhost: x86_64-unknown-linux-gnu\n"
+                .to_owned(),
+            cargo: "cargo 1.76.0 (c84b36747 2024-01-18)\n".to_owned(),
+        };
+
+        let parsed = toolchain.to_string().parse::<Toolchain>().unwrap();
+
+        assert_eq!(parsed, toolchain);
+        assert_eq!(
+            parsed.rustc_version(),
+            "rustc 1.76.0 (07dca489a 2024-02-04)"
+        );
+    }
+}
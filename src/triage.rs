@@ -0,0 +1,222 @@
+//! Quick triage of every error of the target.
+//!
+//! For each error of the initial build, the top-level items of the file it points to are removed
+//! in batches with ddmin under a small build budget, keeping the items without which the error
+//! disappears. The kept items form a rough suspect region for the error. Errors are triaged
+//! concurrently, each one inside its own scratch copy of the target.
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::{
+    builder::{BuildError, CodeBuilder, CodeBuilderError},
+    ddmin::ddmin,
+    parser::{AbstractSyntaxTree, ItemSummary},
+    paths::portable,
+    toolchain::Toolchain,
+    workspace::ScratchWorkspace,
+};
+
+/// Default number of builds spent on each error.
+pub const DEFAULT_TRIAGE_BUDGET: usize = 16;
+
+#[derive(Error, Debug)]
+pub enum TriageError {
+    #[error("Error while trying to build code variant: {0}")]
+    BuildOperationError(CodeBuilderError),
+    #[error("IO error emitted from triage: {0}")]
+    IOError(std::io::Error),
+}
+
+impl From<CodeBuilderError> for TriageError {
+    fn from(value: CodeBuilderError) -> Self {
+        Self::BuildOperationError(value)
+    }
+}
+
+impl From<std::io::Error> for TriageError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IOError(value)
+    }
+}
+
+/// The items of a file an error could not be separated from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuspectRegion {
+    pub error: BuildError,
+    pub source_file: PathBuf,
+    pub suspects: Vec<ItemSummary>,
+    /// Number of top-level items in the file before the reduction.
+    pub item_count: usize,
+    pub builds: usize,
+    pub budget_exhausted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriageReport {
+    pub toolchain: Toolchain,
+    /// Number of errors cargo reported for the initial build.
+    pub reported_error_count: Option<usize>,
+    pub regions: Vec<SuspectRegion>,
+    /// Errors that do not point to a source file and could not be triaged.
+    pub unlocated_errors: Vec<BuildError>,
+}
+
+pub struct Triage<'a> {
+    target: &'a Path,
+    budget: usize,
+    jobs: usize,
+}
+
+impl<'a> Triage<'a> {
+    /// Creates a triage of `target` spending at most `budget` builds per error, triaging `jobs`
+    /// errors at a time.
+    pub fn new(target: &'a Path, budget: usize, jobs: usize) -> Self {
+        Self {
+            target,
+            budget,
+            jobs: jobs.max(1),
+        }
+    }
+
+    pub fn run(&self) -> Result<TriageReport, TriageError> {
+        let toolchain = Toolchain::capture(self.target)?;
+        let build_errors = CodeBuilder::Path(self.target).collect_errors()?;
+        let reported_error_count = build_errors.reported_error_count();
+        let (located_errors, unlocated_errors): (Vec<_>, Vec<_>) = build_errors
+            .errors
+            .into_iter()
+            .partition(|error| error.source_file.is_some());
+
+        let mut regions = vec![];
+        for (chunk_ix, chunk) in located_errors.chunks(self.jobs).enumerate() {
+            let chunk_regions = std::thread::scope(|scope| {
+                let handles = chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, error)| {
+                        let workspace_name = format!("triage-{}", chunk_ix * self.jobs + ix);
+                        scope.spawn(move || self.triage_error(error, &workspace_name))
+                    })
+                    .collect::<Vec<_>>();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("triage thread panicked"))
+                    .collect::<Vec<_>>()
+            });
+            for region in chunk_regions {
+                regions.push(region?);
+            }
+        }
+
+        Ok(TriageReport {
+            toolchain,
+            reported_error_count,
+            regions,
+            unlocated_errors,
+        })
+    }
+
+    /// Runs the item-level batch removal for a single error in its own scratch workspace.
+    fn triage_error(
+        &self,
+        error: &BuildError,
+        workspace_name: &str,
+    ) -> Result<SuspectRegion, TriageError> {
+        let source_file = error
+            .source_file
+            .clone()
+            .expect("only located errors are triaged");
+        let workspace = ScratchWorkspace::copy_of(self.target, workspace_name)?;
+        let file_path = workspace.path().join(&source_file);
+        let ast = AbstractSyntaxTree::parse(std::fs::read_to_string(&file_path)?);
+        let code_builder = CodeBuilder::Path(workspace.path());
+
+        let item_indices = (0..ast.items.len()).collect::<Vec<_>>();
+        let result = ddmin(&item_indices, Some(self.budget), |kept| {
+            let file = syn::File {
+                shebang: None,
+                attrs: ast.attributes.clone(),
+                items: kept.iter().map(|ix| ast.items[*ix].clone()).collect(),
+            };
+            std::fs::write(&file_path, prettyplease::unparse(&file))?;
+            let variant_errors = code_builder.collect_errors()?;
+            Ok::<_, TriageError>(
+                variant_errors
+                    .errors
+                    .iter()
+                    .any(|variant_error| variant_error.is_same_error(error)),
+            )
+        })?;
+
+        Ok(SuspectRegion {
+            error: error.clone(),
+            source_file,
+            suspects: result
+                .kept
+                .iter()
+                .map(|ix| ItemSummary::from(&ast.items[*ix]))
+                .collect(),
+            item_count: ast.items.len(),
+            builds: result.tests,
+            budget_exhausted: result.budget_exhausted,
+        })
+    }
+}
+
+impl std::fmt::Display for TriageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.toolchain.rustc_version())?;
+        if let Some(reported_error_count) = self.reported_error_count {
+            writeln!(f, "cargo reported {reported_error_count} error(s)")?;
+        }
+        for region in &self.regions {
+            writeln!(f, "{}", region.error.error_src)?;
+            writeln!(f, "  --> {}", portable(&region.source_file))?;
+            let exhausted = if region.budget_exhausted {
+                " (budget exhausted)"
+            } else {
+                ""
+            };
+            writeln!(
+                f,
+                "  {} of {} items kept after {} builds{exhausted}:",
+                region.suspects.len(),
+                region.item_count,
+                region.builds
+            )?;
+            for suspect in &region.suspects {
+                writeln!(f, "    {suspect}")?;
+            }
+        }
+        for error in &self.unlocated_errors {
+            writeln!(f, "{}", error.error_src)?;
+            writeln!(f, "  no source location, not triaged")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::Triage;
+
+    #[test]
+    fn triage_test_project() {
+        let project_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test")
+            .join("data")
+            .join("test_project");
+
+        let report = Triage::new(&project_dir, 4, 1).run().unwrap();
+
+        assert_eq!(report.regions.len(), 1);
+        assert!(report.unlocated_errors.is_empty());
+        let region = &report.regions[0];
+        assert_eq!(region.error.error_code.as_deref(), Some("E0384"));
+        assert_eq!(region.item_count, 1);
+        assert_eq!(region.suspects[0].description, "fn main");
+    }
+}
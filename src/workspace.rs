@@ -0,0 +1,223 @@
+//! Scratch copies of the target project, so that variants can be built without touching the
+//! user's files and several of them can be built at the same time.
+//!
+//! The whole workspace of the target is copied, so that the members inheriting from it and the
+//! path dependencies inside it still resolve, and the relative paths of the manifests leading out
+//! of it are made absolute. The copies are built into directories of their own under the target
+//! directory of the workspace, kept from one run to the next so that the dependencies are only
+//! built once.
+use std::path::{Component, Path, PathBuf};
+
+use crate::{manifest::workspace_root, session::SESSION_DIR};
+
+/// Directories that are never copied into a scratch workspace.
+const SKIPPED_DIRS: &[&str] = &["target", SESSION_DIR, ".git"];
+
+/// Directory of the build directories of the scratch workspaces, inside the target directory of
+/// the workspace.
+const SCRATCH_TARGET_DIR: &str = "ddebug";
+
+/// A copy of the target project in the temporary directory, removed on drop.
+pub struct ScratchWorkspace {
+    /// Scratch directory holding the copy of the workspace and its cargo configuration.
+    dir: PathBuf,
+    /// Copy of the target inside the copy of the workspace.
+    path: PathBuf,
+}
+
+impl ScratchWorkspace {
+    /// Copies the workspace of `target` into a new scratch directory, `name` distinguishes
+    /// workspaces created by the same process.
+    pub fn copy_of(target: &Path, name: &str) -> Result<Self, std::io::Error> {
+        let target = std::path::absolute(target)?;
+        let root = workspace_root(&target).unwrap_or(&target);
+        let dir = std::env::temp_dir().join(format!("ddebug-{}-{name}", std::process::id()));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        // The copy is nested in the scratch directory so that the cargo configuration of the
+        // workspace, if it has one, takes precedence over the one setting the build directory.
+        let root_copy = dir.join(root.file_name().unwrap_or("workspace".as_ref()));
+        copy_dir(root, root, &root_copy)?;
+        let target_dir = root.join("target").join(SCRATCH_TARGET_DIR).join(name);
+        std::fs::create_dir_all(dir.join(".cargo"))?;
+        std::fs::write(
+            dir.join(".cargo").join("config.toml"),
+            format!("[build]\ntarget-dir = \"{}\"\n", toml_path(&target_dir)),
+        )?;
+        let path = root_copy.join(target.strip_prefix(root).unwrap_or(Path::new("")));
+        Ok(Self { dir, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ScratchWorkspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Copies the directory `from` of the workspace `root` into `to`.
+fn copy_dir(root: &Path, from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let destination = to.join(&file_name);
+        if entry.file_type()?.is_dir() {
+            if SKIPPED_DIRS.iter().any(|skipped| file_name == *skipped) {
+                continue;
+            }
+            copy_dir(root, &entry.path(), &destination)?;
+        } else if file_name == "Cargo.toml" {
+            let manifest = std::fs::read_to_string(entry.path())?;
+            std::fs::write(
+                destination,
+                outer_paths_made_absolute(&manifest, from, root),
+            )?;
+        } else {
+            std::fs::copy(entry.path(), destination)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the manifest of the directory `dir` with its relative `path` values leading out of
+/// the workspace `root`, such as the ones of the path dependencies, made absolute.
+fn outer_paths_made_absolute(manifest: &str, dir: &Path, root: &Path) -> String {
+    let mut rewritten = String::new();
+    let mut rest = manifest;
+    while let Some(start) = rest.find("path") {
+        let (before, after) = rest.split_at(start + "path".len());
+        rewritten.push_str(before);
+        rest = after;
+        let is_key = !before[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || "_-".contains(c));
+        let Some(value) = rest
+            .trim_start_matches([' ', '\t'])
+            .strip_prefix('=')
+            .map(|value| value.trim_start_matches([' ', '\t']))
+            .and_then(|value| value.strip_prefix('"'))
+            .filter(|_| is_key)
+        else {
+            continue;
+        };
+        let Some(end) = value.find('"') else {
+            continue;
+        };
+        let path = Path::new(&value[..end]);
+        let resolved = normalized(&dir.join(path));
+        if path.is_relative() && !resolved.starts_with(root) {
+            rewritten.push_str(&rest[..rest.len() - value.len()]);
+            rewritten.push_str(&toml_path(&resolved));
+            rest = &value[end..];
+        }
+    }
+    rewritten.push_str(rest);
+    rewritten
+}
+
+/// Resolves the `.` and `..` components of the absolute path without touching the file system.
+fn normalized(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Returns the path as the content of a TOML string, with forward slashes so that nothing has to
+/// be escaped.
+fn toml_path(path: &Path) -> String {
+    path.display().to_string().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{outer_paths_made_absolute, ScratchWorkspace};
+    use crate::{builder::CodeBuilder, test_dir::TestDir};
+
+    #[test]
+    fn scratch_workspace_copies_sources_only() {
+        let project_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test")
+            .join("data")
+            .join("test_project");
+
+        let workspace = ScratchWorkspace::copy_of(&project_dir, "workspace-test").unwrap();
+        let path = workspace.path().to_path_buf();
+
+        assert!(path.join("src").join("main.rs").exists());
+        assert!(path.join("Cargo.toml").exists());
+        assert!(!path.join("target").exists());
+
+        drop(workspace);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn outer_paths_of_manifest_made_absolute() {
+        let manifest = "[lib]\npath = \"src/lib.rs\"\n\n[dependencies]\n\
+                        shapes = { path = \"../shapes\" }\nlog = { version = \"0.4\" }\n";
+        assert_eq!(
+            outer_paths_made_absolute(manifest, Path::new("/ws/app"), Path::new("/ws/app")),
+            "[lib]\npath = \"src/lib.rs\"\n\n[dependencies]\n\
+             shapes = { path = \"/ws/shapes\" }\nlog = { version = \"0.4\" }\n"
+        );
+        // Inside the workspace the paths lead to the copies.
+        assert_eq!(
+            outer_paths_made_absolute(manifest, Path::new("/ws/app"), Path::new("/ws")),
+            manifest
+        );
+    }
+
+    #[test]
+    fn scratch_workspace_of_member_with_path_dependency() {
+        let test_dir = TestDir::new("scratch-member");
+        let dir = test_dir.path();
+        std::fs::create_dir_all(dir.join("shapes/src")).unwrap();
+        std::fs::write(
+            dir.join("shapes/Cargo.toml"),
+            "[package]\nname = \"shapes\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("shapes/src/lib.rs"), "pub struct Square;\n").unwrap();
+        std::fs::create_dir_all(dir.join("ws/app/src")).unwrap();
+        std::fs::write(
+            dir.join("ws/Cargo.toml"),
+            "[workspace]\nmembers = [\"app\"]\n\n[workspace.package]\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("ws/app/Cargo.toml"),
+            "[package]\nname = \"app\"\nversion.workspace = true\nedition = \"2021\"\n\n\
+             [dependencies]\nshapes = { path = \"../../shapes\" }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("ws/app/src/main.rs"),
+            "fn main() {\n    let _ = shapes::Square;\n}\n",
+        )
+        .unwrap();
+
+        let workspace = ScratchWorkspace::copy_of(&dir.join("ws/app"), "scratch-member").unwrap();
+
+        assert!(workspace.path().ends_with("ws/app"));
+        let build_output = CodeBuilder::Path(workspace.path()).build().unwrap();
+        assert!(build_output.success, "{}", build_output.stderr);
+        assert!(dir.join("ws/target/ddebug/scratch-member").is_dir());
+    }
+}